@@ -1,6 +1,11 @@
 use std::collections::{BTreeMap, VecDeque};
+use std::error::Error;
+use std::ops::Range;
 use std::time::Duration;
 use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use serde_with::DurationMilliSeconds;
+use simulation_runner_rs::topology::SimulatedReconnects;
 use crate::rest_node_relocation::{ISQPEvent, TopologyUpdate};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -15,12 +20,47 @@ pub struct MobileDeviceQuadrants {
     quadrant_map: BTreeMap<u64, VecDeque<MobileEntry>>,
 }
 
+//quadrant and device ids are worker ids, the devices are the mobile workers of the experiment
+#[serde_as]
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct QuadrantConfig {
     pub num_quadrants: usize,
     pub devices_per_quadrant: usize,
     pub quadrant_start_id: u64,
     pub mobile_start_id: u64,
+    //every interval one device of each quadrant moves on to the next quadrant
+    #[serde_as(as = "DurationMilliSeconds<u64>")]
+    pub rotation_interval: Duration,
+    //logical sources placed on every device in addition to place_default_sources_on_node_ids_path
+    #[serde(default)]
+    pub device_sources: Vec<u64>,
+}
+
+impl QuadrantConfig {
+    pub fn device_ids(&self) -> Range<u64> {
+        self.mobile_start_id..self.mobile_start_id + (self.num_quadrants * self.devices_per_quadrant) as u64
+    }
+
+    //the runners create the mobile workers with consecutive ids after the fixed workers, the devices have to match them
+    pub fn get_simulated_reconnects(&self, runtime: Duration, first_mobile_worker_id: u64) -> Result<SimulatedReconnects, Box<dyn Error>> {
+        if self.num_quadrants == 0 {
+            return Err("quadrant config without quadrants".into());
+        }
+        if self.rotation_interval.is_zero() {
+            return Err("quadrant rotation interval must be positive".into());
+        }
+        if self.mobile_start_id != first_mobile_worker_id {
+            return Err(format!("mobile_start_id is {} but the first mobile worker has the id {}", self.mobile_start_id, first_mobile_worker_id).into());
+        }
+        if self.quadrant_start_id == 0 || self.quadrant_start_id + self.num_quadrants as u64 > first_mobile_worker_id {
+            return Err("quadrants have to be fixed workers".into());
+        }
+        let quadrants = MobileDeviceQuadrants::from(self.clone());
+        Ok(SimulatedReconnects {
+            initial_parents: quadrants.get_initial_update(),
+            topology_updates: quadrants.get_update_vector(runtime, self.rotation_interval),
+        })
+    }
 }
 
 impl From<QuadrantConfig> for MobileDeviceQuadrants {
@@ -212,6 +252,27 @@ mod tests {
         println!("{}", json);
     }
 
+    #[test]
+    fn test_simulated_reconnects() {
+        let config = super::QuadrantConfig {
+            num_quadrants: 2,
+            devices_per_quadrant: 2,
+            quadrant_start_id: 2,
+            mobile_start_id: 10,
+            rotation_interval: std::time::Duration::from_secs(2),
+            device_sources: vec![1],
+        };
+        assert!(config.get_simulated_reconnects(std::time::Duration::from_secs(6), 11).is_err());
+        let reconnects = config.get_simulated_reconnects(std::time::Duration::from_secs(6), 10).unwrap();
+        assert_eq!(reconnects.initial_parents, vec![(2, 10), (2, 11), (3, 12), (3, 13)]);
+        let timestamps = reconnects.topology_updates.iter().map(|update| update.timestamp.as_secs()).collect::<Vec<_>>();
+        assert_eq!(timestamps, vec![0, 2, 4]);
+        //the first device of every quadrant moves on to the next one
+        let moves = reconnects.topology_updates[0].events.iter().map(|event| (event.parent_id, event.child_id)).collect::<Vec<_>>();
+        assert_eq!(moves, vec![(3, 12), (2, 12), (2, 10), (3, 10)]);
+        assert_eq!(config.device_ids(), 10..14);
+    }
+
     #[test]
     fn test_time() {
         let now = SystemTime::now();
@@ -1,17 +1,468 @@
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::Duration;
 
-const PAPERMILL_PATH: &str = "/home/x/.local/bin/papermill";
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::record::Field;
+use serde::{Deserialize, Serialize};
 
+use simulation_runner_rs::result_reader::{open_result_file, ResultFile, ResultTuple};
+use crate::tuple_schema::{JoinOutputTuple, SinkTuple, StatelessOutputTuple};
+
+//papermill is looked up on the PATH unless this environment variable points to a specific executable
+const PAPERMILL_PATH_VARIABLE: &str = "PAPERMILL_PATH";
+const DEFAULT_PAPERMILL: &str = "papermill";
+
+//suffixes of the files written next to the result file of an attempt
+pub const ANALYSIS_SUFFIX: &str = "analysis.json";
+pub const THROUGHPUT_SUFFIX: &str = "analysis.csv";
+
+const AVRO_MAGIC: &[u8] = b"Obj\x01";
+const PARQUET_MAGIC: &[u8] = b"PAR1";
+
+//render the analysis notebook with papermill. this is optional, the native analysis does not depend on it
 pub fn create_notebook(input_file: &Path, notebook_input_path: &Path, notebook_output_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let papermill = std::env::var(PAPERMILL_PATH_VARIABLE).unwrap_or_else(|_| DEFAULT_PAPERMILL.to_string());
     println!("Creating {} using template: {} and input data: {}", notebook_output_path.display(), notebook_input_path.display(), input_file.display());
-    let mut papermill_process = Command::new(PAPERMILL_PATH)
+    let mut papermill_process = Command::new(&papermill)
         .arg("-p")
         .arg("output_data_path")
         .arg(input_file.to_str().unwrap())
         .arg(notebook_input_path)
         .arg(notebook_output_path)
-        .spawn()?;
+        .spawn()
+        .map_err(|e| format!("could not start papermill ({}): {}", papermill, e))?;
     papermill_process.wait()?;
     Ok(())
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum TimeUnit {
+    NANOSECONDS,
+    MICROSECONDS,
+    MILLISECONDS,
+}
+
+impl TimeUnit {
+    fn to_nanos(self, value: u64) -> u64 {
+        match self {
+            TimeUnit::NANOSECONDS => value,
+            TimeUnit::MICROSECONDS => value * 1_000,
+            TimeUnit::MILLISECONDS => value * 1_000_000,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AnalysisOptions {
+    pub bin_width: Duration,
+    //pauses in the output that are longer than this are reported as gaps
+    pub gap_threshold: Duration,
+    pub event_time_unit: TimeUnit,
+    pub emission_time_unit: TimeUnit,
+}
+
+impl Default for AnalysisOptions {
+    fn default() -> Self {
+        Self {
+            bin_width: Duration::from_secs(1),
+            gap_threshold: Duration::from_secs(1),
+            event_time_unit: TimeUnit::NANOSECONDS,
+            emission_time_unit: TimeUnit::NANOSECONDS,
+        }
+    }
+}
+
+//the latency and output time of a single result tuple, both in nanoseconds
+#[derive(Debug, Clone, Copy)]
+pub struct TupleTiming {
+    pub emission_time: u64,
+    pub latency: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LatencySummary {
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+    pub mean_ms: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ThroughputBin {
+    //start of the bin in milliseconds after the first emitted tuple
+    pub start_ms: u64,
+    pub tuples: u64,
+    pub tuples_per_second: f64,
+    pub mean_latency_ms: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Gap {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AnalysisSummary {
+    pub input_file: PathBuf,
+    pub workload: String,
+    pub tuple_count: usize,
+    pub first_emission_ns: Option<u64>,
+    pub last_emission_ns: Option<u64>,
+    pub latency: Option<LatencySummary>,
+    pub mean_throughput: f64,
+    pub peak_throughput: f64,
+    pub gaps: Vec<Gap>,
+    pub throughput: Vec<ThroughputBin>,
+}
+
+//rows of a result file with the field names from its header or schema
+pub struct OutputRows {
+    pub field_names: Vec<String>,
+    pub rows: Vec<Vec<u64>>,
+}
+
+//read a result file written by one of the output writers. the writers always use the .csv file name, so the format is
+//detected from the magic bytes at the start of the file
+pub fn read_output_rows(path: &Path) -> Result<OutputRows, Box<dyn Error>> {
+    let mut magic = [0u8; 4];
+    let read = File::open(path)?.read(&mut magic)?;
+    let magic = &magic[..read];
+    if magic == AVRO_MAGIC {
+        read_avro_rows(path)
+    } else if magic == PARQUET_MAGIC {
+        read_parquet_rows(path)
+    } else {
+        read_csv_rows(path)
+    }
+}
+
+fn read_avro_rows(path: &Path) -> Result<OutputRows, Box<dyn Error>> {
+    fn collect_rows<T: ResultTuple>(tuples: impl Iterator<Item = Result<T, Box<dyn Error>>>) -> Result<OutputRows, Box<dyn Error>> {
+        Ok(OutputRows {
+            field_names: T::FIELD_NAMES.iter().map(|name| name.to_string()).collect(),
+            rows: tuples.map(|tuple| tuple.map(|tuple| tuple.to_values())).collect::<Result<Vec<_>, _>>()?,
+        })
+    }
+    match open_result_file(path)? {
+        ResultFile::Stateless(reader) => collect_rows(reader),
+        ResultFile::Join(reader) => collect_rows(reader),
+    }
+}
+
+fn read_parquet_rows(path: &Path) -> Result<OutputRows, Box<dyn Error>> {
+    let reader = SerializedFileReader::new(File::open(path)?)?;
+    let field_names = reader
+        .metadata()
+        .file_metadata()
+        .schema_descr()
+        .columns()
+        .iter()
+        .map(|column| column.name().to_string())
+        .collect();
+    let mut rows = vec![];
+    for row in reader.get_row_iter(None)? {
+        let row = row?
+            .get_column_iter()
+            .map(|(name, field)| match field {
+                Field::ULong(v) => Ok(*v),
+                Field::Long(v) => Ok(*v as u64),
+                _ => Err(format!("unsupported parquet value for field {}: {:?}", name, field)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.push(row);
+    }
+    Ok(OutputRows { field_names, rows })
+}
+
+fn read_csv_rows(path: &Path) -> Result<OutputRows, Box<dyn Error>> {
+    let mut reader = csv::Reader::from_path(path)?;
+    let field_names = reader.headers()?.iter().map(|name| name.trim().to_string()).collect();
+    let mut rows = vec![];
+    for record in reader.records() {
+        let row = record?
+            .iter()
+            .map(|value| value.trim().parse::<u64>())
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.push(row);
+    }
+    Ok(OutputRows { field_names, rows })
+}
+
+//decode the rows into tuples of the given layout if the field names match it
+pub fn rows_as_tuples<T: SinkTuple>(output: &OutputRows) -> Option<Vec<T>> {
+    if output.field_names.iter().map(String::as_str).eq(T::FIELD_NAMES.iter().copied()) {
+        Some(output.rows.iter().map(|row| T::from_values(row)).collect())
+    } else {
+        None
+    }
+}
+
+pub fn stateless_timings(tuples: &[StatelessOutputTuple], options: &AnalysisOptions) -> Vec<TupleTiming> {
+    tuples
+        .iter()
+        .map(|tuple| {
+            let emission_time = options.emission_time_unit.to_nanos(tuple.emission_time);
+            let event_time = options.event_time_unit.to_nanos(tuple.event_time);
+            TupleTiming {
+                emission_time,
+                latency: emission_time as i64 - event_time as i64,
+            }
+        })
+        .collect()
+}
+
+//a join result can only be produced once both inputs arrived, so its latency is measured from the later event time
+pub fn join_timings(tuples: &[JoinOutputTuple], options: &AnalysisOptions) -> Vec<TupleTiming> {
+    tuples
+        .iter()
+        .map(|tuple| {
+            let emission_time = options
+                .emission_time_unit
+                .to_nanos(tuple.emission_time_1.max(tuple.emission_time_2));
+            let event_time = options
+                .event_time_unit
+                .to_nanos(tuple.event_time_1.max(tuple.event_time_2));
+            TupleTiming {
+                emission_time,
+                latency: emission_time as i64 - event_time as i64,
+            }
+        })
+        .collect()
+}
+
+pub fn nanos_to_ms(nanos: f64) -> f64 {
+    nanos / 1_000_000.0
+}
+
+//nearest rank percentile of sorted values
+fn percentile(sorted: &[i64], percent: f64) -> i64 {
+    let rank = ((percent / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+pub fn summarize_latency(timings: &[TupleTiming]) -> Option<LatencySummary> {
+    if timings.is_empty() {
+        return None;
+    }
+    let mut latencies = timings.iter().map(|timing| timing.latency).collect::<Vec<_>>();
+    latencies.sort_unstable();
+    let mean = latencies.iter().map(|latency| *latency as f64).sum::<f64>() / latencies.len() as f64;
+    Some(LatencySummary {
+        p50_ms: nanos_to_ms(percentile(&latencies, 50.0) as f64),
+        p90_ms: nanos_to_ms(percentile(&latencies, 90.0) as f64),
+        p99_ms: nanos_to_ms(percentile(&latencies, 99.0) as f64),
+        max_ms: nanos_to_ms(*latencies.last().unwrap() as f64),
+        mean_ms: nanos_to_ms(mean),
+    })
+}
+
+pub fn throughput_bins(timings: &[TupleTiming], bin_width: Duration) -> Vec<ThroughputBin> {
+    let Some(first) = timings.iter().map(|timing| timing.emission_time).min() else {
+        return vec![];
+    };
+    let width = bin_width.as_nanos().max(1) as u64;
+    let mut bins: BTreeMap<u64, (u64, i128)> = BTreeMap::new();
+    for timing in timings {
+        let bin = (timing.emission_time - first) / width;
+        let entry = bins.entry(bin).or_insert((0, 0));
+        entry.0 += 1;
+        entry.1 += timing.latency as i128;
+    }
+    let last_bin = *bins.keys().last().unwrap();
+    (0..=last_bin)
+        .map(|bin| {
+            let (tuples, latency_sum) = bins.get(&bin).copied().unwrap_or((0, 0));
+            ThroughputBin {
+                start_ms: bin * width / 1_000_000,
+                tuples,
+                tuples_per_second: tuples as f64 / bin_width.as_secs_f64(),
+                mean_latency_ms: (tuples > 0).then(|| nanos_to_ms(latency_sum as f64 / tuples as f64)),
+            }
+        })
+        .collect()
+}
+
+//periods without any output that are longer than the threshold, relative to the first emitted tuple
+pub fn find_gaps(timings: &[TupleTiming], threshold: Duration) -> Vec<Gap> {
+    let mut emission_times = timings.iter().map(|timing| timing.emission_time).collect::<Vec<_>>();
+    emission_times.sort_unstable();
+    let Some(first) = emission_times.first().copied() else {
+        return vec![];
+    };
+    emission_times
+        .windows(2)
+        .filter(|pair| pair[1] - pair[0] > threshold.as_nanos() as u64)
+        .map(|pair| {
+            let start_ms = (pair[0] - first) / 1_000_000;
+            let end_ms = (pair[1] - first) / 1_000_000;
+            Gap {
+                start_ms,
+                end_ms,
+                duration_ms: end_ms - start_ms,
+            }
+        })
+        .collect()
+}
+
+pub fn summarize(input_file: &Path, workload: &str, timings: &[TupleTiming], options: &AnalysisOptions) -> AnalysisSummary {
+    let throughput = throughput_bins(timings, options.bin_width);
+    let first_emission_ns = timings.iter().map(|timing| timing.emission_time).min();
+    let last_emission_ns = timings.iter().map(|timing| timing.emission_time).max();
+    let mean_throughput = match (first_emission_ns, last_emission_ns) {
+        (Some(first), Some(last)) if last > first => timings.len() as f64 / Duration::from_nanos(last - first).as_secs_f64(),
+        _ => 0.0,
+    };
+    AnalysisSummary {
+        input_file: input_file.to_path_buf(),
+        workload: workload.to_string(),
+        tuple_count: timings.len(),
+        first_emission_ns,
+        last_emission_ns,
+        latency: summarize_latency(timings),
+        mean_throughput,
+        peak_throughput: throughput.iter().map(|bin| bin.tuples_per_second).fold(0.0, f64::max),
+        gaps: find_gaps(timings, options.gap_threshold),
+        throughput,
+    }
+}
+
+//read a result file of either workload and return the name of the workload with the timings of its tuples
+pub fn read_timings(input_file: &Path, options: &AnalysisOptions) -> Result<(&'static str, Vec<TupleTiming>), Box<dyn Error>> {
+    let output = read_output_rows(input_file)?;
+    if let Some(tuples) = rows_as_tuples::<StatelessOutputTuple>(&output) {
+        Ok(("stateless", stateless_timings(&tuples, options)))
+    } else if let Some(tuples) = rows_as_tuples::<JoinOutputTuple>(&output) {
+        Ok(("join", join_timings(&tuples, options)))
+    } else {
+        Err(format!("unknown result layout in {}: {:?}", input_file.display(), output.field_names).into())
+    }
+}
+
+//analyze a result file and write <file>analysis.json with the summary and <file>analysis.csv with the throughput bins.
+//the plots of the file are written by plots::plot_run
+pub fn analyze_output_file(input_file: &Path, options: &AnalysisOptions) -> Result<AnalysisSummary, Box<dyn Error>> {
+    let (workload, timings) = read_timings(input_file, options)?;
+    let summary = summarize(input_file, workload, &timings, options);
+
+    let base_path = input_file.to_str().ok_or("could not convert result path to string")?;
+    std::fs::write(format!("{}{}", base_path, ANALYSIS_SUFFIX), serde_json::to_string_pretty(&summary)?)?;
+    let mut csv_file = File::create(format!("{}{}", base_path, THROUGHPUT_SUFFIX))?;
+    writeln!(csv_file, "start_ms,tuples,tuples_per_second,mean_latency_ms")?;
+    for bin in &summary.throughput {
+        writeln!(
+            csv_file,
+            "{},{},{},{}",
+            bin.start_ms,
+            bin.tuples,
+            bin.tuples_per_second,
+            bin.mean_latency_ms.map(|latency| latency.to_string()).unwrap_or_default()
+        )?;
+    }
+    if let Some(latency) = &summary.latency {
+        println!(
+            "{} tuples, latency p50 {:.2}ms p90 {:.2}ms p99 {:.2}ms max {:.2}ms, {} gaps",
+            summary.tuple_count, latency.p50_ms, latency.p90_ms, latency.p99_ms, latency.max_ms, summary.gaps.len()
+        );
+    }
+    Ok(summary)
+}
+
+//a width in milliseconds for the bins and gaps of the analysis, zero would put every tuple into a bin or gap of its own
+pub fn parse_width_ms(flag: &str, value: &str) -> Result<Duration, Box<dyn Error>> {
+    match value.parse::<u64>().map_err(|e| format!("invalid value for {}: {}", flag, e))? {
+        0 => Err(format!("{} has to be larger than zero", flag).into()),
+        width => Ok(Duration::from_millis(width)),
+    }
+}
+
+fn parse_time_unit(value: &str) -> Result<TimeUnit, Box<dyn Error>> {
+    match value {
+        "ns" => Ok(TimeUnit::NANOSECONDS),
+        "us" => Ok(TimeUnit::MICROSECONDS),
+        "ms" => Ok(TimeUnit::MILLISECONDS),
+        _ => Err(format!("unknown time unit {}, expected ns, us or ms", value).into()),
+    }
+}
+
+//entry point of the analyze command: analyze <result file>... [--bin-ms <ms>] [--gap-ms <ms>] [--event-unit <ns|us|ms>]
+//[--emission-unit <ns|us|ms>] [--notebook <template>]
+pub fn run_analyze_command(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut options = AnalysisOptions::default();
+    let mut notebook = None;
+    let mut input_files = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--bin-ms" => options.bin_width = parse_width_ms(arg, value()?)?,
+            "--gap-ms" => options.gap_threshold = parse_width_ms(arg, value()?)?,
+            "--event-unit" => options.event_time_unit = parse_time_unit(value()?)?,
+            "--emission-unit" => options.emission_time_unit = parse_time_unit(value()?)?,
+            "--notebook" => notebook = Some(PathBuf::from(value()?)),
+            _ => input_files.push(PathBuf::from(arg)),
+        }
+    }
+    if input_files.is_empty() {
+        return Err("Usage: analyze <result file>... [--bin-ms <ms>] [--gap-ms <ms>] [--event-unit <ns|us|ms>] [--emission-unit <ns|us|ms>] [--notebook <template>]".into());
+    }
+    for input_file in input_files {
+        analyze_output_file(&input_file, &options)?;
+        crate::plots::plot_run(&input_file, &options)?;
+        if let Some(template) = &notebook {
+            let notebook_output = PathBuf::from(format!("{}analysis.ipynb", input_file.display()));
+            if let Err(e) = create_notebook(&input_file, template, &notebook_output) {
+                eprintln!("Could not create notebook: {}", e);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timing(emission_ms: u64, latency_ms: i64) -> TupleTiming {
+        TupleTiming {
+            emission_time: emission_ms * 1_000_000,
+            latency: latency_ms * 1_000_000,
+        }
+    }
+
+    #[test]
+    fn test_zero_widths_are_rejected() {
+        assert!(parse_width_ms("--bin-ms", "0").is_err());
+        assert!(parse_width_ms("--gap-ms", "0").is_err());
+        assert!(parse_width_ms("--gap-ms", "-5").is_err());
+        assert_eq!(parse_width_ms("--bin-ms", "250").unwrap(), Duration::from_millis(250));
+    }
+
+    #[test]
+    fn test_latency_throughput_and_gaps() {
+        let mut timings = (1..=100).map(|i| timing(i * 10, i as i64)).collect::<Vec<_>>();
+        timings.push(timing(3_000, 200));
+        let latency = summarize_latency(&timings).unwrap();
+        assert_eq!(latency.p50_ms, 51.0);
+        assert_eq!(latency.p90_ms, 91.0);
+        assert_eq!(latency.p99_ms, 100.0);
+        assert_eq!(latency.max_ms, 200.0);
+
+        let bins = throughput_bins(&timings, Duration::from_secs(1));
+        assert_eq!(bins.len(), 3);
+        assert_eq!(bins[0].tuples, 100);
+        assert_eq!(bins[1].tuples, 0);
+        assert_eq!(bins[1].mean_latency_ms, None);
+        assert_eq!(bins[2].tuples, 1);
+
+        let gaps = find_gaps(&timings, Duration::from_secs(1));
+        assert_eq!(gaps, vec![Gap { start_ms: 990, end_ms: 2_990, duration_ms: 2_000 }]);
+    }
+}
@@ -0,0 +1,359 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::analyze::{AnalysisOptions, AnalysisSummary, ANALYSIS_SUFFIX};
+use crate::plots::plot_sweep;
+use crate::completeness::CompletenessReport;
+use crate::reconnect_analysis::{find_result_files, RunReconnectAnalysis, COMPLETENESS_SUFFIX, RECONNECT_ANALYSIS_SUFFIX};
+
+pub const INPUT_CONFIG_COPY_FILE: &str = "input_config_copy.toml";
+pub const COMPARISON_CSV_FILE: &str = "comparison.csv";
+pub const COMPARISON_HTML_FILE: &str = "comparison.html";
+
+//metrics extracted from the analysis files of a single attempt, in the order they appear in the report
+pub const METRICS: &[&str] = &[
+    "completeness",
+    "missing_tuples",
+    "duplicate_tuples",
+    "latency_p50_ms",
+    "latency_p99_ms",
+    "mean_throughput",
+    "reconnect_peak_latency_ms",
+    "reconnect_recovery_time_ms",
+    "reconnect_lost_tuples",
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MetricStatistics {
+    pub samples: usize,
+    pub mean: f64,
+    //half width of the 95% confidence interval of the mean, none if there is only one sample
+    pub ci95: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConfigurationComparison {
+    //values of the swept parameters, in the order of SweepComparison::swept_parameters
+    pub parameters: Vec<String>,
+    pub folders: Vec<PathBuf>,
+    pub attempts: usize,
+    pub metrics: BTreeMap<String, MetricStatistics>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SweepComparison {
+    pub sweep_folder: PathBuf,
+    pub swept_parameters: Vec<String>,
+    pub configurations: Vec<ConfigurationComparison>,
+}
+
+//flatten a toml document into dotted keys, e.g. parameters.speedup_factor
+fn flatten_toml(prefix: &str, value: &toml::Value, output: &mut BTreeMap<String, String>) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table {
+                let key = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                flatten_toml(&key, value, output);
+            }
+        }
+        toml::Value::String(value) => {
+            output.insert(prefix.to_string(), value.clone());
+        }
+        value => {
+            output.insert(prefix.to_string(), value.to_string());
+        }
+    }
+}
+
+pub fn read_config_parameters(configuration: &Path) -> Result<BTreeMap<String, String>, Box<dyn Error>> {
+    let config: toml::Value = toml::from_str(&fs::read_to_string(configuration.join(INPUT_CONFIG_COPY_FILE))?)?;
+    let mut parameters = BTreeMap::new();
+    flatten_toml("", &config, &mut parameters);
+    Ok(parameters)
+}
+
+//parameters that do not have the same value in every configuration of the sweep
+pub fn swept_parameters(configurations: &[BTreeMap<String, String>]) -> Vec<String> {
+    let keys = configurations.iter().flat_map(|parameters| parameters.keys()).collect::<BTreeSet<_>>();
+    keys.into_iter()
+        .filter(|key| {
+            configurations
+                .iter()
+                .map(|parameters| parameters.get(*key))
+                .collect::<BTreeSet<_>>()
+                .len()
+                > 1
+        })
+        .cloned()
+        .collect()
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: String) -> Option<T> {
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+//values of the metrics by name
+pub type AttemptMetrics = BTreeMap<String, f64>;
+
+//metrics of one attempt, read from the files the runner and the analysis commands write next to its result file
+pub fn attempt_metrics(result_file: &Path) -> AttemptMetrics {
+    let base_path = result_file.display().to_string();
+    let mut metrics = BTreeMap::new();
+    if let Some(report) = read_json::<CompletenessReport>(format!("{}{}", base_path, COMPLETENESS_SUFFIX)) {
+        if report.expected_tuples > 0 {
+            let complete = report.expected_tuples.saturating_sub(report.missing_tuples);
+            metrics.insert("completeness".to_string(), complete as f64 / report.expected_tuples as f64);
+        }
+        metrics.insert("missing_tuples".to_string(), report.missing_tuples as f64);
+        metrics.insert("duplicate_tuples".to_string(), report.duplicate_tuples as f64);
+    }
+    if let Some(summary) = read_json::<AnalysisSummary>(format!("{}{}", base_path, ANALYSIS_SUFFIX)) {
+        if let Some(latency) = summary.latency {
+            metrics.insert("latency_p50_ms".to_string(), latency.p50_ms);
+            metrics.insert("latency_p99_ms".to_string(), latency.p99_ms);
+        }
+        metrics.insert("mean_throughput".to_string(), summary.mean_throughput);
+    }
+    if let Some(analysis) = read_json::<RunReconnectAnalysis>(format!("{}{}", base_path, RECONNECT_ANALYSIS_SUFFIX)) {
+        let peaks = analysis.reconnects.iter().filter_map(|impact| impact.peak_latency_ms).collect::<Vec<_>>();
+        let recoveries = analysis
+            .reconnects
+            .iter()
+            .filter_map(|impact| impact.recovery_time_ms.map(|time| time as f64))
+            .collect::<Vec<_>>();
+        if let Some(peak) = mean(&peaks) {
+            metrics.insert("reconnect_peak_latency_ms".to_string(), peak);
+        }
+        if let Some(recovery) = mean(&recoveries) {
+            metrics.insert("reconnect_recovery_time_ms".to_string(), recovery);
+        }
+        if analysis.reconnects.iter().any(|impact| impact.lost_tuples.is_some()) {
+            let lost = analysis.reconnects.iter().filter_map(|impact| impact.lost_tuples).sum::<u64>();
+            metrics.insert("reconnect_lost_tuples".to_string(), lost as f64);
+        }
+    }
+    metrics
+}
+
+//two sided 97.5% quantiles of the t distribution for 1 to 30 degrees of freedom
+const T_QUANTILES: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160, 2.145, 2.131, 2.120,
+    2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056, 2.052, 2.048, 2.045, 2.042,
+];
+
+pub fn statistics(values: &[f64]) -> Option<MetricStatistics> {
+    let mean = mean(values)?;
+    let samples = values.len();
+    let ci95 = (samples > 1).then(|| {
+        let variance = values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / (samples - 1) as f64;
+        let quantile = T_QUANTILES.get(samples - 2).copied().unwrap_or(1.96);
+        quantile * variance.sqrt() / (samples as f64).sqrt()
+    });
+    Some(MetricStatistics { samples, mean, ci95 })
+}
+
+//configuration folders of a sweep are the subfolders that contain a copy of their input config
+fn find_configurations(sweep_folder: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut configurations = fs::read_dir(sweep_folder)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.join(INPUT_CONFIG_COPY_FILE).exists())
+        .collect::<Vec<_>>();
+    configurations.sort();
+    Ok(configurations)
+}
+
+pub fn compare_sweep(sweep_folder: &Path) -> Result<SweepComparison, Box<dyn Error>> {
+    let configurations = find_configurations(sweep_folder)?;
+    if configurations.is_empty() {
+        return Err(format!("no configuration folders with {} in {}", INPUT_CONFIG_COPY_FILE, sweep_folder.display()).into());
+    }
+    let parameters = configurations
+        .iter()
+        .map(|configuration| read_config_parameters(configuration))
+        .collect::<Result<Vec<_>, _>>()?;
+    let swept_parameters = swept_parameters(&parameters);
+
+    //folders with the same values for all swept parameters are merged, e.g. the retrials of a configuration
+    let mut groups: BTreeMap<Vec<String>, (Vec<PathBuf>, Vec<AttemptMetrics>)> = BTreeMap::new();
+    for (configuration, parameters) in configurations.iter().zip(&parameters) {
+        let key = swept_parameters
+            .iter()
+            .map(|name| parameters.get(name).cloned().unwrap_or_default())
+            .collect::<Vec<_>>();
+        let group = groups.entry(key).or_default();
+        group.0.push(configuration.clone());
+        for result_file in find_result_files(configuration)? {
+            group.1.push(attempt_metrics(&result_file));
+        }
+    }
+
+    let configurations = groups
+        .into_iter()
+        .map(|(parameters, (folders, attempts))| {
+            let metrics = METRICS
+                .iter()
+                .filter_map(|metric| {
+                    let values = attempts.iter().filter_map(|attempt| attempt.get(*metric).copied()).collect::<Vec<_>>();
+                    statistics(&values).map(|statistics| (metric.to_string(), statistics))
+                })
+                .collect();
+            ConfigurationComparison {
+                parameters,
+                folders,
+                attempts: attempts.len(),
+                metrics,
+            }
+        })
+        .collect();
+    Ok(SweepComparison {
+        sweep_folder: sweep_folder.to_path_buf(),
+        swept_parameters,
+        configurations,
+    })
+}
+
+fn format_value(value: Option<f64>) -> String {
+    value.map(|value| format!("{:.4}", value)).unwrap_or_default()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn comparison_csv(comparison: &SweepComparison) -> String {
+    let mut header = comparison.swept_parameters.clone();
+    header.push("attempts".to_string());
+    for metric in METRICS {
+        header.push(format!("{}_mean", metric));
+        header.push(format!("{}_ci95", metric));
+    }
+    let mut lines = vec![header.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(",")];
+    for configuration in &comparison.configurations {
+        let mut fields = configuration.parameters.iter().map(|value| csv_field(value)).collect::<Vec<_>>();
+        fields.push(configuration.attempts.to_string());
+        for metric in METRICS {
+            let statistics = configuration.metrics.get(*metric);
+            fields.push(format_value(statistics.map(|statistics| statistics.mean)));
+            fields.push(format_value(statistics.and_then(|statistics| statistics.ci95)));
+        }
+        lines.push(fields.join(","));
+    }
+    lines.join("\n") + "\n"
+}
+
+fn escape_html(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+pub fn comparison_html(comparison: &SweepComparison) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>Comparison of {}</title>\n", escape_html(&comparison.sweep_folder.display().to_string())));
+    html.push_str("<style>table { border-collapse: collapse; font-family: sans-serif; font-size: 13px; } ");
+    html.push_str("th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: right; } th { background: #eee; }</style>\n");
+    html.push_str("</head>\n<body>\n");
+    html.push_str(&format!("<h1>{}</h1>\n", escape_html(&comparison.sweep_folder.display().to_string())));
+    html.push_str("<p>Mean &plusmn; half width of the 95% confidence interval over all attempts of a configuration.</p>\n");
+    html.push_str("<table>\n<tr>");
+    for parameter in &comparison.swept_parameters {
+        html.push_str(&format!("<th>{}</th>", escape_html(parameter)));
+    }
+    html.push_str("<th>attempts</th>");
+    for metric in METRICS {
+        html.push_str(&format!("<th>{}</th>", metric));
+    }
+    html.push_str("</tr>\n");
+    for configuration in &comparison.configurations {
+        html.push_str("<tr>");
+        for value in &configuration.parameters {
+            html.push_str(&format!("<td>{}</td>", escape_html(value)));
+        }
+        html.push_str(&format!("<td>{}</td>", configuration.attempts));
+        for metric in METRICS {
+            let cell = match configuration.metrics.get(*metric) {
+                Some(MetricStatistics { mean, ci95: Some(ci95), .. }) => format!("{:.4} &plusmn; {:.4}", mean, ci95),
+                Some(MetricStatistics { mean, ci95: None, .. }) => format!("{:.4}", mean),
+                None => String::new(),
+            };
+            html.push_str(&format!("<td>{}</td>", cell));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+    html
+}
+
+//entry point of the compare command: compare <sweep folder>. writes comparison.csv, comparison.html and the latency cdf
+//of all configurations to the folder
+pub fn run_compare_command(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let [sweep_folder] = args else {
+        return Err("Usage: compare <sweep folder>".into());
+    };
+    let sweep_folder = PathBuf::from(sweep_folder);
+    let comparison = compare_sweep(&sweep_folder)?;
+    fs::write(sweep_folder.join(COMPARISON_CSV_FILE), comparison_csv(&comparison))?;
+    fs::write(sweep_folder.join(COMPARISON_HTML_FILE), comparison_html(&comparison))?;
+    let configurations = comparison
+        .configurations
+        .iter()
+        .map(|configuration| {
+            let label = comparison
+                .swept_parameters
+                .iter()
+                .zip(&configuration.parameters)
+                .map(|(name, value)| format!("{}={}", name.rsplit('.').next().unwrap_or(name), value))
+                .collect::<Vec<_>>()
+                .join(", ");
+            (label, configuration.folders.clone())
+        })
+        .collect::<Vec<_>>();
+    plot_sweep(&sweep_folder, &configurations, &AnalysisOptions::default())?;
+    println!(
+        "Compared {} configurations over swept parameters {:?}",
+        comparison.configurations.len(),
+        comparison.swept_parameters
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_swept_parameters_and_statistics() {
+        let configurations = [
+            "[parameters]\nspeedup_factor = 1.0\nenable_query_reconfiguration = true\nruntime = 10\n",
+            "[parameters]\nspeedup_factor = 2.0\nenable_query_reconfiguration = true\nruntime = 10\n",
+            "[parameters]\nspeedup_factor = 1.0\nenable_query_reconfiguration = false\nruntime = 10\n",
+        ]
+        .iter()
+        .map(|config| {
+            let mut parameters = BTreeMap::new();
+            flatten_toml("", &toml::from_str(config).unwrap(), &mut parameters);
+            parameters
+        })
+        .collect::<Vec<_>>();
+        assert_eq!(
+            swept_parameters(&configurations),
+            vec!["parameters.enable_query_reconfiguration", "parameters.speedup_factor"]
+        );
+
+        let statistics = statistics(&[1.0, 2.0, 3.0]).unwrap();
+        assert_eq!(statistics.mean, 2.0);
+        assert!((statistics.ci95.unwrap() - 4.303 / 3f64.sqrt()).abs() < 1e-9);
+        assert_eq!(super::statistics(&[5.0]).unwrap().ci95, None);
+    }
+}
@@ -0,0 +1,533 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use simulation_runner_rs::data_generation::{DataGenerationSpec, JoinKeyRule};
+use simulation_runner_rs::input_sources::{join_partner, InputSourceConfig};
+use simulation_runner_rs::rate_profile::{EmissionSchedule, RateProfile};
+
+use crate::expected_output::{JoinInput, JoinPairModel};
+
+//default width of the buckets that group missing and duplicated tuples by their distance to a topology update
+pub const DEFAULT_BUCKET_WIDTH: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct JoinSpec {
+    pub window_size: u64,
+}
+
+//input parameters of one source, see InputSourceConfig
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GeneratedSource {
+    pub tuple_count: u64,
+    pub tuples_per_buffer: u64,
+    pub gathering_interval: Duration,
+    //the profile the input server emits with, with the reconnect bursts resolved
+    pub rate_profile: RateProfile,
+    //rule of the join key field of the spec of the source, the join output is not modelled without one
+    pub join_keys: Option<JoinKeyRule>,
+    //id of the source the tuples are joined with
+    pub join_partner: Option<u64>,
+}
+
+impl GeneratedSource {
+    fn buffer(&self, sequence_number: u64) -> u64 {
+        sequence_number / self.tuples_per_buffer.max(1)
+    }
+
+    //times after the ingestion start at which the input server sends the first buffers, replayed from the same
+    //schedule. none if the profile draws the intervals at random
+    fn buffer_offsets(&self, buffers: u64) -> Option<Vec<Duration>> {
+        if self.rate_profile == RateProfile::Poisson {
+            return None;
+        }
+        let mut schedule = EmissionSchedule::new(self.rate_profile.clone(), self.gathering_interval, 0);
+        let mut offset = Duration::ZERO;
+        let mut offsets = Vec::with_capacity(buffers as usize);
+        for _ in 0..buffers {
+            offsets.push(offset);
+            offset += schedule.next_interval(offset);
+        }
+        Some(offsets)
+    }
+}
+
+//describes the data produced by the input server, which lets us reconstruct every tuple it emitted. the tuples of
+//every source are numbered from zero
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GeneratorSpec {
+    //keyed by source id
+    pub sources: BTreeMap<u64, GeneratedSource>,
+    //time since the unix epoch at which the first buffer is emitted
+    pub ingestion_start: Duration,
+    pub join: Option<JoinSpec>,
+}
+
+impl GeneratorSpec {
+    //input_sources are the sources of the attempt with the reconnect bursts resolved, specs holds the data generation
+    //spec of every source in the same order. the join output of the sources is not modelled without specs
+    pub fn from_input_sources(
+        input_sources: &[InputSourceConfig],
+        specs: Option<&[DataGenerationSpec]>,
+        ingestion_start: Duration,
+        join: Option<JoinSpec>,
+    ) -> Self {
+        let sources = input_sources
+            .iter()
+            .enumerate()
+            .map(|(index, source)| {
+                let generated = GeneratedSource {
+                    tuple_count: source.tuple_count(),
+                    tuples_per_buffer: source.tuples_per_buffer as u64,
+                    gathering_interval: source.gathering_interval,
+                    rate_profile: source.rate_profile.clone(),
+                    join_keys: specs.and_then(|specs| specs[index].join_key_rule()),
+                    join_partner: join_partner(input_sources, source).map(|partner| partner.source_id),
+                };
+                (source.source_id, generated)
+            })
+            .collect();
+        Self {
+            sources,
+            ingestion_start,
+            join,
+        }
+    }
+
+    //sequence numbers a source is expected to contribute to the output if the sources emitted the given number of
+    //tuples. none if the join output of the source is not modelled
+    pub fn expected_sequence_numbers(&self, source_id: u64, tuple_count: impl Fn(u64) -> u64) -> Option<Vec<u64>> {
+        let source = self.sources.get(&source_id)?;
+        let Some(join) = self.join else {
+            return Some((0..tuple_count(source_id)).collect());
+        };
+        let partner_id = source.join_partner?;
+        let partner = self.sources.get(&partner_id)?;
+        let model = JoinPairModel {
+            left: JoinInput { source_id, tuple_count: tuple_count(source_id), join_keys: source.join_keys? },
+            right: JoinInput { source_id: partner_id, tuple_count: tuple_count(partner_id), join_keys: partner.join_keys? },
+            window_size: join.window_size,
+        };
+        Some(model.expected_tuples().iter().map(|tuple| tuple.sequence_number_1).dedup().collect())
+    }
+
+    //the time at which the input server sent the buffer containing the sequence number
+    pub fn emission_time(&self, source_id: u64, sequence_number: u64) -> Option<Duration> {
+        let source = self.sources.get(&source_id)?;
+        let buffer = source.buffer(sequence_number);
+        source
+            .buffer_offsets(buffer + 1)
+            .map(|offsets| self.ingestion_start + offsets[buffer as usize])
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SequenceRange {
+    pub source_id: u64,
+    pub first_sequence_number: u64,
+    pub last_sequence_number: u64,
+    pub count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DuplicateTuple {
+    pub source_id: u64,
+    pub sequence_number: u64,
+    pub count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct IssueCounts {
+    pub missing: u64,
+    pub duplicates: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UpdateBucket {
+    pub update_index: usize,
+    pub sent_at_ns: u128,
+    pub totals: IssueCounts,
+    //counts keyed by the start of the bucket in milliseconds after the update was sent
+    pub by_offset_ms: BTreeMap<u64, IssueCounts>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CompletenessReport {
+    pub expected_tuples: u64,
+    pub received_tuples: u64,
+    pub unique_tuples: u64,
+    pub missing_tuples: u64,
+    pub duplicate_tuples: u64,
+    //tuples with a source id or sequence number the generator did not produce for this run
+    pub unexpected_tuples: u64,
+    pub missing: Vec<SequenceRange>,
+    pub duplicates: Vec<DuplicateTuple>,
+    pub unexpected: Vec<SequenceRange>,
+    pub before_first_update: IssueCounts,
+    pub by_update: Vec<UpdateBucket>,
+    //missing tuples that are not assigned to an update because the rate profile does not tell when they were sent
+    pub unbucketed_missing: u64,
+    //sources whose join output is not modelled, their tuples are neither expected nor unexpected
+    pub unmodelled_sources: Vec<u64>,
+}
+
+struct Received {
+    count: u64,
+    first_event_time_ns: u64,
+}
+
+//collects the (source id, sequence number) of every received tuple. for joins each result is recorded once for each
+//side, so every source is expected to contribute each matching sequence number exactly once
+pub struct CompletenessAnalyzer {
+    spec: GeneratorSpec,
+    received_tuples: u64,
+    received: HashMap<(u64, u64), Received>,
+    //tuples the input server reported as emitted per source, replaces the tuple counts of the spec
+    emitted_tuples: HashMap<u64, u64>,
+}
+
+impl CompletenessAnalyzer {
+    pub fn new(spec: GeneratorSpec) -> Self {
+        Self {
+            spec,
+            received_tuples: 0,
+            received: HashMap::new(),
+            emitted_tuples: HashMap::new(),
+        }
+    }
+
+    //for joins the counts have to be limited to the tuples the partner source emitted, see joined_tuple_counts
+    pub fn set_emitted_tuples(&mut self, emitted_tuples: HashMap<u64, u64>) {
+        self.emitted_tuples = emitted_tuples;
+    }
+
+    pub fn record(&mut self, source_id: u64, sequence_number: u64, event_time_ns: u64) {
+        self.received_tuples += 1;
+        self.received
+            .entry((source_id, sequence_number))
+            .and_modify(|received| received.count += 1)
+            .or_insert(Received {
+                count: 1,
+                first_event_time_ns: event_time_ns,
+            });
+    }
+
+    fn tuple_count(&self, source_id: u64) -> u64 {
+        match self.emitted_tuples.get(&source_id) {
+            Some(tuples) => *tuples,
+            None => self.spec.sources.get(&source_id).map_or(0, |source| source.tuple_count),
+        }
+    }
+
+    //update_send_times are the times since the unix epoch at which the topology updates were sent
+    pub fn report(&self, update_send_times: &[Duration], bucket_width: Duration) -> CompletenessReport {
+        let mut expected_sequence_numbers = HashMap::new();
+        let mut unmodelled_sources = vec![];
+        for source_id in self.spec.sources.keys() {
+            match self.spec.expected_sequence_numbers(*source_id, |source_id| self.tuple_count(source_id)) {
+                Some(sequence_numbers) => {
+                    expected_sequence_numbers.insert(*source_id, sequence_numbers);
+                }
+                None => unmodelled_sources.push(*source_id),
+            }
+        }
+        let mut buckets = TimeBuckets::new(update_send_times, bucket_width);
+
+        let mut missing = vec![];
+        let mut unbucketed_missing = 0;
+        for (source_id, source) in &self.spec.sources {
+            let Some(sequence_numbers) = expected_sequence_numbers.get(source_id) else {
+                continue;
+            };
+            let buffers = sequence_numbers.last().map_or(0, |sequence_number| source.buffer(*sequence_number) + 1);
+            let buffer_offsets = source.buffer_offsets(buffers);
+            let missing_sequence_numbers = sequence_numbers
+                .iter()
+                .filter(|sequence_number| !self.received.contains_key(&(*source_id, **sequence_number)))
+                .copied()
+                .collect::<Vec<_>>();
+            for sequence_number in &missing_sequence_numbers {
+                match &buffer_offsets {
+                    Some(offsets) => {
+                        let offset = offsets[source.buffer(*sequence_number) as usize];
+                        buckets.add(self.spec.ingestion_start + offset, true);
+                    }
+                    None => unbucketed_missing += 1,
+                }
+            }
+            missing.extend(to_ranges(*source_id, &missing_sequence_numbers));
+        }
+
+        let is_expected = |source_id: u64, sequence_number: u64| {
+            expected_sequence_numbers
+                .get(&source_id)
+                .is_some_and(|sequence_numbers| sequence_numbers.binary_search(&sequence_number).is_ok())
+        };
+
+        let mut duplicates = vec![];
+        let mut unexpected_keys = vec![];
+        for ((source_id, sequence_number), received) in &self.received {
+            if unmodelled_sources.contains(source_id) {
+                continue;
+            }
+            if !is_expected(*source_id, *sequence_number) {
+                unexpected_keys.push((*source_id, *sequence_number));
+                continue;
+            }
+            if received.count > 1 {
+                duplicates.push(DuplicateTuple {
+                    source_id: *source_id,
+                    sequence_number: *sequence_number,
+                    count: received.count,
+                });
+                for _ in 1..received.count {
+                    buckets.add(Duration::from_nanos(received.first_event_time_ns), false);
+                }
+            }
+        }
+        duplicates.sort_by_key(|duplicate| (duplicate.source_id, duplicate.sequence_number));
+        unexpected_keys.sort();
+        let mut unexpected_by_source: BTreeMap<u64, Vec<u64>> = BTreeMap::new();
+        for (source_id, sequence_number) in &unexpected_keys {
+            unexpected_by_source.entry(*source_id).or_default().push(*sequence_number);
+        }
+        let unexpected = unexpected_by_source
+            .iter()
+            .flat_map(|(source_id, sequence_numbers)| to_ranges(*source_id, sequence_numbers))
+            .collect();
+
+        let expected_tuples = expected_sequence_numbers
+            .values()
+            .map(|sequence_numbers| sequence_numbers.len() as u64)
+            .sum();
+        let missing_tuples = missing.iter().map(|range| range.count).sum();
+        let unexpected_tuples = unexpected_keys
+            .iter()
+            .map(|key| self.received[key].count)
+            .sum();
+        CompletenessReport {
+            expected_tuples,
+            received_tuples: self.received_tuples,
+            unique_tuples: self.received.len() as u64,
+            missing_tuples,
+            duplicate_tuples: duplicates.iter().map(|duplicate| duplicate.count - 1).sum(),
+            unexpected_tuples,
+            missing,
+            duplicates,
+            unexpected,
+            before_first_update: buckets.before_first_update,
+            by_update: buckets.updates,
+            unbucketed_missing,
+            unmodelled_sources,
+        }
+    }
+}
+
+struct TimeBuckets {
+    bucket_width: Duration,
+    before_first_update: IssueCounts,
+    updates: Vec<UpdateBucket>,
+}
+
+impl TimeBuckets {
+    fn new(update_send_times: &[Duration], bucket_width: Duration) -> Self {
+        let mut send_times = update_send_times.to_vec();
+        send_times.sort();
+        Self {
+            bucket_width,
+            before_first_update: IssueCounts::default(),
+            updates: send_times
+                .iter()
+                .enumerate()
+                .map(|(update_index, sent_at)| UpdateBucket {
+                    update_index,
+                    sent_at_ns: sent_at.as_nanos(),
+                    totals: IssueCounts::default(),
+                    by_offset_ms: BTreeMap::new(),
+                })
+                .collect(),
+        }
+    }
+
+    //assign an issue to the latest update that was sent before it occurred
+    fn add(&mut self, time: Duration, missing: bool) {
+        let preceding = self
+            .updates
+            .partition_point(|update| update.sent_at_ns <= time.as_nanos());
+        let (counts, offset_counts) = if preceding == 0 {
+            (&mut self.before_first_update, None)
+        } else {
+            let update = &mut self.updates[preceding - 1];
+            let offset_ms = (time.as_nanos() - update.sent_at_ns) / 1_000_000;
+            let width_ms = self.bucket_width.as_millis().max(1);
+            let bucket_start = (offset_ms / width_ms * width_ms) as u64;
+            (
+                &mut update.totals,
+                Some(update.by_offset_ms.entry(bucket_start).or_default()),
+            )
+        };
+        for counts in std::iter::once(counts).chain(offset_counts) {
+            if missing {
+                counts.missing += 1;
+            } else {
+                counts.duplicates += 1;
+            }
+        }
+    }
+}
+
+//collapse sorted sequence numbers into contiguous ranges
+fn to_ranges(source_id: u64, sequence_numbers: &[u64]) -> Vec<SequenceRange> {
+    let mut ranges: Vec<SequenceRange> = vec![];
+    for sequence_number in sequence_numbers {
+        match ranges.last_mut() {
+            Some(range) if range.last_sequence_number + 1 == *sequence_number => {
+                range.last_sequence_number = *sequence_number;
+                range.count += 1;
+            }
+            _ => ranges.push(SequenceRange {
+                source_id,
+                first_sequence_number: *sequence_number,
+                last_sequence_number: *sequence_number,
+                count: 1,
+            }),
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use simulation_runner_rs::rate_profile::RateStep;
+
+    fn source(join_partner: u64) -> GeneratedSource {
+        GeneratedSource {
+            tuple_count: 10,
+            tuples_per_buffer: 2,
+            gathering_interval: Duration::from_millis(100),
+            rate_profile: RateProfile::Constant,
+            join_keys: Some(JoinKeyRule::Interval(3)),
+            join_partner: Some(join_partner),
+        }
+    }
+
+    fn spec(join: Option<JoinSpec>) -> GeneratorSpec {
+        GeneratorSpec {
+            sources: BTreeMap::from([(1, source(2)), (2, source(1))]),
+            ingestion_start: Duration::from_secs(1000),
+            join,
+        }
+    }
+
+    #[test]
+    fn test_missing_and_duplicates_are_bucketed_by_update() {
+        let spec = spec(None);
+        let mut analyzer = CompletenessAnalyzer::new(spec.clone());
+        for source_id in 1..=2 {
+            for sequence_number in 0..10 {
+                //source 2 loses the buffers emitted between 200ms and 400ms
+                if source_id == 2 && (4..8).contains(&sequence_number) {
+                    continue;
+                }
+                let event_time = spec.emission_time(source_id, sequence_number).unwrap().as_nanos() as u64;
+                analyzer.record(source_id, sequence_number, event_time);
+            }
+        }
+        analyzer.record(1, 9, spec.emission_time(1, 9).unwrap().as_nanos() as u64);
+        analyzer.record(3, 0, 0);
+
+        let update = Duration::from_millis(1_000_150);
+        let report = analyzer.report(&[update], Duration::from_millis(100));
+        assert_eq!(report.expected_tuples, 20);
+        assert_eq!(report.missing_tuples, 4);
+        assert_eq!(
+            report.missing,
+            vec![SequenceRange { source_id: 2, first_sequence_number: 4, last_sequence_number: 7, count: 4 }]
+        );
+        assert_eq!(report.duplicate_tuples, 1);
+        assert_eq!(report.unexpected_tuples, 1);
+        assert_eq!(report.by_update[0].totals, IssueCounts { missing: 4, duplicates: 1 });
+        assert_eq!(report.by_update[0].by_offset_ms[&0], IssueCounts { missing: 2, duplicates: 0 });
+        assert_eq!(report.by_update[0].by_offset_ms[&100], IssueCounts { missing: 2, duplicates: 0 });
+        assert_eq!(report.by_update[0].by_offset_ms[&200], IssueCounts { missing: 0, duplicates: 1 });
+    }
+
+    #[test]
+    fn test_emitted_tuples_limit_expected_output() {
+        let mut analyzer = CompletenessAnalyzer::new(spec(None));
+        for sequence_number in 0..6 {
+            analyzer.record(1, sequence_number, 0);
+        }
+        //the input server was stopped after source 1 emitted 6 tuples and source 2 emitted none
+        analyzer.set_emitted_tuples(HashMap::from([(1, 6), (2, 0)]));
+        let report = analyzer.report(&[], DEFAULT_BUCKET_WIDTH);
+        assert_eq!(report.expected_tuples, 6);
+        assert_eq!(report.missing_tuples, 0);
+        assert_eq!(report.unexpected_tuples, 0);
+    }
+
+    #[test]
+    fn test_emission_times_follow_the_rate_profile() {
+        let mut spec = spec(None);
+        let steps = RateProfile::Steps { steps: vec![RateStep { start_ms: 200, factor: 2.0 }] };
+        spec.sources.get_mut(&1).unwrap().rate_profile = steps;
+        //the buffers are sent after 0, 100, 200, 250 and 300 ms
+        assert_eq!(spec.emission_time(1, 8), Some(Duration::from_millis(1_000_300)));
+        assert_eq!(spec.emission_time(2, 8), Some(Duration::from_millis(1_000_400)));
+
+        //random intervals can not be replayed, the missing tuples are counted without an update
+        for source in spec.sources.values_mut() {
+            source.rate_profile = RateProfile::Poisson;
+        }
+        assert_eq!(spec.emission_time(1, 8), None);
+        let mut analyzer = CompletenessAnalyzer::new(spec);
+        analyzer.record(1, 0, 0);
+        let report = analyzer.report(&[Duration::from_secs(1000)], DEFAULT_BUCKET_WIDTH);
+        assert_eq!(report.missing_tuples, 19);
+        assert_eq!(report.unbucketed_missing, 19);
+        assert_eq!(report.by_update[0].totals, IssueCounts::default());
+    }
+
+    #[test]
+    fn test_sources_keep_their_own_input_parameters() {
+        let mut spec = spec(None);
+        let overridden = spec.sources.get_mut(&2).unwrap();
+        overridden.tuple_count = 20;
+        overridden.tuples_per_buffer = 5;
+        assert_eq!(spec.emission_time(2, 12), Some(Duration::from_millis(1_000_200)));
+        let report = CompletenessAnalyzer::new(spec).report(&[], DEFAULT_BUCKET_WIDTH);
+        assert_eq!(report.expected_tuples, 30);
+        assert_eq!(
+            report.missing,
+            vec![
+                SequenceRange { source_id: 1, first_sequence_number: 0, last_sequence_number: 9, count: 10 },
+                SequenceRange { source_id: 2, first_sequence_number: 0, last_sequence_number: 19, count: 20 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_expected_join_sequence_numbers() {
+        let mut spec = spec(Some(JoinSpec { window_size: 4 }));
+        //windows [0, 4) and [4, 8) are closed by sequence number 9, the last window stays open
+        assert_eq!(spec.expected_sequence_numbers(1, |_| 10), Some(vec![0, 3, 6]));
+
+        //matches drawn with a selectivity are the same on both sources
+        let rule = JoinKeyRule::Selectivity { selectivity: 0.5, seed: 3 };
+        for source in spec.sources.values_mut() {
+            source.join_keys = Some(rule);
+        }
+        let matching = (0..8).filter(|sequence_number| rule.is_match(*sequence_number)).collect::<Vec<_>>();
+        assert_eq!(spec.expected_sequence_numbers(2, |_| 10), Some(matching));
+
+        //without the join keys of its partner the output of a source is not modelled
+        spec.sources.get_mut(&2).unwrap().join_keys = None;
+        let mut analyzer = CompletenessAnalyzer::new(spec);
+        analyzer.record(1, 0, 0);
+        let report = analyzer.report(&[], DEFAULT_BUCKET_WIDTH);
+        assert_eq!(report.unmodelled_sources, vec![1, 2]);
+        assert_eq!((report.expected_tuples, report.unexpected_tuples), (0, 0));
+    }
+}
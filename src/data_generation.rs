@@ -0,0 +1,433 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::tuple_schema::{generated_source_fields, FieldType, SourceField};
+
+//describes the tuples the tcp input servers emit. every field is written as 8 little endian bytes in the order of the
+//field list. the logical sources, the queries and the sink decoding use the fixed layouts of tuple_schema, so a spec
+//has to list exactly the fields of that layout with their types. what a spec configures is how the values of these
+//fields are generated: their distributions, the join key selectivity and the event time skew and disorder
+
+//join key assigned to matching tuples, the legacy generator used the same factor
+pub const JOIN_KEY_FACTOR: u64 = 1000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind")]
+pub enum ValueDistribution {
+    Constant {
+        value: f64,
+    },
+    //the id the input server assigned to the connection
+    SourceId,
+    //the sequence number of the tuple scaled by step and shifted by start
+    Sequential {
+        #[serde(default)]
+        start: u64,
+        #[serde(default = "default_step")]
+        step: u64,
+    },
+    Uniform {
+        min: f64,
+        max: f64,
+    },
+    //ranks 1..=elements drawn with probability proportional to 1 / rank^exponent
+    Zipf {
+        elements: u64,
+        exponent: f64,
+    },
+    //sequence_number * JOIN_KEY_FACTOR for matching tuples, the source id for all others. tuples match either every
+    //match_interval sequence numbers or with probability selectivity. the choice only depends on the sequence number and
+    //the seed, so all sources pick the same tuples
+    JoinKey {
+        match_interval: Option<u64>,
+        selectivity: Option<f64>,
+    },
+    //nanoseconds since the unix epoch with the skew and disorder of the spec applied
+    EventTime,
+}
+
+fn default_step() -> u64 {
+    1
+}
+
+//decides which tuples of a JoinKey field carry a matching join key. the interval wins if both are set
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum JoinKeyRule {
+    Interval(u64),
+    Selectivity { selectivity: f64, seed: u64 },
+}
+
+impl JoinKeyRule {
+    pub fn new(match_interval: Option<u64>, selectivity: Option<f64>, seed: u64) -> Self {
+        match match_interval {
+            Some(interval) => JoinKeyRule::Interval(interval),
+            None => JoinKeyRule::Selectivity { selectivity: selectivity.unwrap_or(0.0), seed },
+        }
+    }
+
+    pub fn is_match(&self, sequence_number: u64) -> bool {
+        match self {
+            JoinKeyRule::Interval(interval) => sequence_number.is_multiple_of((*interval).max(1)),
+            JoinKeyRule::Selectivity { selectivity, seed } => unit_hash(*seed, sequence_number) < *selectivity,
+        }
+    }
+
+    //sequence_number * JOIN_KEY_FACTOR for matching tuples, the source id for all others
+    pub fn join_key(&self, source_id: u64, sequence_number: u64) -> u64 {
+        if self.is_match(sequence_number) {
+            sequence_number * JOIN_KEY_FACTOR
+        } else {
+            source_id
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FieldSpec {
+    pub name: String,
+    pub field_type: FieldType,
+    pub distribution: ValueDistribution,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct EventTimeSpec {
+    //offset added to the event times of source n as (n - 1) * skew_ms, so the sources drift apart
+    #[serde(default)]
+    pub skew_ms: i64,
+    //fraction of tuples whose event time is moved into the past to make them arrive out of order
+    #[serde(default)]
+    pub out_of_order_fraction: f64,
+    #[serde(default)]
+    pub max_delay_ms: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DataGenerationSpec {
+    pub fields: Vec<FieldSpec>,
+    #[serde(default)]
+    pub event_time: EventTimeSpec,
+    //random if not set
+    pub seed: Option<u64>,
+}
+
+impl DataGenerationSpec {
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let spec: Self = toml::from_str(&fs::read_to_string(path)?)?;
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if self.fields.is_empty() {
+            return Err("data generation spec does not contain any fields".into());
+        }
+        for field in &self.fields {
+            match &field.distribution {
+                ValueDistribution::Uniform { min, max } if min > max => {
+                    return Err(format!("field {}: uniform minimum {} is larger than maximum {}", field.name, min, max).into())
+                }
+                ValueDistribution::Zipf { elements: 0, .. } => {
+                    return Err(format!("field {}: zipf distribution needs at least one element", field.name).into())
+                }
+                ValueDistribution::JoinKey { match_interval: None, selectivity: None } => {
+                    return Err(format!("field {}: join key needs a match interval or a selectivity", field.name).into())
+                }
+                ValueDistribution::JoinKey { selectivity: Some(selectivity), .. } if !(0.0..=1.0).contains(selectivity) => {
+                    return Err(format!("field {}: join key selectivity {} is not between 0 and 1", field.name, selectivity).into())
+                }
+                _ => {}
+            }
+        }
+        if !(0.0..=1.0).contains(&self.event_time.out_of_order_fraction) {
+            return Err("out of order fraction has to be between 0 and 1".into());
+        }
+        Ok(())
+    }
+
+    //the field names and types are not configurable, they have to be the generated fields of the logical source the
+    //tuples are ingested into. only the distributions of a spec may differ from the default layout
+    pub fn validate_source_fields(&self, source_fields: &[SourceField]) -> Result<(), Box<dyn Error>> {
+        let expected = generated_source_fields(source_fields);
+        let matches = self.fields.len() == expected.len()
+            && self
+                .fields
+                .iter()
+                .zip(expected)
+                .all(|(field, expected)| field.name == expected.name && field.field_type == expected.field_type);
+        if !matches {
+            let names = expected.iter().map(|field| field.name).collect::<Vec<_>>();
+            return Err(format!("data generation spec fields do not match the source fields {:?}", names).into());
+        }
+        Ok(())
+    }
+
+    //id, sequence number and event time as written by the stateless input server before specs existed
+    pub fn default_stateless() -> Self {
+        Self {
+            fields: vec![
+                FieldSpec {
+                    name: "id".to_string(),
+                    field_type: FieldType::UINT64,
+                    distribution: ValueDistribution::SourceId,
+                },
+                FieldSpec {
+                    name: "value".to_string(),
+                    field_type: FieldType::UINT64,
+                    distribution: ValueDistribution::Sequential { start: 0, step: 1 },
+                },
+                FieldSpec {
+                    name: "ingestion_timestamp".to_string(),
+                    field_type: FieldType::UINT64,
+                    distribution: ValueDistribution::EventTime,
+                },
+            ],
+            event_time: EventTimeSpec::default(),
+            seed: None,
+        }
+    }
+
+    //the stateless layout with a join key matching every join_match_interval tuples after the id
+    pub fn default_join(join_match_interval: u64) -> Self {
+        let mut spec = Self::default_stateless();
+        spec.fields[2].name = "event_timestamp".to_string();
+        spec.fields.insert(
+            1,
+            FieldSpec {
+                name: "join_id".to_string(),
+                field_type: FieldType::UINT64,
+                distribution: ValueDistribution::JoinKey {
+                    match_interval: Some(join_match_interval),
+                    selectivity: None,
+                },
+            },
+        );
+        spec
+    }
+
+    //all connections of an input server have to share the seed for random join keys to match, so it is fixed once
+    //before the first generator is created
+    pub fn resolve_seed(&mut self) -> u64 {
+        *self.seed.get_or_insert_with(|| rand::thread_rng().gen())
+    }
+
+    pub fn tuple_size(&self) -> usize {
+        self.fields.len() * std::mem::size_of::<u64>()
+    }
+
+    pub fn field_types(&self) -> Vec<FieldType> {
+        self.fields.iter().map(|field| field.field_type).collect()
+    }
+
+    //the rule of the join key field, none if the spec has none or draws the matches with a seed that is not resolved
+    pub fn join_key_rule(&self) -> Option<JoinKeyRule> {
+        self.fields.iter().find_map(|field| match field.distribution {
+            ValueDistribution::JoinKey { match_interval: None, selectivity } => {
+                Some(JoinKeyRule::new(None, selectivity, self.seed?))
+            }
+            ValueDistribution::JoinKey { match_interval, selectivity } => {
+                Some(JoinKeyRule::new(match_interval, selectivity, self.seed.unwrap_or_default()))
+            }
+            _ => None,
+        })
+    }
+}
+
+//maps the seed and a sequence number to a number in [0, 1), the same on every source
+fn unit_hash(seed: u64, sequence_number: u64) -> f64 {
+    //splitmix64 finalizer
+    let mut z = seed ^ sequence_number.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z >> 11) as f64 / (1u64 << 53) as f64
+}
+
+//generates the tuples of one source connection
+pub struct TupleGenerator {
+    spec: DataGenerationSpec,
+    source_id: u64,
+    seed: u64,
+    sequence_number: u64,
+    rng: StdRng,
+    //cumulative probabilities of the zipf fields, indexed like the field list
+    zipf_tables: Vec<Option<Vec<f64>>>,
+}
+
+impl TupleGenerator {
+    pub fn new(mut spec: DataGenerationSpec, source_id: u64) -> Self {
+        let seed = spec.resolve_seed();
+        let zipf_tables = spec
+            .fields
+            .iter()
+            .map(|field| match field.distribution {
+                ValueDistribution::Zipf { elements, exponent } => {
+                    let weights = (1..=elements).map(|rank| 1.0 / (rank as f64).powf(exponent)).collect::<Vec<_>>();
+                    let total: f64 = weights.iter().sum();
+                    let mut cumulative = 0.0;
+                    Some(
+                        weights
+                            .iter()
+                            .map(|weight| {
+                                cumulative += weight / total;
+                                cumulative
+                            })
+                            .collect(),
+                    )
+                }
+                _ => None,
+            })
+            .collect();
+        Self {
+            spec,
+            source_id,
+            seed,
+            sequence_number: 0,
+            rng: StdRng::seed_from_u64(seed ^ source_id),
+            zipf_tables,
+        }
+    }
+
+    pub fn sequence_number(&self) -> u64 {
+        self.sequence_number
+    }
+
+    fn event_time(&mut self, now_ns: u64) -> u64 {
+        let event_time = &self.spec.event_time;
+        let skew_ns = event_time.skew_ms * self.source_id.saturating_sub(1) as i64 * 1_000_000;
+        let mut time = (now_ns as i64 + skew_ns).max(0) as u64;
+        if event_time.out_of_order_fraction > 0.0 && self.rng.gen_bool(event_time.out_of_order_fraction) {
+            let delay_ns = self.rng.gen_range(0..=event_time.max_delay_ms) * 1_000_000;
+            time = time.saturating_sub(delay_ns);
+        }
+        time
+    }
+
+    //ids, sequence numbers, keys and timestamps are computed as integers so they do not lose precision as f64
+    fn integer_value(&mut self, field_index: usize, now_ns: u64) -> Option<u64> {
+        match self.spec.fields[field_index].distribution {
+            ValueDistribution::SourceId => Some(self.source_id),
+            ValueDistribution::Sequential { start, step } => Some(start + self.sequence_number * step),
+            ValueDistribution::JoinKey { match_interval, selectivity } => {
+                Some(JoinKeyRule::new(match_interval, selectivity, self.seed).join_key(self.source_id, self.sequence_number))
+            }
+            ValueDistribution::EventTime => Some(self.event_time(now_ns)),
+            _ => None,
+        }
+    }
+
+    fn sampled_value(&mut self, field_index: usize) -> f64 {
+        match self.spec.fields[field_index].distribution {
+            ValueDistribution::Constant { value } => value,
+            ValueDistribution::Uniform { min, max } if min == max => min,
+            ValueDistribution::Uniform { min, max } => self.rng.gen_range(min..max),
+            ValueDistribution::Zipf { .. } => {
+                let table = self.zipf_tables[field_index].as_ref().expect("zipf table missing");
+                let sample: f64 = self.rng.gen();
+                (table.partition_point(|cumulative| *cumulative < sample).min(table.len() - 1) + 1) as f64
+            }
+            _ => unreachable!("integer distributions are not sampled"),
+        }
+    }
+
+    pub fn write_tuple(&mut self, now_ns: u64, output: &mut Vec<u8>) {
+        for field_index in 0..self.spec.fields.len() {
+            let bytes = match (self.spec.fields[field_index].field_type, self.integer_value(field_index, now_ns)) {
+                (FieldType::UINT64, Some(value)) => value.to_le_bytes(),
+                (FieldType::UINT64, None) => (self.sampled_value(field_index).max(0.0) as u64).to_le_bytes(),
+                (FieldType::FLOAT64, Some(value)) => (value as f64).to_le_bytes(),
+                (FieldType::FLOAT64, None) => self.sampled_value(field_index).to_le_bytes(),
+            };
+            output.extend_from_slice(&bytes);
+        }
+        self.sequence_number += 1;
+    }
+
+    pub fn generate_buffer(&mut self, num_tuples: usize) -> Vec<u8> {
+        let mut buffer = Vec::with_capacity(num_tuples * self.spec.tuple_size());
+        for _ in 0..num_tuples {
+            let now_ns = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64;
+            self.write_tuple(now_ns, &mut buffer);
+        }
+        buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tuple_schema::{JOIN_SOURCE_FIELDS, STATELESS_SOURCE_FIELDS};
+
+    fn values(bytes: &[u8]) -> Vec<u64> {
+        bytes.chunks_exact(8).map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap())).collect()
+    }
+
+    #[test]
+    fn test_generated_tuples() {
+        //the default join spec keeps the layout the stateful input server always wrote
+        let mut generator = TupleGenerator::new(DataGenerationSpec::default_join(3), 2);
+        let mut output = vec![];
+        for _ in 0..4 {
+            generator.write_tuple(5_000, &mut output);
+        }
+        assert_eq!(
+            values(&output),
+            vec![2, 0, 0, 5_000, 2, 2, 1, 5_000, 2, 2, 2, 5_000, 2, 3_000, 3, 5_000]
+        );
+
+        //sources with the same seed select the same tuples to match on
+        let spec = DataGenerationSpec {
+            fields: vec![FieldSpec {
+                name: "join_id".to_string(),
+                field_type: FieldType::UINT64,
+                distribution: ValueDistribution::JoinKey { match_interval: None, selectivity: Some(0.3) },
+            }],
+            event_time: EventTimeSpec::default(),
+            seed: Some(7),
+        };
+        let keys = |source_id| {
+            let mut generator = TupleGenerator::new(spec.clone(), source_id);
+            let mut output = vec![];
+            for _ in 0..1000 {
+                generator.write_tuple(0, &mut output);
+            }
+            values(&output).into_iter().map(|key| key != source_id).collect::<Vec<_>>()
+        };
+        let matching = keys(1);
+        assert_eq!(matching, keys(2));
+        let rule = spec.join_key_rule().unwrap();
+        assert!((0..1000).all(|sequence_number| rule.is_match(sequence_number) == matching[sequence_number as usize]));
+        let fraction = matching.iter().filter(|matching| **matching).count() as f64 / 1000.0;
+        assert!((0.25..0.35).contains(&fraction));
+
+        let parsed: DataGenerationSpec = toml::from_str(
+            r#"
+            seed = 1
+            [event_time]
+            skew_ms = 5
+            [[fields]]
+            name = "value"
+            field_type = "FLOAT64"
+            distribution = { kind = "Zipf", elements = 10, exponent = 1.2 }
+            "#,
+        )
+        .unwrap();
+        assert!(parsed.validate().is_ok());
+        let mut out_of_range = spec.clone();
+        out_of_range.fields[0].distribution = ValueDistribution::JoinKey { match_interval: None, selectivity: Some(1.5) };
+        assert!(out_of_range.validate().is_err());
+        assert!(parsed.validate_source_fields(JOIN_SOURCE_FIELDS).is_err());
+        assert!(DataGenerationSpec::default_join(3).validate_source_fields(JOIN_SOURCE_FIELDS).is_ok());
+        assert!(DataGenerationSpec::default_stateless().validate_source_fields(STATELESS_SOURCE_FIELDS).is_ok());
+        let mut generator = TupleGenerator::new(parsed, 1);
+        let mut output = vec![];
+        generator.write_tuple(0, &mut output);
+        let value = f64::from_le_bytes(output[..8].try_into().unwrap());
+        assert!((1.0..=10.0).contains(&value));
+    }
+}
@@ -0,0 +1,435 @@
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use avro_rs::types::Value;
+use serde::{Deserialize, Serialize};
+
+use crate::tuple_schema::{generated_source_fields, FieldType, SourceField};
+
+//replays a recorded csv or avro dataset through the tcp input server instead of generating tuples. every connection
+//replays the dataset from the start, the fields are written as 8 little endian bytes in the order of the mapping
+
+//written to the folder of a configuration and passed to the input server
+pub const DATASET_REPLAY_FILE: &str = "dataset_replay.json";
+
+const AVRO_MAGIC: &[u8] = b"Obj\x01";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum DatasetFormat {
+    CSV,
+    AVRO,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum TimestampUnit {
+    NANOSECONDS,
+    MICROSECONDS,
+    MILLISECONDS,
+    SECONDS,
+}
+
+impl TimestampUnit {
+    fn to_nanos(self, value: f64) -> f64 {
+        match self {
+            TimestampUnit::NANOSECONDS => value,
+            TimestampUnit::MICROSECONDS => value * 1e3,
+            TimestampUnit::MILLISECONDS => value * 1e6,
+            TimestampUnit::SECONDS => value * 1e9,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind")]
+pub enum FieldValue {
+    Column { column: String },
+    Constant { value: f64 },
+    //the id the input server assigned to the connection
+    SourceId,
+    //position of the tuple in the replay of the connection, starting at zero
+    SequenceNumber,
+    //nanoseconds since the unix epoch at which the tuple is sent
+    EmissionTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FieldMapping {
+    pub name: String,
+    pub field_type: FieldType,
+    pub value: FieldValue,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum ReplayMode {
+    //keep the gaps between the timestamps of consecutive rows, multiplied by speedup_factor
+    #[default]
+    TIMED,
+    //send full buffers as fast as the connection accepts them
+    UNTHROTTLED,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DatasetReplaySpec {
+    pub path: PathBuf,
+    //detected from the file if not set
+    pub format: Option<DatasetFormat>,
+    pub fields: Vec<FieldMapping>,
+    //column with the original time of each row, required for timed replay
+    pub timestamp_column: Option<String>,
+    #[serde(default = "default_timestamp_unit")]
+    pub timestamp_unit: TimestampUnit,
+    #[serde(default)]
+    pub mode: ReplayMode,
+    //set by the runner from the speedup factor of the experiment
+    #[serde(default = "default_speedup_factor")]
+    pub speedup_factor: f64,
+}
+
+fn default_timestamp_unit() -> TimestampUnit {
+    TimestampUnit::MILLISECONDS
+}
+
+fn default_speedup_factor() -> f64 {
+    1.0
+}
+
+impl DatasetReplaySpec {
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let spec: Self = serde_json::from_str(&fs::read_to_string(path)?)?;
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    //checked by every path a spec is loaded on, a spec embedded in the input config is not read with from_file
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if self.fields.is_empty() {
+            return Err("dataset replay does not map any fields".into());
+        }
+        if self.mode == ReplayMode::TIMED && self.timestamp_column.is_none() {
+            return Err("timed replay needs a timestamp column".into());
+        }
+        Ok(())
+    }
+
+    //like a data generation spec, the mapping has to produce the generated fields of the logical source in order
+    pub fn validate_source_fields(&self, source_fields: &[SourceField]) -> Result<(), Box<dyn Error>> {
+        let expected = generated_source_fields(source_fields);
+        let matches = self.fields.len() == expected.len()
+            && self
+                .fields
+                .iter()
+                .zip(expected)
+                .all(|(field, expected)| field.name == expected.name && field.field_type == expected.field_type);
+        if !matches {
+            let names = expected.iter().map(|field| field.name).collect::<Vec<_>>();
+            return Err(format!("dataset replay fields do not match the source fields {:?}", names).into());
+        }
+        Ok(())
+    }
+
+    pub fn write_to_file(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    fn format(&self) -> Result<DatasetFormat, Box<dyn Error>> {
+        if let Some(format) = self.format {
+            return Ok(format);
+        }
+        let mut magic = [0u8; 4];
+        let read = File::open(&self.path)?.read(&mut magic)?;
+        Ok(if &magic[..read] == AVRO_MAGIC { DatasetFormat::AVRO } else { DatasetFormat::CSV })
+    }
+
+    //columns that have to be read from the dataset, the timestamp column last if it is set
+    fn columns(&self) -> Vec<&str> {
+        let mut columns = self
+            .fields
+            .iter()
+            .filter_map(|field| match &field.value {
+                FieldValue::Column { column } => Some(column.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        columns.extend(self.timestamp_column.as_deref());
+        columns
+    }
+}
+
+fn encode(value: f64, field_type: FieldType) -> [u8; 8] {
+    match field_type {
+        FieldType::UINT64 => (value.max(0.0) as u64).to_le_bytes(),
+        FieldType::FLOAT64 => value.to_le_bytes(),
+    }
+}
+
+fn parse_column_value(value: &str, field_type: FieldType) -> Result<[u8; 8], Box<dyn Error>> {
+    let value = value.trim();
+    //integers are parsed directly so large ids and timestamps keep their precision
+    if field_type == FieldType::UINT64 {
+        if let Ok(value) = value.parse::<u64>() {
+            return Ok(value.to_le_bytes());
+        }
+    }
+    Ok(encode(value.parse::<f64>()?, field_type))
+}
+
+fn avro_value_to_string(value: &Value) -> Result<String, Box<dyn Error>> {
+    Ok(match value {
+        Value::Long(value) => value.to_string(),
+        Value::Int(value) => value.to_string(),
+        Value::Double(value) => value.to_string(),
+        Value::Float(value) => value.to_string(),
+        Value::Boolean(value) => (*value as u8).to_string(),
+        Value::String(value) => value.clone(),
+        Value::Union(value) => avro_value_to_string(value)?,
+        value => return Err(format!("unsupported avro value {:?}", value).into()),
+    })
+}
+
+//the values of the columns in the order of DatasetReplaySpec::columns
+fn read_raw_rows(spec: &DatasetReplaySpec) -> Result<Vec<Vec<String>>, Box<dyn Error>> {
+    let columns = spec.columns();
+    let mut rows = vec![];
+    match spec.format()? {
+        DatasetFormat::CSV => {
+            let mut reader = csv::Reader::from_path(&spec.path)?;
+            let headers = reader.headers()?.iter().map(|name| name.trim().to_string()).collect::<Vec<_>>();
+            let indices = columns
+                .iter()
+                .map(|column| headers.iter().position(|header| header == column).ok_or(format!("column {} not in dataset", column)))
+                .collect::<Result<Vec<_>, _>>()?;
+            for record in reader.records() {
+                let record = record?;
+                rows.push(indices.iter().map(|index| record[*index].to_string()).collect());
+            }
+        }
+        DatasetFormat::AVRO => {
+            let reader = avro_rs::Reader::new(BufReader::new(File::open(&spec.path)?))?;
+            for value in reader {
+                let Value::Record(fields) = value? else {
+                    return Err("expected avro records in dataset".into());
+                };
+                let row = columns
+                    .iter()
+                    .map(|column| match fields.iter().find(|(name, _)| name == column) {
+                        Some((_, value)) => avro_value_to_string(value),
+                        None => Err(format!("column {} not in dataset", column).into()),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                rows.push(row);
+            }
+        }
+    }
+    Ok(rows)
+}
+
+struct DatasetRow {
+    //time since the first row, already scaled by the speedup factor
+    offset: Duration,
+    //encoded values of the fields that do not change between connections
+    values: Vec<Option<[u8; 8]>>,
+}
+
+//a dataset loaded into memory, shared by all connections of the input server
+pub struct Dataset {
+    spec: DatasetReplaySpec,
+    rows: Vec<DatasetRow>,
+}
+
+impl Dataset {
+    pub fn load(spec: DatasetReplaySpec) -> Result<Self, Box<dyn Error>> {
+        spec.validate()?;
+        let raw_rows = read_raw_rows(&spec)?;
+        let mut first_time = None;
+        let mut rows = Vec::with_capacity(raw_rows.len());
+        for raw_row in raw_rows {
+            let mut columns = raw_row.iter();
+            let values = spec
+                .fields
+                .iter()
+                .map(|field| match &field.value {
+                    FieldValue::Column { .. } => parse_column_value(columns.next().unwrap(), field.field_type).map(Some),
+                    FieldValue::Constant { value } => Ok(Some(encode(*value, field.field_type))),
+                    _ => Ok(None),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let offset = match (&spec.timestamp_column, spec.mode) {
+                (Some(_), ReplayMode::TIMED) => {
+                    let time = spec.timestamp_unit.to_nanos(columns.next().unwrap().trim().parse::<f64>()?);
+                    let first_time = *first_time.get_or_insert(time);
+                    Duration::from_nanos(((time - first_time).max(0.0) * spec.speedup_factor) as u64)
+                }
+                _ => Duration::ZERO,
+            };
+            rows.push(DatasetRow { offset, values });
+        }
+        Ok(Self { spec, rows })
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn tuple_size(&self) -> usize {
+        self.spec.fields.len() * std::mem::size_of::<u64>()
+    }
+
+    pub fn field_types(&self) -> Vec<FieldType> {
+        self.spec.fields.iter().map(|field| field.field_type).collect()
+    }
+}
+
+//replays a dataset on one connection
+pub struct DatasetReplayer {
+    dataset: Arc<Dataset>,
+    source_id: u64,
+    next_row: usize,
+    //the replay stops after this many tuples even if the dataset has more rows
+    max_tuples: usize,
+}
+
+impl DatasetReplayer {
+    pub fn new(dataset: Arc<Dataset>, source_id: u64, max_tuples: usize) -> Self {
+        Self {
+            dataset,
+            source_id,
+            next_row: 0,
+            max_tuples,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_row >= self.dataset.len().min(self.max_tuples)
+    }
+
+    //time after the start of the replay at which the next row is due
+    pub fn next_due(&self) -> Option<Duration> {
+        if self.is_finished() {
+            None
+        } else {
+            Some(self.dataset.rows[self.next_row].offset)
+        }
+    }
+
+    //encode all rows that are due at elapsed, at most max_tuples of them
+    pub fn take_due(&mut self, elapsed: Duration, now_ns: u64, max_tuples: usize) -> Vec<u8> {
+        let mut buffer = Vec::with_capacity(max_tuples * self.dataset.tuple_size());
+        let mut taken = 0;
+        while taken < max_tuples && self.next_due().is_some_and(|due| due <= elapsed) {
+            let row = &self.dataset.rows[self.next_row];
+            for (field, value) in self.dataset.spec.fields.iter().zip(&row.values) {
+                let bytes = match (value, &field.value) {
+                    (Some(bytes), _) => *bytes,
+                    (None, FieldValue::SourceId) => encode_integer(self.source_id, field.field_type),
+                    (None, FieldValue::SequenceNumber) => encode_integer(self.next_row as u64, field.field_type),
+                    (None, _) => encode_integer(now_ns, field.field_type),
+                };
+                buffer.extend_from_slice(&bytes);
+            }
+            self.next_row += 1;
+            taken += 1;
+        }
+        buffer
+    }
+}
+
+fn encode_integer(value: u64, field_type: FieldType) -> [u8; 8] {
+    match field_type {
+        FieldType::UINT64 => value.to_le_bytes(),
+        FieldType::FLOAT64 => (value as f64).to_le_bytes(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tuple_schema::STATELESS_SOURCE_FIELDS;
+
+    fn values(bytes: &[u8]) -> Vec<u64> {
+        bytes.chunks_exact(8).map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap())).collect()
+    }
+
+    #[test]
+    fn test_timed_csv_replay() {
+        let path = std::env::temp_dir().join(format!("dataset_replay_test_{}.csv", std::process::id()));
+        fs::write(&path, "time,vehicle,speed\n1000,7,12.5\n1000,8,3\n1500,7,13\n").unwrap();
+        let spec = DatasetReplaySpec {
+            path: path.clone(),
+            format: None,
+            fields: vec![
+                FieldMapping { name: "id".to_string(), field_type: FieldType::UINT64, value: FieldValue::SourceId },
+                FieldMapping {
+                    name: "vehicle".to_string(),
+                    field_type: FieldType::UINT64,
+                    value: FieldValue::Column { column: "vehicle".to_string() },
+                },
+                FieldMapping {
+                    name: "value".to_string(),
+                    field_type: FieldType::UINT64,
+                    value: FieldValue::SequenceNumber,
+                },
+                FieldMapping {
+                    name: "speed".to_string(),
+                    field_type: FieldType::FLOAT64,
+                    value: FieldValue::Column { column: "speed".to_string() },
+                },
+            ],
+            timestamp_column: Some("time".to_string()),
+            timestamp_unit: TimestampUnit::MILLISECONDS,
+            mode: ReplayMode::TIMED,
+            speedup_factor: 2.0,
+        };
+        assert!(spec.validate_source_fields(STATELESS_SOURCE_FIELDS).is_err());
+        let untimed = DatasetReplaySpec { timestamp_column: None, ..spec.clone() };
+        assert!(Dataset::load(untimed).is_err());
+        let dataset = Arc::new(Dataset::load(spec).unwrap());
+        fs::remove_file(&path).unwrap();
+        assert_eq!(dataset.len(), 3);
+
+        let mut replayer = DatasetReplayer::new(dataset, 3, 10);
+        let first = values(&replayer.take_due(Duration::ZERO, 0, 10));
+        assert_eq!(first.len(), 8);
+        assert_eq!(&first[..3], &[3, 7, 0]);
+        assert_eq!(f64::from_bits(first[3]), 12.5);
+        assert_eq!(&first[4..7], &[3, 8, 1]);
+
+        //the gap of 500ms is doubled by the speedup factor
+        assert_eq!(replayer.next_due(), Some(Duration::from_secs(1)));
+        assert!(replayer.take_due(Duration::from_millis(999), 0, 10).is_empty());
+        assert_eq!(values(&replayer.take_due(Duration::from_secs(1), 0, 10))[..3], [3, 7, 2]);
+        assert!(replayer.is_finished());
+    }
+
+    #[test]
+    fn test_mapping_matches_source_fields() {
+        let field = |name: &str, value| FieldMapping { name: name.to_string(), field_type: FieldType::UINT64, value };
+        let spec = DatasetReplaySpec {
+            path: PathBuf::from("unused.csv"),
+            format: Some(DatasetFormat::CSV),
+            fields: vec![
+                field("id", FieldValue::SourceId),
+                field("value", FieldValue::SequenceNumber),
+                field("ingestion_timestamp", FieldValue::EmissionTime),
+            ],
+            timestamp_column: None,
+            timestamp_unit: TimestampUnit::MILLISECONDS,
+            mode: ReplayMode::UNTHROTTLED,
+            speedup_factor: 1.0,
+        };
+        assert!(spec.validate().is_ok());
+        assert!(spec.validate_source_fields(STATELESS_SOURCE_FIELDS).is_ok());
+        let mut reordered = spec.clone();
+        reordered.fields.swap(0, 1);
+        assert!(reordered.validate_source_fields(STATELESS_SOURCE_FIELDS).is_err());
+        assert!(DatasetReplaySpec { mode: ReplayMode::TIMED, ..spec }.validate().is_err());
+    }
+}
@@ -0,0 +1,193 @@
+use std::future::Future;
+use std::io;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+
+use crate::ingest_stats::EmissionControl;
+use crate::source_protocol::TupleEncoding;
+
+//buffers of a connection pass through a bounded channel to the task writing the socket. when the sink stops reading,
+//for example during a migration, the policy decides what happens to buffers that do not fit into the channel
+
+pub const DEFAULT_CHANNEL_CAPACITY: usize = 16;
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum BackpressurePolicy {
+    //wait until the channel has room, the tuples keep the timestamps of their generation
+    #[default]
+    BLOCK,
+    //drop buffers that do not fit into the channel and count them in the ingest statistics
+    DROP,
+    //skip the channel and generate every buffer right before writing it, so the timestamps reflect when it left
+    TIMESTAMP_ON_SEND,
+}
+
+impl FromStr for BackpressurePolicy {
+    type Err = String;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy {
+            "BLOCK" => Ok(BackpressurePolicy::BLOCK),
+            "DROP" => Ok(BackpressurePolicy::DROP),
+            "TIMESTAMP_ON_SEND" => Ok(BackpressurePolicy::TIMESTAMP_ON_SEND),
+            policy => Err(format!("unknown backpressure policy {}", policy)),
+        }
+    }
+}
+
+//a connection to a source, written one complete message at a time
+pub trait MessageWriter: Send + 'static {
+    fn write_message(&mut self, message: &[u8]) -> impl Future<Output = io::Result<()>> + Send;
+    fn close(&mut self) -> impl Future<Output = ()> + Send;
+}
+
+impl<W: AsyncWrite + Unpin + Send + 'static> MessageWriter for W {
+    async fn write_message(&mut self, message: &[u8]) -> io::Result<()> {
+        self.write_all(message).await
+    }
+
+    async fn close(&mut self) {
+        let _ = self.shutdown().await;
+    }
+}
+
+struct OutgoingBuffer {
+    data: Vec<u8>,
+    //time since the unix epoch at which the emission schedule wanted the buffer to leave
+    scheduled_at: Duration,
+}
+
+enum Output<W> {
+    Channel(Sender<OutgoingBuffer>),
+    Direct(W),
+}
+
+//hands the buffers of one connection to its socket and records what was written, dropped and how late it was
+pub struct EmissionPipeline<W: MessageWriter> {
+    policy: BackpressurePolicy,
+    output: Output<W>,
+    control: Arc<EmissionControl>,
+    connection: usize,
+    encoding: TupleEncoding,
+}
+
+impl<W: MessageWriter> EmissionPipeline<W> {
+    //has to be called from within a tokio runtime, which runs the writer task
+    pub fn new(
+        writer: W,
+        policy: BackpressurePolicy,
+        channel_capacity: usize,
+        control: Arc<EmissionControl>,
+        connection: usize,
+        encoding: TupleEncoding,
+    ) -> Self {
+        let output = match policy {
+            BackpressurePolicy::TIMESTAMP_ON_SEND => Output::Direct(writer),
+            BackpressurePolicy::BLOCK | BackpressurePolicy::DROP => {
+                let (sender, receiver) = channel(channel_capacity.max(1));
+                tokio::spawn(write_buffers(writer, receiver, control.clone(), connection, encoding.clone()));
+                Output::Channel(sender)
+            }
+        };
+        Self {
+            policy,
+            output,
+            control,
+            connection,
+            encoding,
+        }
+    }
+
+    //generate the buffer scheduled for the given time and pass it on. returns false once the socket failed
+    pub async fn emit(&mut self, scheduled_at: Duration, generate: impl FnOnce() -> Vec<u8>) -> bool {
+        let data = generate();
+        match &mut self.output {
+            Output::Direct(writer) => write_buffer(writer, data, scheduled_at, &self.control, self.connection, &self.encoding).await,
+            Output::Channel(sender) => {
+                let buffer = OutgoingBuffer { data, scheduled_at };
+                match self.policy {
+                    BackpressurePolicy::DROP => match sender.try_send(buffer) {
+                        Ok(()) => true,
+                        Err(TrySendError::Full(buffer)) => {
+                            let tuples = self.encoding.tuple_count(&buffer.data);
+                            self.control.update_connection(self.connection, |stats| stats.record_drop(tuples));
+                            true
+                        }
+                        Err(TrySendError::Closed(_)) => false,
+                    },
+                    _ => sender.send(buffer).await.is_ok(),
+                }
+            }
+        }
+    }
+}
+
+async fn write_buffers<W: MessageWriter>(mut writer: W, mut receiver: Receiver<OutgoingBuffer>, control: Arc<EmissionControl>, connection: usize, encoding: TupleEncoding) {
+    while let Some(buffer) = receiver.recv().await {
+        if !write_buffer(&mut writer, buffer.data, buffer.scheduled_at, &control, connection, &encoding).await {
+            break;
+        }
+    }
+    writer.close().await;
+}
+
+async fn write_buffer<W: MessageWriter>(writer: &mut W, data: Vec<u8>, scheduled_at: Duration, control: &EmissionControl, connection: usize, encoding: &TupleEncoding) -> bool {
+    let tuples = encoding.tuple_count(&data);
+    let mut bytes = 0;
+    for message in encoding.encode(data) {
+        if let Err(err) = writer.write_message(&message).await {
+            eprintln!("Error writing to socket: {}", err);
+            control.update_connection(connection, |stats| stats.write_errors += 1);
+            return false;
+        }
+        bytes += message.len() as u64;
+    }
+    let written_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    control.update_connection(connection, |stats| {
+        stats.record_write(tuples, bytes, written_at);
+        stats.record_lag(written_at.saturating_sub(scheduled_at));
+    });
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingest_stats::EmissionState;
+    use crate::tuple_schema::FieldType;
+    use tokio::io::AsyncReadExt;
+
+    #[test]
+    fn test_drop_policy_counts_buffers_that_do_not_fit() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let control = Arc::new(EmissionControl::new(EmissionState::RUNNING));
+        let connection = control.add_connection(1);
+        let received = runtime.block_on(async {
+            //the socket takes a single buffer until the reader starts reading
+            let (writer, mut reader) = tokio::io::duplex(16);
+            let mut pipeline = EmissionPipeline::new(writer, BackpressurePolicy::DROP, 2, control.clone(), connection, TupleEncoding::binary(vec![FieldType::UINT64]));
+            let scheduled_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+            for _ in 0..10 {
+                assert!(pipeline.emit(scheduled_at, || vec![0; 16]).await);
+            }
+            drop(pipeline);
+            let mut received = vec![];
+            reader.read_to_end(&mut received).await.unwrap();
+            received.len()
+        });
+
+        let stats = control.stats().connections[0].clone();
+        //at most one buffer is written, one is held by the blocked writer and two wait in the channel
+        assert!(stats.buffers_dropped >= 6);
+        assert_eq!(received as u64, stats.bytes_sent);
+        assert_eq!(stats.tuples_sent + stats.tuples_dropped, 20);
+        assert_eq!(stats.tuples_dropped, stats.buffers_dropped * 2);
+    }
+}
@@ -0,0 +1,142 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use tokio::sync::oneshot;
+
+//drives the emission of all connections of an input server. a single thread keeps the next emission time of every
+//connection in a heap, sleeps until shortly before the earliest one and spins for the rest. the tokio timer only has
+//millisecond resolution, which is too coarse for short gathering intervals
+
+//remaining time below which the timer thread spins instead of sleeping
+const SPIN_THRESHOLD: Duration = Duration::from_millis(1);
+
+struct TimerRequest {
+    due: Instant,
+    wake: oneshot::Sender<()>,
+}
+
+//ordered by due time, reversed so that the binary heap returns the earliest request first
+impl Ord for TimerRequest {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.due.cmp(&self.due)
+    }
+}
+
+impl PartialOrd for TimerRequest {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for TimerRequest {
+    fn eq(&self, other: &Self) -> bool {
+        self.due == other.due
+    }
+}
+
+impl Eq for TimerRequest {}
+
+#[derive(Clone)]
+pub struct EmissionTimer {
+    requests: Sender<TimerRequest>,
+}
+
+impl EmissionTimer {
+    //the timer thread stops once all clones of the timer are dropped
+    pub fn start() -> Self {
+        let (requests, receiver) = channel();
+        thread::spawn(move || run_timer(receiver));
+        Self { requests }
+    }
+
+    //wait until the given time since the unix epoch
+    pub async fn sleep_until(&self, time: Duration) {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        if time <= now {
+            return;
+        }
+        let (wake, woken) = oneshot::channel();
+        let request = TimerRequest {
+            due: Instant::now() + (time - now),
+            wake,
+        };
+        if self.requests.send(request).is_ok() {
+            let _ = woken.await;
+        }
+    }
+}
+
+fn run_timer(requests: Receiver<TimerRequest>) {
+    let mut pending: BinaryHeap<TimerRequest> = BinaryHeap::new();
+    loop {
+        let now = Instant::now();
+        while pending.peek().is_some_and(|request| request.due <= now) {
+            //the connection might have been closed in the meantime
+            let _ = pending.pop().unwrap().wake.send(());
+        }
+        let request = match pending.peek() {
+            None => match requests.recv() {
+                Ok(request) => request,
+                Err(_) => return,
+            },
+            Some(next) => {
+                let remaining = next.due.saturating_duration_since(Instant::now());
+                if remaining > SPIN_THRESHOLD {
+                    match requests.recv_timeout(remaining - SPIN_THRESHOLD) {
+                        Ok(request) => request,
+                        Err(RecvTimeoutError::Timeout) => continue,
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                } else {
+                    match requests.try_recv() {
+                        Ok(request) => request,
+                        Err(TryRecvError::Empty) => {
+                            //yield instead of a busy loop, so the runtime threads are not starved on small machines
+                            thread::yield_now();
+                            continue;
+                        }
+                        Err(TryRecvError::Disconnected) => return,
+                    }
+                }
+            }
+        };
+        pending.push(request);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connections_are_woken_on_time() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let timer = EmissionTimer::start();
+        let start = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let wake_ups = runtime.block_on(async {
+            let tasks = (0..1000u64)
+                .map(|connection| {
+                    let timer = timer.clone();
+                    //connections are registered in reverse order of their emission times
+                    let due = start + Duration::from_millis(50) + Duration::from_micros(100 * (1000 - connection));
+                    tokio::spawn(async move {
+                        timer.sleep_until(due).await;
+                        let woken = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+                        (due, woken)
+                    })
+                })
+                .collect::<Vec<_>>();
+            let mut wake_ups = vec![];
+            for task in tasks {
+                wake_ups.push(task.await.unwrap());
+            }
+            wake_ups
+        });
+        let mut lags = wake_ups.iter().map(|(due, woken)| woken.checked_sub(*due).expect("woken early")).collect::<Vec<_>>();
+        lags.sort();
+        assert!(lags[lags.len() / 2] < Duration::from_millis(1));
+    }
+}
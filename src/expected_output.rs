@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use simulation_runner_rs::data_generation::JoinKeyRule;
+
+//models the data of the stateful input server with a join key field (ValueDistribution::JoinKey) and the tumbling
+//window join over the sequence number, so the output of a join query can be computed exactly
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct JoinInput {
+    pub source_id: u64,
+    //number of tuples the source emits, sequence numbers run from zero to tuple_count - 1
+    pub tuple_count: u64,
+    //the rule of the data generation spec of the source
+    pub join_keys: JoinKeyRule,
+}
+
+impl JoinInput {
+    fn join_id(&self, sequence_number: u64) -> u64 {
+        self.join_keys.join_key(self.source_id, sequence_number)
+    }
+}
+
+//one output tuple of the join without the timestamps, which depend on the run
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ExpectedJoinTuple {
+    pub win_start: u64,
+    pub win_end: u64,
+    pub id_1: u64,
+    pub sequence_number_1: u64,
+    pub id_2: u64,
+    pub sequence_number_2: u64,
+}
+
+//a join of two sources with tumbling windows of window_size sequence numbers
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct JoinPairModel {
+    pub left: JoinInput,
+    pub right: JoinInput,
+    pub window_size: u64,
+}
+
+impl JoinPairModel {
+    //window k covers the sequence numbers [k * window_size, (k + 1) * window_size) and is only emitted once both inputs
+    //ingested a tuple with a sequence number of at least its end
+    pub fn finished_windows(&self) -> u64 {
+        if self.window_size == 0 || self.left.tuple_count == 0 || self.right.tuple_count == 0 {
+            return 0;
+        }
+        let last_sequence_number = self.left.tuple_count.min(self.right.tuple_count) - 1;
+        last_sequence_number / self.window_size
+    }
+
+    fn window_bounds(&self, window: u64) -> (u64, u64) {
+        (window * self.window_size, (window + 1) * self.window_size)
+    }
+
+    //join ids of the right input in the window with the sequence numbers carrying them
+    fn right_join_ids(&self, window: u64) -> HashMap<u64, Vec<u64>> {
+        let (start, end) = self.window_bounds(window);
+        let mut join_ids: HashMap<u64, Vec<u64>> = HashMap::new();
+        for sequence_number in start..end {
+            join_ids
+                .entry(self.right.join_id(sequence_number))
+                .or_default()
+                .push(sequence_number);
+        }
+        join_ids
+    }
+
+    pub fn expected_count(&self) -> u64 {
+        (0..self.finished_windows())
+            .map(|window| {
+                let right = self.right_join_ids(window);
+                let (start, end) = self.window_bounds(window);
+                (start..end)
+                    .filter_map(|sequence_number| {
+                        right.get(&self.left.join_id(sequence_number))
+                    })
+                    .map(|matches| matches.len() as u64)
+                    .sum::<u64>()
+            })
+            .sum()
+    }
+
+    pub fn expected_tuples(&self) -> Vec<ExpectedJoinTuple> {
+        let mut tuples = vec![];
+        for window in 0..self.finished_windows() {
+            let right = self.right_join_ids(window);
+            let (start, end) = self.window_bounds(window);
+            for sequence_number_1 in start..end {
+                let Some(matches) = right.get(&self.left.join_id(sequence_number_1)) else {
+                    continue;
+                };
+                for sequence_number_2 in matches {
+                    tuples.push(ExpectedJoinTuple {
+                        win_start: start,
+                        win_end: end,
+                        id_1: self.left.source_id,
+                        sequence_number_1,
+                        id_2: self.right.source_id,
+                        sequence_number_2: *sequence_number_2,
+                    });
+                }
+            }
+        }
+        tuples
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(left_tuples: u64, right_tuples: u64, window_size: u64, join_match_interval: u64) -> JoinPairModel {
+        let join_keys = JoinKeyRule::Interval(join_match_interval);
+        JoinPairModel {
+            left: JoinInput { source_id: 1, tuple_count: left_tuples, join_keys },
+            right: JoinInput { source_id: 2, tuple_count: right_tuples, join_keys },
+            window_size,
+        }
+    }
+
+    #[test]
+    fn test_expected_join_output() {
+        //no window is finished if the inputs end inside the first window
+        assert_eq!(model(10, 10, 10, 2).expected_count(), 0);
+        assert_eq!(model(0, 10, 10, 2).expected_count(), 0);
+        assert_eq!(model(11, 11, 10, 2).expected_count(), 5);
+
+        //the shorter input decides which windows are emitted
+        assert_eq!(model(300, 25, 10, 2).finished_windows(), 2);
+        assert_eq!(model(300, 25, 10, 2).expected_count(), 10);
+
+        let pair = model(9, 9, 4, 3);
+        let tuples = pair.expected_tuples();
+        assert_eq!(
+            tuples.iter().map(|tuple| (tuple.win_start, tuple.sequence_number_1)).collect::<Vec<_>>(),
+            vec![(0, 0), (0, 3), (4, 6)]
+        );
+        assert_eq!(tuples.len() as u64, pair.expected_count());
+
+        //a source id that is a multiple of the join id factor matches the tuples of the other input carrying that id
+        let colliding = JoinPairModel {
+            left: JoinInput { source_id: 3000, ..pair.left },
+            ..pair
+        };
+        assert_eq!(colliding.expected_count(), 5);
+    }
+}
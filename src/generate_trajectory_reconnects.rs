@@ -0,0 +1,72 @@
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use simulation_runner_rs::topology::{FixedTopology, WorkerIds};
+use simulation_runner_rs::trajectory_reconnects::{generate_reconnects, read_trajectory, TrajectoryReconnectConfig};
+
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a String> {
+    args.iter().position(|arg| arg == flag).and_then(|index| args.get(index + 1))
+}
+
+fn parse_flag<T: std::str::FromStr>(args: &[String], flag: &str) -> Result<Option<T>, Box<dyn Error>>
+where
+    T::Err: std::fmt::Display,
+{
+    flag_value(args, flag)
+        .map(|value| value.parse::<T>().map_err(|e| format!("invalid value for {}: {}", flag, e).into()))
+        .transpose()
+}
+
+fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let topology: FixedTopology = serde_json::from_str(&fs::read_to_string(&args[1])?)?;
+
+    //the mobile workers are created in the order of their trace files
+    let mut trajectory_paths = fs::read_dir(&args[2])?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    trajectory_paths.retain(|path| path.extension().is_some_and(|extension| extension == "csv"));
+    trajectory_paths.sort();
+    let trajectories = trajectory_paths.iter().map(|path| read_trajectory(path)).collect::<Result<Vec<_>, _>>()?;
+
+    let mut config = TrajectoryReconnectConfig::default();
+    if let Some(radius) = parse_flag(args, "--coverage-radius")? {
+        config.coverage_radius = radius;
+    }
+    if let Some(hysteresis) = parse_flag(args, "--hysteresis")? {
+        config.hysteresis = hysteresis;
+    }
+    if let Some(interval) = parse_flag(args, "--sample-interval")? {
+        config.sample_interval = Duration::from_millis(interval);
+    }
+    config.duration = parse_flag(args, "--duration")?.map(Duration::from_millis);
+    config.eligible_parents = flag_value(args, "--parents")
+        .map(|ids| ids.split(',').map(|id| id.trim().parse::<u64>().map_err(|e| format!("invalid parent id {}: {}", id, e))).collect::<Result<Vec<_>, _>>())
+        .transpose()?;
+
+    if args.iter().any(|arg| arg == "--stateless") {
+        config.worker_ids = WorkerIds::STATELESS;
+    }
+
+    let reconnects = generate_reconnects(&topology, &trajectories, &config)?;
+    for (path, (parent, child)) in trajectory_paths.iter().zip(&reconnects.initial_parents) {
+        println!("worker {} ({}) starts at parent {}", child, path.display(), parent);
+    }
+    println!("generated {} topology updates", reconnects.topology_updates.len());
+    fs::write(Path::new(&args[3]), serde_json::to_string_pretty(&reconnects)?)?;
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 4 {
+        eprintln!("Usage: {} <fixed topology json> <trajectory dir> <output json> [--coverage-radius <meters>] [--hysteresis <meters>] [--sample-interval <ms>] [--duration <ms>] [--parents <id,id,...>] [--stateless]", args[0]);
+        std::process::exit(1);
+    }
+    if let Err(e) = run(&args) {
+        eprintln!("Error generating reconnects: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+//the runner starts, pauses and stops the emission of the input server it hosts through EmissionControl and saves the
+//statistics of all connections after every attempt
+
+pub const INGEST_STATS_SUFFIX: &str = "ingest_stats.json";
+
+//buffers written later than this after their scheduled time count as late
+pub const LATE_BUFFER_THRESHOLD: Duration = Duration::from_millis(1);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum EmissionState {
+    //no buffers are emitted before the state is set to running
+    WAITING,
+    RUNNING,
+    PAUSED,
+    //final state, all connections stop emitting
+    STOPPED,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ConnectionIngestStats {
+    pub source_id: u64,
+    pub tuples_sent: u64,
+    pub bytes_sent: u64,
+    pub write_errors: u64,
+    //buffers discarded by the drop backpressure policy
+    pub buffers_dropped: u64,
+    pub tuples_dropped: u64,
+    //times since the unix epoch at which the first and the last buffer were written to the socket
+    pub first_buffer_ns: Option<u64>,
+    pub last_buffer_ns: Option<u64>,
+    //delay between the scheduled emission time of a buffer and the time it was written to the socket
+    pub late_buffers: u64,
+    pub max_lag_ns: u64,
+    pub total_lag_ns: u64,
+}
+
+impl ConnectionIngestStats {
+    pub fn new(source_id: u64) -> Self {
+        Self {
+            source_id,
+            tuples_sent: 0,
+            bytes_sent: 0,
+            write_errors: 0,
+            buffers_dropped: 0,
+            tuples_dropped: 0,
+            first_buffer_ns: None,
+            last_buffer_ns: None,
+            late_buffers: 0,
+            max_lag_ns: 0,
+            total_lag_ns: 0,
+        }
+    }
+
+    pub fn record_write(&mut self, tuples: u64, bytes: u64, written_at: Duration) {
+        self.tuples_sent += tuples;
+        self.bytes_sent += bytes;
+        let written_at_ns = written_at.as_nanos() as u64;
+        self.first_buffer_ns.get_or_insert(written_at_ns);
+        self.last_buffer_ns = Some(written_at_ns);
+    }
+
+    pub fn record_drop(&mut self, tuples: u64) {
+        self.buffers_dropped += 1;
+        self.tuples_dropped += tuples;
+    }
+
+    pub fn record_lag(&mut self, lag: Duration) {
+        let lag_ns = lag.as_nanos() as u64;
+        if lag > LATE_BUFFER_THRESHOLD {
+            self.late_buffers += 1;
+        }
+        self.max_lag_ns = self.max_lag_ns.max(lag_ns);
+        self.total_lag_ns += lag_ns;
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct IngestStats {
+    pub state: EmissionState,
+    pub connections: Vec<ConnectionIngestStats>,
+}
+
+impl IngestStats {
+    //number of sequence numbers generated per source, dropped tuples included so they show up as missing. a source
+    //that reconnects starts again at sequence number zero, so the connection that got furthest counts
+    pub fn emitted_by_source(&self) -> HashMap<u64, u64> {
+        let mut emitted = HashMap::new();
+        for connection in &self.connections {
+            let tuples = emitted.entry(connection.source_id).or_insert(0);
+            *tuples = (connection.tuples_sent + connection.tuples_dropped).max(*tuples);
+        }
+        emitted
+    }
+
+    pub fn total_tuples_sent(&self) -> u64 {
+        self.connections.iter().map(|connection| connection.tuples_sent).sum()
+    }
+}
+
+//emission state shared by all connections of the input server
+pub struct EmissionControl {
+    state: watch::Sender<EmissionState>,
+    connections: Mutex<Vec<ConnectionIngestStats>>,
+}
+
+impl EmissionControl {
+    pub fn new(initial_state: EmissionState) -> Self {
+        Self {
+            state: watch::channel(initial_state).0,
+            connections: Mutex::new(vec![]),
+        }
+    }
+
+    pub fn state(&self) -> EmissionState {
+        *self.state.borrow()
+    }
+
+    pub fn set_state(&self, state: EmissionState) {
+        self.state.send_if_modified(|current| {
+            let modified = *current != EmissionState::STOPPED && *current != state;
+            if modified {
+                *current = state;
+            }
+            modified
+        });
+    }
+
+    //waits while the emission is waiting or paused. returns the time spent waiting, or none once it is stopped
+    pub async fn wait_until_running(&self) -> Option<Duration> {
+        let mut wait_start: Option<Instant> = None;
+        let mut state = self.state.subscribe();
+        loop {
+            let current = *state.borrow_and_update();
+            match current {
+                EmissionState::RUNNING => return Some(wait_start.map_or(Duration::ZERO, |start| start.elapsed())),
+                EmissionState::STOPPED => return None,
+                EmissionState::WAITING | EmissionState::PAUSED => {
+                    wait_start.get_or_insert_with(Instant::now);
+                    state.changed().await.ok()?;
+                }
+            }
+        }
+    }
+
+    //register a new connection, the returned index identifies it in update_connection
+    pub fn add_connection(&self, source_id: u64) -> usize {
+        let mut connections = self.connections.lock().unwrap();
+        connections.push(ConnectionIngestStats::new(source_id));
+        connections.len() - 1
+    }
+
+    pub fn update_connection(&self, index: usize, update: impl FnOnce(&mut ConnectionIngestStats)) {
+        update(&mut self.connections.lock().unwrap()[index]);
+    }
+
+    pub fn stats(&self) -> IngestStats {
+        IngestStats {
+            state: self.state(),
+            connections: self.connections.lock().unwrap().clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_emission_control() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let control = Arc::new(EmissionControl::new(EmissionState::WAITING));
+
+        let first = control.add_connection(1);
+        let second = control.add_connection(1);
+        control.update_connection(first, |connection| connection.record_write(10, 160, Duration::from_secs(1)));
+        control.update_connection(second, |connection| connection.record_write(4, 64, Duration::from_secs(2)));
+
+        let emitter_control = control.clone();
+        let emitter = runtime.spawn(async move { emitter_control.wait_until_running().await.is_some() });
+
+        control.set_state(EmissionState::RUNNING);
+        assert_eq!(control.state(), EmissionState::RUNNING);
+        assert!(runtime.block_on(emitter).unwrap());
+
+        control.set_state(EmissionState::STOPPED);
+        let stats = control.stats();
+        assert_eq!(stats.state, EmissionState::STOPPED);
+        assert_eq!(stats.total_tuples_sent(), 14);
+        assert_eq!(stats.emitted_by_source(), HashMap::from([(1, 10)]));
+        assert_eq!(stats.connections[0].first_buffer_ns, Some(1_000_000_000));
+        //stopping is final
+        control.set_state(EmissionState::RUNNING);
+        assert_eq!(control.state(), EmissionState::STOPPED);
+        assert_eq!(runtime.block_on(control.wait_until_running()), None);
+    }
+}
@@ -0,0 +1,450 @@
+use std::error::Error;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tokio::net::{lookup_host, TcpListener};
+use tokio::task::{JoinHandle, JoinSet};
+
+use crate::data_generation::{DataGenerationSpec, TupleGenerator};
+use crate::dataset_replay::{Dataset, DatasetReplaySpec, DatasetReplayer};
+use crate::emission_pipeline::{BackpressurePolicy, EmissionPipeline, MessageWriter, DEFAULT_CHANNEL_CAPACITY};
+use crate::emission_timer::EmissionTimer;
+use crate::ingest_stats::{EmissionControl, EmissionState, IngestStats};
+use crate::input_sources::{read_input_sources, InputSourceConfig};
+use crate::mqtt_broker::MqttPublisher;
+use crate::rate_profile::{EmissionSchedule, RateProfile};
+use crate::source_protocol::{DatagramWriter, SourceProtocol, TupleEncoding, DEFAULT_TUPLE_SEPARATOR};
+
+//the input server emits the tuples of the sources to the nes workers, see source_protocol. the runner hosts it on its own
+//runtime, the tcp_input_server binaries run it standalone until they are killed
+
+pub struct InputServerConfig {
+    pub hostname: String,
+    //without a source list all sources connect to this port and get their ids in the order in which they connect
+    pub port: u16,
+    pub num_buffers: usize,
+    pub buffer_size: usize,
+    pub gathering_interval: Duration,
+    //time since the unix epoch at which the emission starts
+    pub deadline: Duration,
+    pub spec: DataGenerationSpec,
+    pub rate_profile: RateProfile,
+    pub dataset_replay: Option<DatasetReplaySpec>,
+    //every source of the list has its own port, protocol and input parameters
+    pub sources: Option<Vec<InputSourceConfig>>,
+    pub initial_state: EmissionState,
+    pub backpressure: BackpressurePolicy,
+    pub channel_capacity: usize,
+}
+
+impl InputServerConfig {
+    fn source_spec(&self, source: &InputSourceConfig) -> Result<DataGenerationSpec, Box<dyn Error>> {
+        let mut spec = match &source.data_generation_spec {
+            Some(path) => DataGenerationSpec::from_file(path)?,
+            None => self.spec.clone(),
+        };
+        //sources with their own spec use the seed of the default spec so that their join keys still match
+        if spec.seed.is_none() {
+            spec.seed = self.spec.seed;
+        }
+        Ok(spec)
+    }
+
+    //the specs the tuples of all sources are generated with
+    pub fn source_specs(&self) -> Result<Vec<DataGenerationSpec>, Box<dyn Error>> {
+        match &self.sources {
+            Some(sources) => sources.iter().map(|source| self.source_spec(source)).collect(),
+            None => Ok(vec![self.spec.clone()]),
+        }
+    }
+}
+
+impl InputServerConfig {
+    //parse the command line of the standalone server, the default spec is used without --data-generation
+    pub fn from_args(args: &[String], default_spec: DataGenerationSpec) -> Result<Self, Box<dyn Error>> {
+        if args.len() < 7 {
+            return Err("expected <hostname> <port> <num_buffers> <buffer_size> <gathering_interval> <deadline>".into());
+        }
+        let mut spec = match flag_value(args, "--data-generation") {
+            Some(path) => DataGenerationSpec::from_file(Path::new(path))?,
+            None => default_spec,
+        };
+        spec.resolve_seed();
+        let rate_profile = match flag_value(args, "--rate-profile") {
+            Some(path) => RateProfile::from_file(Path::new(path))?,
+            None => RateProfile::Constant,
+        };
+        let dataset_replay = flag_value(args, "--replay").map(|path| DatasetReplaySpec::from_file(Path::new(path))).transpose()?;
+        let sources = flag_value(args, "--sources").map(|path| read_input_sources(Path::new(path))).transpose()?;
+        let backpressure = match flag_value(args, "--backpressure") {
+            Some(policy) => policy.parse::<BackpressurePolicy>()?,
+            None => BackpressurePolicy::default(),
+        };
+        let channel_capacity = match flag_value(args, "--channel-capacity") {
+            Some(capacity) => capacity.parse::<usize>().map_err(|e| format!("invalid channel capacity: {}", e))?,
+            None => DEFAULT_CHANNEL_CAPACITY,
+        };
+        Ok(Self {
+            hostname: args[1].clone(),
+            port: args[2].parse().map_err(|e| format!("invalid port number: {}", e))?,
+            num_buffers: args[3].parse().map_err(|e| format!("invalid number of buffers: {}", e))?,
+            buffer_size: args[4].parse().map_err(|e| format!("invalid buffer size: {}", e))?,
+            gathering_interval: Duration::from_millis(args[5].parse().map_err(|e| format!("invalid gathering interval: {}", e))?),
+            deadline: Duration::from_millis(args[6].parse().map_err(|e| format!("invalid deadline: {}", e))?),
+            spec,
+            rate_profile,
+            dataset_replay,
+            sources,
+            initial_state: EmissionState::RUNNING,
+            backpressure,
+            channel_capacity,
+        })
+    }
+}
+
+//value following the flag among the optional arguments
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a String> {
+    args.iter().position(|arg| arg == flag).and_then(|index| args.get(index + 1))
+}
+
+//state shared by all connections of the server
+#[derive(Clone)]
+struct ServerContext {
+    deadline: Duration,
+    dataset: Option<Arc<Dataset>>,
+    control: Arc<EmissionControl>,
+    timer: EmissionTimer,
+    backpressure: BackpressurePolicy,
+    channel_capacity: usize,
+    //tasks of the accepted connections, aborted together with the listeners when the server stops
+    connection_tasks: Arc<Mutex<JoinSet<()>>>,
+}
+
+//input parameters of a single connection
+#[derive(Clone)]
+struct ConnectionInput {
+    id: u64,
+    num_buffers: usize,
+    buffer_size: usize,
+    gathering_interval: Duration,
+    spec: DataGenerationSpec,
+    rate_profile: RateProfile,
+    protocol: SourceProtocol,
+    tuple_separator: char,
+    //topic the tuples are published on if the source uses mqtt
+    topic: String,
+}
+
+pub struct InputServer {
+    control: Arc<EmissionControl>,
+    listener_tasks: Vec<JoinHandle<()>>,
+    connection_tasks: Arc<Mutex<JoinSet<()>>>,
+}
+
+impl InputServer {
+    //bind all ports and start accepting connections on the current tokio runtime
+    pub async fn start(config: InputServerConfig) -> Result<Self, Box<dyn Error>> {
+        let source_specs = config.source_specs()?;
+        let dataset = match config.dataset_replay {
+            Some(spec) => {
+                println!("Replaying dataset {}", spec.path.display());
+                Some(Arc::new(Dataset::load(spec)?))
+            }
+            None => None,
+        };
+        let context = ServerContext {
+            deadline: config.deadline,
+            dataset,
+            control: Arc::new(EmissionControl::new(config.initial_state)),
+            timer: EmissionTimer::start(),
+            backpressure: config.backpressure,
+            channel_capacity: config.channel_capacity,
+            connection_tasks: Arc::new(Mutex::new(JoinSet::new())),
+        };
+        let mut listener_tasks = vec![];
+        println!("Deadline for ingestion: {:?}", config.deadline);
+
+        match config.sources {
+            Some(sources) => {
+                for (source, spec) in sources.into_iter().zip(source_specs) {
+                    let input = ConnectionInput {
+                        id: source.source_id,
+                        num_buffers: source.num_buffers as usize,
+                        buffer_size: source.tuples_per_buffer,
+                        gathering_interval: source.gathering_interval,
+                        spec,
+                        rate_profile: source.rate_profile.clone(),
+                        protocol: source.protocol,
+                        tuple_separator: source.tuple_separator,
+                        topic: source.logical_source_name.clone(),
+                    };
+                    //the udp source of the worker listens on the port itself, all other sources connect to the server
+                    if source.protocol == SourceProtocol::UDP {
+                        let target = lookup_host((config.hostname.as_str(), source.port))
+                            .await?
+                            .next()
+                            .ok_or_else(|| format!("Could not resolve {}", config.hostname))?;
+                        println!("Source {} (id {}) sends datagrams to port {}", source.logical_source_name, source.source_id, source.port);
+                        let writer = DatagramWriter::bind(target).await?;
+                        listener_tasks.push(tokio::spawn(handle_client(writer, input, context.clone())));
+                        continue;
+                    }
+                    let listener = bind(&config.hostname, source.port, &format!("source {}", source.logical_source_name)).await?;
+                    println!("Source {} (id {}) listening on port {} ({:?})", source.logical_source_name, source.source_id, source.port, source.protocol);
+                    listener_tasks.push(tokio::spawn(accept_connections(listener, context.clone(), move || input.clone())));
+                }
+            }
+            None => {
+                let listener = bind(&config.hostname, config.port, "input server").await?;
+                println!("Server listening on port {}...", config.port);
+                let mut id_count = 0;
+                listener_tasks.push(tokio::spawn(accept_connections(listener, context.clone(), move || {
+                    id_count += 1;
+                    ConnectionInput {
+                        id: id_count,
+                        num_buffers: config.num_buffers,
+                        buffer_size: config.buffer_size,
+                        gathering_interval: config.gathering_interval,
+                        spec: config.spec.clone(),
+                        rate_profile: config.rate_profile.clone(),
+                        protocol: SourceProtocol::BINARY,
+                        tuple_separator: DEFAULT_TUPLE_SEPARATOR,
+                        topic: String::new(),
+                    }
+                })));
+            }
+        }
+        Ok(Self {
+            control: context.control,
+            listener_tasks,
+            connection_tasks: context.connection_tasks,
+        })
+    }
+
+    pub fn control(&self) -> &Arc<EmissionControl> {
+        &self.control
+    }
+
+    pub fn stats(&self) -> IngestStats {
+        self.control.stats()
+    }
+
+    //stop emitting and close the ports so the next attempt can bind them again. returns what was emitted until now
+    pub fn stop(&self) -> IngestStats {
+        self.control.set_state(EmissionState::STOPPED);
+        for listener_task in &self.listener_tasks {
+            listener_task.abort();
+        }
+        self.connection_tasks.lock().unwrap().abort_all();
+        self.control.stats()
+    }
+
+    //serve until the server is stopped
+    pub async fn wait(mut self) {
+        for listener_task in std::mem::take(&mut self.listener_tasks) {
+            let _ = listener_task.await;
+        }
+    }
+}
+
+//the runners return early on errors during an attempt, dropping the server has to free the ports for the next one
+impl Drop for InputServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+//run the server on its own runtime until the process is killed
+pub fn run_standalone(config: InputServerConfig) -> Result<(), Box<dyn Error>> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        InputServer::start(config).await?.wait().await;
+        Ok(())
+    })
+}
+
+async fn bind(hostname: &str, port: u16, purpose: &str) -> Result<TcpListener, Box<dyn Error>> {
+    TcpListener::bind((hostname, port))
+        .await
+        .map_err(|e| format!("Error creating TCP listener for {} on port {}: {}", purpose, port, e).into())
+}
+
+//handle every connection on the listener in its own task
+async fn accept_connections(listener: TcpListener, context: ServerContext, mut next_input: impl FnMut() -> ConnectionInput) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let mut input = next_input();
+                let mut connection_tasks = context.connection_tasks.lock().unwrap();
+                if input.protocol != SourceProtocol::MQTT {
+                    connection_tasks.spawn(handle_client(stream, input, context.clone()));
+                    continue;
+                }
+                //the worker has to subscribe before the tuples can be published
+                let context = context.clone();
+                connection_tasks.spawn(async move {
+                    match MqttPublisher::accept_subscriber(stream).await {
+                        Ok(publisher) => {
+                            input.topic = publisher.topic().to_string();
+                            handle_client(publisher, input, context).await;
+                        }
+                        Err(e) => eprintln!("Error accepting mqtt subscriber for id {}: {}", input.id, e),
+                    }
+                });
+            }
+            Err(e) => {
+                eprintln!("Error accepting connection: {}", e);
+            }
+        }
+    }
+}
+
+async fn handle_client<W: MessageWriter>(writer: W, input: ConnectionInput, context: ServerContext) {
+    println!("Starting {:?} writer for id {}", input.protocol, input.id);
+
+    let control = &context.control;
+    let connection = control.add_connection(input.id);
+    let field_types = context.dataset.as_ref().map_or(input.spec.field_types(), |dataset| dataset.field_types());
+    let encoding = TupleEncoding::new(input.protocol, field_types, input.tuple_separator, &input.topic);
+    let mut pipeline = EmissionPipeline::new(writer, context.backpressure, context.channel_capacity, control.clone(), connection, encoding);
+
+    //wait if deadline is not reached
+    println!("Deadline: {:?}", context.deadline);
+    println!("Current time: {:?}", SystemTime::now().duration_since(UNIX_EPOCH).unwrap());
+    context.timer.sleep_until(context.deadline).await;
+    println!("Deadline reached, starting to write to socket");
+
+    if let Some(dataset) = &context.dataset {
+        replay_dataset(&mut pipeline, dataset.clone(), &input, &context).await;
+    } else {
+        //emission times are computed from the deadline, so delays in one interval do not shift the following buffers
+        let mut next_emission_time = context.deadline;
+
+        let mut schedule = EmissionSchedule::new(input.rate_profile, input.gathering_interval, input.spec.seed.unwrap_or_default() ^ input.id);
+        let mut generator = TupleGenerator::new(input.spec, input.id);
+        for _buffer in 0..input.num_buffers {
+            //a pause delays all following buffers instead of emitting the missed ones at once
+            match control.wait_until_running().await {
+                Some(paused) => next_emission_time += paused,
+                None => break,
+            }
+            // Generate data to write into the socket
+            if !pipeline.emit(next_emission_time, || generator.generate_buffer(input.buffer_size)).await {
+                break;
+            }
+
+            next_emission_time += schedule.next_interval(next_emission_time - context.deadline);
+            context.timer.sleep_until(next_emission_time).await;
+        }
+    }
+
+    let stats = control.stats().connections[connection].clone();
+    println!("All buffers done for id {}, {} late buffers, max lag {:?}, {} buffers dropped", input.id, stats.late_buffers, Duration::from_nanos(stats.max_lag_ns), stats.buffers_dropped);
+
+    //keep the connection open while the last buffers are written
+    tokio::time::sleep(Duration::from_secs(10)).await;
+}
+
+//send the rows of the dataset once they are due, at most buffer_size tuples at a time
+async fn replay_dataset<W: MessageWriter>(pipeline: &mut EmissionPipeline<W>, dataset: Arc<Dataset>, input: &ConnectionInput, context: &ServerContext) {
+    let mut deadline = context.deadline;
+    let mut replayer = DatasetReplayer::new(dataset, input.id, input.num_buffers * input.buffer_size);
+    while let Some(due) = replayer.next_due() {
+        context.timer.sleep_until(deadline + due).await;
+        match context.control.wait_until_running().await {
+            Some(paused) => deadline += paused,
+            None => break,
+        }
+        let sent = pipeline.emit(deadline + due, || {
+            let curr_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+            replayer.take_due(curr_time.saturating_sub(deadline), curr_time.as_nanos() as u64, input.buffer_size)
+        }).await;
+        if !sent {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpStream;
+
+    #[test]
+    fn test_embedded_server_reports_emitted_tuples() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let spec = DataGenerationSpec::default_stateless();
+        let tuple_size = spec.tuple_size();
+        let config = InputServerConfig {
+            hostname: "127.0.0.1".to_string(),
+            port,
+            num_buffers: 5,
+            buffer_size: 10,
+            gathering_interval: Duration::from_millis(1),
+            deadline: SystemTime::now().duration_since(UNIX_EPOCH).unwrap(),
+            spec,
+            rate_profile: RateProfile::Constant,
+            dataset_replay: None,
+            sources: None,
+            initial_state: EmissionState::RUNNING,
+            backpressure: BackpressurePolicy::BLOCK,
+            channel_capacity: DEFAULT_CHANNEL_CAPACITY,
+        };
+        let server = runtime.block_on(InputServer::start(config)).unwrap();
+        runtime.block_on(async {
+            let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+            let mut received = vec![0; 50 * tuple_size];
+            stream.read_exact(&mut received).await.unwrap();
+            //the statistics are updated right after the last write returned
+            while server.stats().total_tuples_sent() < 50 {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        });
+
+        let stats = server.stop();
+        assert_eq!(stats.state, EmissionState::STOPPED);
+        assert_eq!(stats.emitted_by_source(), std::collections::HashMap::from([(1, 50)]));
+        //the port is free again once the server is stopped
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(std::net::TcpListener::bind(("127.0.0.1", port)).is_ok());
+    }
+
+    #[test]
+    fn test_stop_closes_open_connections() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let config = InputServerConfig {
+            hostname: "127.0.0.1".to_string(),
+            port,
+            num_buffers: 5,
+            buffer_size: 10,
+            gathering_interval: Duration::from_millis(1),
+            //the connection waits for the deadline and is still open when the server is stopped
+            deadline: SystemTime::now().duration_since(UNIX_EPOCH).unwrap() + Duration::from_secs(3600),
+            spec: DataGenerationSpec::default_stateless(),
+            rate_profile: RateProfile::Constant,
+            dataset_replay: None,
+            sources: None,
+            initial_state: EmissionState::RUNNING,
+            backpressure: BackpressurePolicy::BLOCK,
+            channel_capacity: DEFAULT_CHANNEL_CAPACITY,
+        };
+        let server = runtime.block_on(InputServer::start(config)).unwrap();
+        let mut stream = runtime.block_on(async {
+            let stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+            while server.stats().connections.is_empty() {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+            stream
+        });
+
+        server.stop();
+        runtime.block_on(async {
+            let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut [0; 8])).await;
+            assert!(matches!(read, Ok(Ok(0)) | Ok(Err(_))));
+        });
+    }
+}
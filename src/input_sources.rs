@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use serde_with::DurationMilliSeconds;
+
+use crate::rate_profile::RateProfile;
+use crate::source_protocol::{SourceProtocol, DEFAULT_TUPLE_SEPARATOR};
+
+//every physical source gets a dedicated port on the input server, so its id and input parameters no longer depend on
+//the order in which the workers connect. the list is generated with the worker configs and written to the folder of
+//the configuration, where it also documents which source got which id
+
+pub const INPUT_SOURCES_FILE: &str = "input_sources.json";
+
+//input parameters of a single source that differ from the default source input
+#[serde_as]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct SourceInputOverride {
+    pub tuples_per_buffer: Option<usize>,
+    #[serde_as(as = "Option<DurationMilliSeconds<u64>>")]
+    #[serde(default)]
+    pub gathering_interval: Option<Duration>,
+    pub data_generation_spec: Option<PathBuf>,
+    pub rate_profile: Option<RateProfile>,
+    pub protocol: Option<SourceProtocol>,
+}
+
+#[serde_as]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct InputSourceConfig {
+    pub logical_source_name: String,
+    pub node_id: u64,
+    pub source_id: u64,
+    pub port: u16,
+    pub num_buffers: u64,
+    pub tuples_per_buffer: usize,
+    #[serde_as(as = "DurationMilliSeconds<u64>")]
+    pub gathering_interval: Duration,
+    //the default spec of the input server is used if not set
+    pub data_generation_spec: Option<PathBuf>,
+    pub rate_profile: RateProfile,
+    #[serde(default)]
+    pub protocol: SourceProtocol,
+    #[serde(default = "default_tuple_separator")]
+    pub tuple_separator: char,
+}
+
+pub fn default_tuple_separator() -> char {
+    DEFAULT_TUPLE_SEPARATOR
+}
+
+impl InputSourceConfig {
+    pub fn tuple_count(&self) -> u64 {
+        self.num_buffers * self.tuples_per_buffer as u64
+    }
+}
+
+//default input parameters of all sources, see DefaultSourceInput in the runner libs
+pub struct SourceInputDefaults<'a> {
+    pub tuples_per_buffer: usize,
+    pub gathering_interval: Duration,
+    pub data_generation_spec: Option<&'a PathBuf>,
+    pub rate_profile: &'a RateProfile,
+    pub protocol: SourceProtocol,
+    pub tuple_separator: char,
+    pub overrides: &'a HashMap<String, SourceInputOverride>,
+    //time during which the sources produce data, divided into buffers by the gathering interval and the rate profile
+    pub data_production_time: Duration,
+    //send times of the topology updates the reconnect bursts of the rate profiles are placed at
+    pub update_offsets: &'a [Duration],
+    pub first_port: u16,
+    //ports of the nes processes, the input server must not bind into them
+    pub reserved_ports: &'a [Range<u16>],
+}
+
+impl SourceInputDefaults<'_> {
+    //add a source with the next free id and port, overrides are looked up by the logical source name
+    pub fn add_source(&self, sources: &mut Vec<InputSourceConfig>, logical_source_name: &str, node_id: u64) -> Result<InputSourceConfig, Box<dyn Error>> {
+        let port = u16::try_from(sources.len())
+            .ok()
+            .and_then(|offset| self.first_port.checked_add(offset))
+            .ok_or(format!("no port left for source {} after port {}", logical_source_name, self.first_port))?;
+        if let Some(reserved) = self.reserved_ports.iter().find(|reserved| reserved.contains(&port)) {
+            return Err(format!("port {} of source {} overlaps the reserved ports {:?}", port, logical_source_name, reserved).into());
+        }
+        let source_override = self.overrides.get(logical_source_name).cloned().unwrap_or_default();
+        let gathering_interval = source_override.gathering_interval.unwrap_or(self.gathering_interval);
+        let rate_profile = source_override.rate_profile.unwrap_or(self.rate_profile.clone());
+        let num_buffers = rate_profile
+            .resolve_reconnect_bursts(self.update_offsets)
+            .buffer_count(gathering_interval, self.data_production_time);
+        let source = InputSourceConfig {
+            logical_source_name: logical_source_name.to_string(),
+            node_id,
+            source_id: sources.len() as u64 + 1,
+            port,
+            num_buffers,
+            tuples_per_buffer: source_override.tuples_per_buffer.unwrap_or(self.tuples_per_buffer),
+            gathering_interval,
+            data_generation_spec: source_override.data_generation_spec.or(self.data_generation_spec.cloned()),
+            rate_profile,
+            protocol: source_override.protocol.unwrap_or(self.protocol),
+            tuple_separator: self.tuple_separator,
+        };
+        sources.push(source.clone());
+        Ok(source)
+    }
+}
+
+//replace reconnect bursts in the rate profiles of the sources with bursts at the update times of the attempt
+pub fn resolve_reconnect_bursts(sources: &[InputSourceConfig], update_offsets: &[Duration]) -> Vec<InputSourceConfig> {
+    sources
+        .iter()
+        .map(|source| InputSourceConfig {
+            rate_profile: source.rate_profile.resolve_reconnect_bursts(update_offsets),
+            ..source.clone()
+        })
+        .collect()
+}
+
+pub fn write_input_sources(path: &Path, sources: &[InputSourceConfig]) -> Result<(), Box<dyn Error>> {
+    fs::write(path, serde_json::to_string_pretty(sources)?)?;
+    Ok(())
+}
+
+pub fn read_input_sources(path: &Path) -> Result<Vec<InputSourceConfig>, Box<dyn Error>> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+//a join pair {name}s{2i+1} and {name}s{2i+2} only processes the sequence numbers that both of its sources emitted
+pub fn joined_tuple_counts(sources: &[InputSourceConfig], emitted: &HashMap<u64, u64>) -> HashMap<u64, u64> {
+    let emitted_by_name = sources
+        .iter()
+        .map(|source| (source.logical_source_name.as_str(), emitted.get(&source.source_id).copied().unwrap_or(0)))
+        .collect::<HashMap<_, _>>();
+    sources
+        .iter()
+        .map(|source| {
+            let tuples = emitted_by_name[source.logical_source_name.as_str()];
+            let partner_tuples = join_partner_name(&source.logical_source_name)
+                .and_then(|partner| emitted_by_name.get(partner.as_str()).copied());
+            (source.source_id, partner_tuples.map_or(tuples, |partner_tuples| tuples.min(partner_tuples)))
+        })
+        .collect()
+}
+
+//the source the tuples of a join source are joined with
+pub fn join_partner<'a>(sources: &'a [InputSourceConfig], source: &InputSourceConfig) -> Option<&'a InputSourceConfig> {
+    let partner_name = join_partner_name(&source.logical_source_name)?;
+    sources.iter().find(|partner| partner.logical_source_name == partner_name)
+}
+
+fn join_partner_name(source_name: &str) -> Option<String> {
+    let (logical_source_name, count) = source_name.rsplit_once('s')?;
+    let count = count.parse::<u64>().ok()?;
+    let partner_count = if count % 2 == 1 { count + 1 } else { count - 1 };
+    Some(format!("{}s{}", logical_source_name, partner_count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_sources_with_overrides() {
+        let overrides = HashMap::from([(
+            "1s2".to_string(),
+            SourceInputOverride {
+                tuples_per_buffer: Some(50),
+                gathering_interval: Some(Duration::from_millis(20)),
+                protocol: Some(SourceProtocol::UDP),
+                ..Default::default()
+            },
+        ), (
+            "1s3".to_string(),
+            SourceInputOverride {
+                rate_profile: Some(RateProfile::ReconnectBursts { lead_ms: 0, duration_ms: 500, factor: 2.0 }),
+                ..Default::default()
+            },
+        )]);
+        let defaults = SourceInputDefaults {
+            tuples_per_buffer: 10,
+            gathering_interval: Duration::from_millis(10),
+            data_generation_spec: None,
+            rate_profile: &RateProfile::Constant,
+            protocol: SourceProtocol::BINARY,
+            tuple_separator: '|',
+            overrides: &overrides,
+            data_production_time: Duration::from_secs(1),
+            update_offsets: &[Duration::from_millis(500)],
+            first_port: 9000,
+            reserved_ports: &[8000..9000, 9003..9004],
+        };
+        let mut sources = vec![];
+        let first = defaults.add_source(&mut sources, "1s1", 4).unwrap();
+        let second = defaults.add_source(&mut sources, "1s2", 5).unwrap();
+        assert_eq!((first.source_id, first.port, first.tuple_count()), (1, 9000, 1000));
+        assert_eq!((second.source_id, second.port, second.num_buffers, second.tuple_count()), (2, 9001, 50, 2500));
+        assert_eq!((first.protocol, second.protocol, second.tuple_separator), (SourceProtocol::BINARY, SourceProtocol::UDP, '|'));
+        assert_eq!(sources, vec![first.clone(), second.clone()]);
+        //a burst at twice the rate during the second half of the production time
+        assert_eq!(defaults.add_source(&mut sources, "1s3", 6).unwrap().num_buffers, 150);
+        //the next port is reserved and the last port of the range can not be exceeded
+        assert!(defaults.add_source(&mut sources, "1s4", 6).is_err());
+        assert!(SourceInputDefaults { first_port: u16::MAX, ..defaults }.add_source(&mut sources, "1s4", 6).is_err());
+        sources.pop();
+
+        let joined = joined_tuple_counts(&sources, &HashMap::from([(1, 800), (2, 2500)]));
+        assert_eq!(joined, HashMap::from([(1, 800), (2, 800)]));
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::{BTreeMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use simulation_runner_rs::data_generation::{DataGenerationSpec, ValueDistribution, JOIN_KEY_FACTOR};
+
+use crate::tuple_schema::JoinOutputTuple;
+
+//number of offending tuples kept per violation kind in the report
+pub const DEFAULT_MAX_EXAMPLES: usize = 10;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum JoinViolation {
+    SequenceNumberMismatch,
+    JoinIdMismatch,
+    IdenticalSourceIds,
+    Duplicate,
+    EventTimeOutsideWindow,
+}
+
+//the checks that depend on how the tuples were generated. a check is only applied if the fields it relies on are
+//generated the same way by every source of the run, the default is a run whose values are unknown, e.g. a replayed
+//dataset
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub struct JoinRules {
+    //start and step of the sequential value field if matching tuples carry the join key of their sequence number
+    pub sequential_join_keys: Option<(u64, u64)>,
+    //the id field holds the source id, so the two sides of a result never share it
+    pub distinct_source_ids: bool,
+}
+
+impl JoinRules {
+    pub fn from_spec(spec: &DataGenerationSpec) -> Self {
+        let distribution = |name: &str| {
+            spec.fields
+                .iter()
+                .find(|field| field.name == name)
+                .map(|field| &field.distribution)
+        };
+        let sequential_join_keys = match (distribution("join_id"), distribution("value")) {
+            (Some(ValueDistribution::JoinKey { .. }), Some(ValueDistribution::Sequential { start, step })) if *step > 0 => {
+                Some((*start, *step))
+            }
+            _ => None,
+        };
+        Self {
+            sequential_join_keys,
+            distinct_source_ids: distribution("id") == Some(&ValueDistribution::SourceId),
+        }
+    }
+
+    //the rules that hold for the tuples of all specs
+    pub fn from_specs(specs: &[DataGenerationSpec]) -> Self {
+        let rules = specs.iter().map(Self::from_spec).collect::<Vec<_>>();
+        match rules.first() {
+            Some(first) => Self {
+                sequential_join_keys: first
+                    .sequential_join_keys
+                    .filter(|keys| rules.iter().all(|rule| rule.sequential_join_keys == Some(*keys))),
+                distinct_source_ids: rules.iter().all(|rule| rule.distinct_source_ids),
+            },
+            None => Self::default(),
+        }
+    }
+
+    pub fn skipped_checks(&self) -> Vec<JoinViolation> {
+        let mut skipped = vec![];
+        if self.sequential_join_keys.is_none() {
+            skipped.push(JoinViolation::SequenceNumberMismatch);
+            skipped.push(JoinViolation::JoinIdMismatch);
+        }
+        if !self.distinct_source_ids {
+            skipped.push(JoinViolation::IdenticalSourceIds);
+        }
+        skipped
+    }
+
+    //the join key of a tuple emitted with the value, none if the generator can not have emitted the value
+    fn join_key(&self, value: u64) -> Option<u64> {
+        let (start, step) = self.sequential_join_keys?;
+        let offset = value.checked_sub(start)?;
+        if !offset.is_multiple_of(step) {
+            return None;
+        }
+        //a sequence number too large to carry a join id can not have been emitted with a matching one
+        (offset / step).checked_mul(JOIN_KEY_FACTOR)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ValidationReport {
+    //checks that were not applied because the data of the run does not follow the rule they rely on
+    pub skipped_checks: Vec<JoinViolation>,
+    pub checked_tuples: usize,
+    pub valid_tuples: usize,
+    pub violation_counts: BTreeMap<JoinViolation, usize>,
+    pub examples: BTreeMap<JoinViolation, Vec<JoinOutputTuple>>,
+}
+
+//checks join output tuples against the invariants of the data generated by the stateful input server
+pub struct JoinValidator {
+    max_examples: usize,
+    rules: JoinRules,
+    checked_tuples: usize,
+    valid_tuples: usize,
+    violation_counts: BTreeMap<JoinViolation, usize>,
+    examples: BTreeMap<JoinViolation, Vec<JoinOutputTuple>>,
+    //window start, source id and sequence number of both sides
+    seen: HashSet<(u64, u64, u64, u64, u64)>,
+}
+
+impl JoinValidator {
+    pub fn new(max_examples: usize, rules: JoinRules) -> Self {
+        Self {
+            max_examples,
+            rules,
+            checked_tuples: 0,
+            valid_tuples: 0,
+            violation_counts: BTreeMap::new(),
+            examples: BTreeMap::new(),
+            seen: HashSet::new(),
+        }
+    }
+
+    //record all violations of the tuple and return them
+    pub fn check(&mut self, tuple: &JoinOutputTuple) -> Vec<JoinViolation> {
+        let mut violations = vec![];
+        if self.rules.sequential_join_keys.is_some() {
+            if tuple.sequence_number_1 != tuple.sequence_number_2 {
+                violations.push(JoinViolation::SequenceNumberMismatch);
+            }
+            if self.rules.join_key(tuple.sequence_number_1) != Some(tuple.join_id_1)
+                || self.rules.join_key(tuple.sequence_number_2) != Some(tuple.join_id_2)
+            {
+                violations.push(JoinViolation::JoinIdMismatch);
+            }
+        }
+        if self.rules.distinct_source_ids && tuple.id_1 == tuple.id_2 {
+            violations.push(JoinViolation::IdenticalSourceIds);
+        }
+        let key = (
+            tuple.win_start,
+            tuple.id_1,
+            tuple.sequence_number_1,
+            tuple.id_2,
+            tuple.sequence_number_2,
+        );
+        if !self.seen.insert(key) {
+            violations.push(JoinViolation::Duplicate);
+        }
+        //the windows are assigned from the raw event time values, so the bounds are in the unit of the event times
+        let window = tuple.win_start..tuple.win_end;
+        if !window.contains(&tuple.event_time_1) || !window.contains(&tuple.event_time_2) {
+            violations.push(JoinViolation::EventTimeOutsideWindow);
+        }
+
+        self.checked_tuples += 1;
+        if violations.is_empty() {
+            self.valid_tuples += 1;
+        }
+        for violation in &violations {
+            *self.violation_counts.entry(*violation).or_insert(0) += 1;
+            let examples = self.examples.entry(*violation).or_default();
+            if examples.len() < self.max_examples {
+                examples.push(tuple.clone());
+            }
+        }
+        violations
+    }
+
+    pub fn report(&self) -> ValidationReport {
+        ValidationReport {
+            skipped_checks: self.rules.skipped_checks(),
+            checked_tuples: self.checked_tuples,
+            valid_tuples: self.valid_tuples,
+            violation_counts: self.violation_counts.clone(),
+            examples: self.examples.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn valid_tuple(sequence_number: u64) -> JoinOutputTuple {
+        JoinOutputTuple {
+            win_start: 0,
+            win_end: 100,
+            id_1: 1,
+            join_id_1: sequence_number * 1000,
+            sequence_number_1: sequence_number,
+            event_time_1: 10,
+            processing_time_1: 11,
+            emission_time_1: 12,
+            id_2: 2,
+            join_id_2: sequence_number * 1000,
+            sequence_number_2: sequence_number,
+            event_time_2: 20,
+            processing_time_2: 21,
+            emission_time_2: 22,
+        }
+    }
+
+    #[test]
+    fn test_violations_are_counted_by_kind() {
+        let mut validator = JoinValidator::new(1, JoinRules::from_spec(&DataGenerationSpec::default_join(3)));
+        assert!(validator.check(&valid_tuple(3)).is_empty());
+        assert_eq!(validator.check(&valid_tuple(3)), vec![JoinViolation::Duplicate]);
+
+        let mut mismatch = valid_tuple(4);
+        mismatch.sequence_number_2 = 5;
+        mismatch.id_2 = 1;
+        mismatch.event_time_2 = 100;
+        assert_eq!(
+            validator.check(&mismatch),
+            vec![
+                JoinViolation::SequenceNumberMismatch,
+                JoinViolation::JoinIdMismatch,
+                JoinViolation::IdenticalSourceIds,
+                JoinViolation::EventTimeOutsideWindow,
+            ]
+        );
+        assert!(validator.check(&valid_tuple(4)).is_empty());
+        let mut overflowing = valid_tuple(6);
+        overflowing.sequence_number_2 = u64::MAX;
+        assert!(validator.check(&overflowing).contains(&JoinViolation::JoinIdMismatch));
+        assert_eq!(validator.check(&valid_tuple(3)), vec![JoinViolation::Duplicate]);
+
+        let report = validator.report();
+        assert_eq!(report.checked_tuples, 6);
+        assert_eq!(report.valid_tuples, 2);
+        assert_eq!(report.violation_counts[&JoinViolation::Duplicate], 2);
+        assert_eq!(report.examples[&JoinViolation::Duplicate].len(), 1);
+        assert!(report.skipped_checks.is_empty());
+    }
+
+    #[test]
+    fn test_rules_follow_the_data_generation_spec() {
+        let mut shifted = DataGenerationSpec::default_join(3);
+        shifted.fields[2].distribution = ValueDistribution::Sequential { start: 10, step: 2 };
+        let mut validator = JoinValidator::new(1, JoinRules::from_spec(&shifted));
+        let mut tuple = valid_tuple(16);
+        tuple.join_id_1 = 3000;
+        tuple.join_id_2 = 3000;
+        assert!(validator.check(&tuple).is_empty());
+        tuple.sequence_number_2 = 17;
+        assert_eq!(validator.check(&tuple), vec![JoinViolation::SequenceNumberMismatch, JoinViolation::JoinIdMismatch]);
+
+        //random values carry no relation between the sequence number and the join id
+        let mut random = DataGenerationSpec::default_join(3);
+        random.fields[2].distribution = ValueDistribution::Uniform { min: 0.0, max: 10.0 };
+        let rules = JoinRules::from_specs(&[DataGenerationSpec::default_join(3), random]);
+        let mut validator = JoinValidator::new(1, rules);
+        assert!(validator.check(&tuple).is_empty());
+        assert_eq!(
+            validator.report().skipped_checks,
+            vec![JoinViolation::SequenceNumberMismatch, JoinViolation::JoinIdMismatch]
+        );
+        assert_eq!(JoinRules::default().skipped_checks().len(), 3);
+    }
+}
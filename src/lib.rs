@@ -0,0 +1,18 @@
+//modules shared by the experiment runner and the input servers
+pub mod tuple_schema;
+pub mod result_reader;
+pub mod data_generation;
+pub mod rate_profile;
+pub mod dataset_replay;
+pub mod input_sources;
+pub mod ingest_stats;
+pub mod emission_pipeline;
+pub mod emission_timer;
+pub mod input_server;
+pub mod source_protocol;
+pub mod mqtt_broker;
+pub mod topology;
+pub mod trajectory_reconnects;
+pub mod mobility_models;
+pub mod reconnect_schedule;
+pub mod mobility_scenario;
@@ -16,7 +16,7 @@ use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::path::PathBuf;
 
-use crate::rest_node_relocation::TopologyUpdate;
+use simulation_runner_rs::topology::{write_device_placements, FixedTopology, SimulatedReconnects, TopologyUpdate, WorkerIds};
 use avro_rs::types::Record;
 use avro_rs::{Schema, Writer};
 use chrono::Local;
@@ -47,37 +47,34 @@ const INPUT_FOLDER_SUB_PATH: &'static str = "nes_experiment_input";
 const INPUT_CONFIG_NAME: &'static str = "input_data_config.toml";
 //const PORT_RANGE: std::ops::Range<u16> = 10_000..20_000;
 const PORT_RANGE: std::ops::Range<u16> = 7000..8000;
+const REST_PORT: u16 = 8081;
+//rpc and data port of the coordinator, which are not set in the coordinator config and keep the nes defaults
+const COORDINATOR_PORTS: std::ops::Range<u16> = 4000..4002;
+//the source ports of the input server must not overlap the ports of the nes processes
+const RESERVED_PORTS: [std::ops::Range<u16>; 3] = [PORT_RANGE, REST_PORT..REST_PORT + 1, COORDINATOR_PORTS];
 
 pub const JOIN_QUERY: bool = true;
 
-#[derive(Serialize, Deserialize, Clone)]
-pub struct SimulatedReconnects {
-    pub initial_parents: Vec<(u64, u64)>,
-    pub topology_updates: Vec<TopologyUpdate>,
-}
-
-impl SimulatedReconnects {
-    pub fn get_mobility_input_config_list(&self) -> MobilityInputConfigList {
-        let mut mobility_configs = vec![];
-        for initial in &self.initial_parents {
-            let generated_mobility_config = InputMobilityconfig {
-                mobility_base_path: None,
-                //locationProviderConfig: output_trajectory_path,
-                locationProviderConfig: RelativePathBuf::from_path("invalid").unwrap(),
-                //locationProviderType: "CSV".to_owned(),
-                locationProviderType: "BASE".to_owned(),
-                // locationProviderConfig: String::from(output_trajectory_path.to_str()
-                //     .ok_or("Could not get output trajectory path")?),
-                reconnectPredictorType: PRECALCULATED,
-                //precalcReconnectPath: output_precalculated_reconnects,
-                precalcReconnectPath: RelativePathBuf::from_path("invalid").unwrap(),
-            };
-            mobility_configs.push(generated_mobility_config.clone());
-        }
-        MobilityInputConfigList {
-            worker_mobility_configs: mobility_configs,
-            central_topology_update_list_path: None,
-        }
+fn get_mobility_input_config_list(simulated_reconnects: &SimulatedReconnects) -> MobilityInputConfigList {
+    let mut mobility_configs = vec![];
+    for initial in &simulated_reconnects.initial_parents {
+        let generated_mobility_config = InputMobilityconfig {
+            mobility_base_path: None,
+            //locationProviderConfig: output_trajectory_path,
+            locationProviderConfig: RelativePathBuf::from_path("invalid").unwrap(),
+            //locationProviderType: "CSV".to_owned(),
+            locationProviderType: "BASE".to_owned(),
+            // locationProviderConfig: String::from(output_trajectory_path.to_str()
+            //     .ok_or("Could not get output trajectory path")?),
+            reconnectPredictorType: PRECALCULATED,
+            //precalcReconnectPath: output_precalculated_reconnects,
+            precalcReconnectPath: RelativePathBuf::from_path("invalid").unwrap(),
+        };
+        mobility_configs.push(generated_mobility_config.clone());
+    }
+    MobilityInputConfigList {
+        worker_mobility_configs: mobility_configs,
+        central_topology_update_list_path: None,
     }
 }
 
@@ -381,7 +378,7 @@ impl SimulationConfig {
     ) -> Result<Vec<(String, InputConfig, Vec<u64>)>, Box<dyn Error>> {
         //iterate over all subfolders in retrial path and check for tuple_count files
         let mut setups = vec![];
-        let re = Regex::new(r"out_run:(\d+)\.csvtuple_count\.csv").unwrap();
+        let re = Regex::new(r"out_run:(\d+)\.(?:csv|avro|parquet)tuple_count\.csv").unwrap();
         for entry in fs::read_dir(self.run_for_retrial_path.as_ref().unwrap())? {
             let entry = entry?;
             let path = entry.path();
@@ -579,6 +576,9 @@ pub struct Parameters {
     pub query_duplication_factor: usize,
     pub join_match_interval: u64,
     pub window_size: u64,
+    //overrides the output type of the simulation config for this experiment
+    #[serde(default)]
+    pub output_type: Option<OutputType>,
 }
 
 #[serde_as]
@@ -588,6 +588,33 @@ pub struct DefaultSourceInput {
     #[serde_as(as = "DurationMilliSeconds<u64>")]
     pub gathering_interval: Duration,
     pub source_input_method: SourceInputMethod,
+    //toml file describing how the values of the source fields are generated, see data_generation. the input server uses
+    //its default distributions if unset
+    #[serde(default)]
+    pub data_generation_spec: Option<PathBuf>,
+    #[serde(default)]
+    pub rate_profile: RateProfile,
+    //replay a recorded dataset instead of generating tuples
+    #[serde(default)]
+    pub dataset_replay: Option<DatasetReplaySpec>,
+    //input parameters of single sources by logical source name
+    #[serde(default)]
+    pub source_overrides: HashMap<String, SourceInputOverride>,
+    //what the input server does with buffers the socket can not take, see emission_pipeline
+    #[serde(default)]
+    pub backpressure_policy: BackpressurePolicy,
+    #[serde(default = "default_channel_capacity")]
+    pub channel_capacity: usize,
+    //how the tuples reach the workers and which nes source type the worker configs use, see source_protocol
+    #[serde(default)]
+    pub protocol: SourceProtocol,
+    //ends every tuple of the text based protocols
+    #[serde(default = "default_tuple_separator")]
+    pub tuple_separator: char,
+}
+
+fn default_channel_capacity() -> usize {
+    DEFAULT_CHANNEL_CAPACITY
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -599,10 +626,23 @@ pub enum SourceInputMethod {
 use serde::Deserializer;
 
 use crate::lib_stateful::config::Paths;
-use crate::lib_stateful::FieldType::UINT64;
 use crate::lib_stateful::ReconnectPredictorType::PRECALCULATED;
 use crate::lib_stateful::WorkerConfigType::Fixed;
 use crate::{rest_node_relocation, LogLevel};
+use crate::parquet_output::{write_u64_rows, PARQUET_ROW_GROUP_SIZE};
+use crate::sink_reader::{receive_tuples, ConnectionStats};
+use crate::completeness::CompletenessAnalyzer;
+use simulation_runner_rs::data_generation::{DataGenerationSpec, JoinKeyRule};
+use simulation_runner_rs::dataset_replay::DatasetReplaySpec;
+use simulation_runner_rs::emission_pipeline::{BackpressurePolicy, DEFAULT_CHANNEL_CAPACITY};
+use simulation_runner_rs::ingest_stats::EmissionState;
+use simulation_runner_rs::input_server::InputServerConfig;
+use simulation_runner_rs::input_sources::{default_tuple_separator, resolve_reconnect_bursts, InputSourceConfig, SourceInputDefaults, SourceInputOverride};
+use simulation_runner_rs::source_protocol::SourceProtocol;
+use simulation_runner_rs::rate_profile::RateProfile;
+use crate::join_validation::JoinValidator;
+use crate::tuple_schema::{FieldType, JoinOutputTuple as OutputTuple, SinkTuple, SourceField, JOIN_SOURCE_FIELDS};
+use crate::expected_output::{JoinInput, JoinPairModel};
 
 pub fn deserialize_relative_path<'de, D>(deserializer: D) -> Result<RelativePathBuf, D::Error>
 where
@@ -617,8 +657,9 @@ where
 pub mod config {
     use crate::MobileDeviceQuadrants::QuadrantConfig;
     use relative_path::RelativePathBuf;
+    use simulation_runner_rs::mobility_models::MobilityModelConfig;
     use serde::{Deserialize, Serialize};
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
 
     #[derive(Debug, Deserialize, Serialize, Clone)]
     pub struct Paths {
@@ -634,6 +675,7 @@ pub mod config {
     #[derive(Debug, Deserialize, Serialize, Clone)]
     pub enum MobileTopologyInput {
         Quadrants(QuadrantConfig),
+        MobilityModel(MobilityModelConfig),
         #[serde(deserialize_with = "super::deserialize_relative_path")]
         TrajectoriesDir(RelativePathBuf),
     }
@@ -655,6 +697,14 @@ pub mod config {
             }
         }
 
+        pub fn get_mobility_model_config(&self) -> Option<MobilityModelConfig> {
+            if let MobileTopologyInput::MobilityModel(config) = &self.mobile_trajectories_directory {
+                Some(config.clone())
+            } else {
+                None
+            }
+        }
+
         pub fn get_mobile_trajectories_directory(&self) -> Option<PathBuf> {
             if let MobileTopologyInput::TrajectoriesDir(dir) = &self.mobile_trajectories_directory {
                 Some(dir.to_path(self.base_path.as_ref().expect("base path not set")))
@@ -679,6 +729,11 @@ pub mod config {
         pub fn set_base_path(&mut self, base_path: PathBuf) {
             self.base_path = Some(base_path);
         }
+
+        //a path of the config relative to the folder of the config file, absolute paths are kept as they are
+        pub fn resolve_path(&self, path: &Path) -> PathBuf {
+            self.base_path.as_ref().expect("base path not set").join(path)
+        }
     }
 }
 
@@ -711,6 +766,7 @@ pub struct ExperimentSetup {
     pub total_number_of_tuples_to_emit: u64,
     pub num_buffers: u128,
     pub simulated_reconnects: SimulatedReconnects,
+    pub input_sources: Vec<InputSourceConfig>,
     // pub central_topology_updates: Vec<TopologyUpdate>,
     // pub initial_topology_update: Option<Vec<(u64, u64)>>,
 }
@@ -737,6 +793,32 @@ pub fn get_reconnect_list(rest_port: u16) -> Result<ReconnectList, Box<dyn Error
 }
 
 impl ExperimentSetup {
+    //input server of an attempt, hosted by the runner. the ingestion start is the time since the unix epoch
+    pub fn input_server_config(&self, ingestion_start: Duration) -> Result<InputServerConfig, Box<dyn Error>> {
+        let input_config = &self.input_config;
+        let topology_updates = &self.simulated_reconnects.topology_updates;
+        let mut spec = match &input_config.default_source_input.data_generation_spec {
+            Some(path) => DataGenerationSpec::from_file(path)?,
+            None => DataGenerationSpec::default_join(input_config.parameters.join_match_interval),
+        };
+        spec.resolve_seed();
+        Ok(InputServerConfig {
+            hostname: "127.0.0.1".to_string(),
+            port: input_config.parameters.source_input_server_port,
+            num_buffers: self.num_buffers as usize,
+            buffer_size: input_config.default_source_input.tuples_per_buffer,
+            gathering_interval: input_config.default_source_input.gathering_interval,
+            deadline: ingestion_start,
+            spec,
+            rate_profile: input_config.get_rate_profile(topology_updates),
+            dataset_replay: input_config.get_dataset_replay(),
+            sources: Some(resolve_reconnect_bursts(&self.input_sources, &input_config.get_update_offsets(topology_updates))),
+            initial_state: EmissionState::RUNNING,
+            backpressure: input_config.default_source_input.backpressure_policy,
+            channel_capacity: input_config.default_source_input.channel_capacity,
+        })
+    }
+
     pub fn start(
         &mut self,
         executable_paths: &NesExecutablePaths,
@@ -748,7 +830,7 @@ impl ExperimentSetup {
         self.mobile_worker_processes = vec![];
 
         //let rest_port = get_available_port(PORT_RANGE).ok_or("Could not find available port")?;
-        let rest_port = 8081;
+        let rest_port = REST_PORT;
 
         self.start_coordinator(
             &executable_paths.coordinator_path,
@@ -968,6 +1050,49 @@ impl InputConfig {
     //     }
     // }
 
+    //send times of the topology updates measured from the ingestion deadline, which is also the origin of rate profiles
+    pub fn get_update_offsets(&self, topology_updates: &[TopologyUpdate]) -> Vec<Duration> {
+        let reconnect_end = self.parameters.warmup + self.parameters.reconnect_runtime;
+        topology_updates
+            .iter()
+            .map(|update| self.parameters.warmup + update.timestamp.mul_f64(self.parameters.speedup_factor))
+            .filter(|offset| *offset <= reconnect_end)
+            .collect()
+    }
+
+    //rate profile of an attempt with reconnect bursts placed at the send times of the topology updates
+    pub fn get_rate_profile(&self, topology_updates: &[TopologyUpdate]) -> RateProfile {
+        self.default_source_input.rate_profile.resolve_reconnect_bursts(&self.get_update_offsets(topology_updates))
+    }
+
+    fn get_source_input_defaults<'a>(&'a self, update_offsets: &'a [Duration]) -> SourceInputDefaults<'a> {
+        SourceInputDefaults {
+            tuples_per_buffer: self.default_source_input.tuples_per_buffer,
+            gathering_interval: self.default_source_input.gathering_interval,
+            data_generation_spec: self.default_source_input.data_generation_spec.as_ref(),
+            rate_profile: &self.default_source_input.rate_profile,
+            protocol: self.default_source_input.protocol,
+            tuple_separator: self.default_source_input.tuple_separator,
+            overrides: &self.default_source_input.source_overrides,
+            data_production_time: self.get_data_production_time(),
+            update_offsets,
+            first_port: self.parameters.source_input_server_port,
+            reserved_ports: &RESERVED_PORTS,
+        }
+    }
+
+    //the dataset replay with the speedup factor of the experiment applied to the gaps between rows
+    pub fn get_dataset_replay(&self) -> Option<DatasetReplaySpec> {
+        self.default_source_input.dataset_replay.clone().map(|mut replay| {
+            replay.speedup_factor = self.parameters.speedup_factor;
+            replay
+        })
+    }
+
+    pub fn get_place_default_sources_on_node_ids_path(&self) -> PathBuf {
+        self.paths.resolve_path(&self.parameters.place_default_sources_on_node_ids_path)
+    }
+
     pub fn get_data_production_time(&self) -> Duration {
         self.parameters.warmup + self.parameters.reconnect_runtime + self.parameters.cooldown_time
     }
@@ -978,11 +1103,116 @@ impl InputConfig {
             + self.parameters.cooldown_time
             + self.parameters.post_cooldown_time
     }
+    //one query per logical source, joining the sources of the group pairwise. the placements map worker ids to the
+    //logical source names
+    pub fn get_query_strings(&self, place_default_sources_on_node_ids: &HashMap<String, Vec<String>>) -> Result<Vec<String>, Box<dyn Error>> {
+        let query_string = &self.parameters.query_string;
+        let mut query_strings = vec![];
+
+        if JOIN_QUERY {
+            let mut source_count_map = HashMap::<String, u64>::new();
+
+            for v in place_default_sources_on_node_ids.values().flatten() {
+
+                let source_count = source_count_map.entry(v.clone()).or_insert(0);
+                *source_count += 1;
+            };
+
+            for (k, c) in source_count_map.iter() {
+                if *c % 2 != 0 {
+                    return Err(format!("logical source {} is placed {} times, the join needs an even number of sources", k, c).into());
+                }
+                let mut joins = String::from("{");
+                for i in 0..*c / 2 {
+                    //replace input 1 and 2 in query string and add to join string
+                    let join_string = query_string
+                    .replace("{INPUT1}", format!("{}s{}", k, i * 2 + 1).as_str())
+                    .replace("{INPUT2}", format!("{}s{}", k, i * 2 + 2).as_str());
+                    joins.push_str(&join_string);
+                    if i < *c / 2 - 1 {
+                        joins.push_str(", ");
+                    }
+                }
+                joins.push('}');
+
+                let outer_query = "Query::sink2({SINK}, {JOINS});";
+                //replace joins
+                let outer_query = outer_query.replace("{JOINS}", &joins);
+
+                let window_size = self.parameters.window_size;
+                let input_replaced = outer_query.replace("{WINDOW_SIZE}", &window_size.to_string());
+                let sink_string = format!("FileSinkDescriptor::create(\"{}:{{OUTPUT}}\", \"CSV_FORMAT\", \"true\")", k);
+                let tcp_sink = input_replaced.replace("{SINK}", &sink_string);
+                println!("--------------");
+                println!("Query: {}", tcp_sink);
+                println!("--------------");
+                query_strings.push(tcp_sink);
+            }
+        } else {
+            for id in place_default_sources_on_node_ids
+                .values()
+                .flatten()
+                .unique()
+            {
+                let input_replaced = query_string.replace("{INPUT}", &id.to_string());
+                let sink_string = format!("FileSinkDescriptor::create(\"{}:{{OUTPUT}}\", \"CSV_FORMAT\", \"true\")", id);
+                let tcp_sink = input_replaced.replace("{SINK}", &sink_string);
+                let null_sink = input_replaced
+                    .replace("{SINK}", "NullOutputSinkDescriptor::create()");
+                query_strings.push(tcp_sink);
+                for _i in 0..self.parameters.query_duplication_factor
+                {
+                    query_strings.push(null_sink.clone());
+                }
+            }
+        }
+        Ok(query_strings)
+    }
+
+    //custom data generation specs and dataset replays have to produce the tuples of the logical sources registered at
+    //the coordinator
+    fn validate_data_generation_specs(&self) -> Result<(), Box<dyn Error>> {
+        let overrides = self.default_source_input.source_overrides.values().filter_map(|source| source.data_generation_spec.as_ref());
+        for path in self.default_source_input.data_generation_spec.iter().chain(overrides) {
+            DataGenerationSpec::from_file(path)?
+                .validate_source_fields(JOIN_SOURCE_FIELDS)
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+        if let Some(replay) = &self.default_source_input.dataset_replay {
+            replay
+                .validate()
+                .and_then(|_| replay.validate_source_fields(JOIN_SOURCE_FIELDS))
+                .map_err(|e| format!("dataset replay {}: {}", replay.path.display(), e))?;
+        }
+        Ok(())
+    }
+
+    //in the quadrant and mobility model modes the sources of the devices are added to the configured placements. the
+    //merged placements are written to the generated folder and replace the configured path, so everything reading them
+    //later sees the devices
+    fn place_device_sources(&mut self, generated_folder: &Path) -> Result<(), Box<dyn Error>> {
+        let (device_ids, device_sources) = if let Some(quadrant_config) = self.paths.get_quadrant_config() {
+            (quadrant_config.device_ids(), quadrant_config.device_sources)
+        } else if let Some(model_config) = self.paths.get_mobility_model_config() {
+            let topology: FixedTopology = serde_json::from_str(&fs::read_to_string(self.paths.get_fixed_topology_nodes_path())?)?;
+            let first_mobile_worker_id = WorkerIds::STATEFUL.first_mobile_worker_id(&topology);
+            (first_mobile_worker_id..first_mobile_worker_id + model_config.num_devices as u64, model_config.device_sources)
+        } else {
+            return Ok(());
+        };
+        let generated_path = generated_folder.join("place_default_sources_on_node_ids.json");
+        write_device_placements(&self.get_place_default_sources_on_node_ids_path(), &generated_path, device_ids, &device_sources)?;
+        //the generated folder is not next to the config, so the merged placements are referred to by absolute path
+        self.parameters.place_default_sources_on_node_ids_path = generated_path.canonicalize()?;
+        Ok(())
+    }
+
     fn generate_output_config(
         &mut self,
         generated_folder: &Path,
     ) -> Result<ExperimentSetup, Box<dyn Error>> {
         println!("generating output config");
+        self.place_device_sources(generated_folder)?;
         // let input_trajectories_directory = &self.paths.mobile_trajectories_directory;
         let output_config_directory = generated_folder.join("config");
         fs::create_dir_all(&output_config_directory).expect("Failed to create folder");
@@ -1003,8 +1233,9 @@ impl InputConfig {
         let mut logicalSources = vec![];
 
         println!("generating logical sources");
+        self.validate_data_generation_specs()?;
         let place_default_sources_on_node_ids =
-            fs::read_to_string(&self.parameters.place_default_sources_on_node_ids_path)
+            fs::read_to_string(self.get_place_default_sources_on_node_ids_path())
                 .expect("Failed to read place_default_sources_on_node_ids");
         let place_default_sources_on_node_ids: HashMap<u64, Vec<u64>> =
             serde_json::from_str(&place_default_sources_on_node_ids)
@@ -1052,97 +1283,20 @@ impl InputConfig {
             // for name in &self.parameters.logical_source_names {
             logicalSources.push(LogicalSource {
                 logicalSourceName: name.to_string(),
-                fields: vec![
-                    LogicalSourceField {
-                        name: "id".to_string(),
-                        Type: UINT64,
-                    },
-                    LogicalSourceField {
-                        name: "join_id".to_string(),
-                        Type: UINT64,
-                    },
-                    LogicalSourceField {
-                        name: "value".to_string(),
-                        Type: UINT64,
-                    },
-                    LogicalSourceField {
-                        name: "event_timestamp".to_string(),
-                        Type: UINT64,
-                    },
-                    LogicalSourceField {
-                        name: "processing_timestamp".to_string(),
-                        Type: UINT64,
-                    },
-                    LogicalSourceField {
-                        name: "output_timestamp".to_string(),
-                        Type: UINT64,
-                    },
-                ],
+                fields: LogicalSourceField::from_source_fields(JOIN_SOURCE_FIELDS),
             });
         }
-        
+
         println!("register fake_migration_source");
         logicalSources.push(LogicalSource {
             logicalSourceName: "fake_migration_source".to_owned(),
-            fields: vec![
-                LogicalSourceField {
-                    name: "id".to_string(),
-                    Type: UINT64,
-                },
-                LogicalSourceField {
-                    name: "join_id".to_string(),
-                    Type: UINT64,
-                },
-                LogicalSourceField {
-                    name: "value".to_string(),
-                    Type: UINT64,
-                },
-                LogicalSourceField {
-                    name: "event_timestamp".to_string(),
-                    Type: UINT64,
-                },
-                LogicalSourceField {
-                    name: "processing_timestamp".to_string(),
-                    Type: UINT64,
-                },
-                LogicalSourceField {
-                    name: "output_timestamp".to_string(),
-                    Type: UINT64,
-                },
-            ],
+            fields: LogicalSourceField::from_source_fields(JOIN_SOURCE_FIELDS),
         });
 
-
-              
         println!("register fake_migration_source");
         logicalSources.push(LogicalSource {
             logicalSourceName: "fake_migration_source".to_owned(),
-            fields: vec![
-                LogicalSourceField {
-                    name: "id".to_string(),
-                    Type: UINT64,
-                },
-                LogicalSourceField {
-                    name: "join_id".to_string(),
-                    Type: UINT64,
-                },
-                LogicalSourceField {
-                    name: "value".to_string(),
-                    Type: UINT64,
-                },
-                LogicalSourceField {
-                    name: "event_timestamp".to_string(),
-                    Type: UINT64,
-                },
-                LogicalSourceField {
-                    name: "processing_timestamp".to_string(),
-                    Type: UINT64,
-                },
-                LogicalSourceField {
-                    name: "output_timestamp".to_string(),
-                    Type: UINT64,
-                },
-            ],
+            fields: LogicalSourceField::from_source_fields(JOIN_SOURCE_FIELDS),
         });
 
         println!("generating coordinator config");
@@ -1172,23 +1326,83 @@ impl InputConfig {
             SourceInputMethod::TCP => 0,
         };
 
+        //the reconnects are generated before the sources are added, the rate profiles of the sources place their bursts
+        //at the topology updates
+        let max_fixed_id = *topology.nodes.keys().max().unwrap_or(&0);
+        //println!("reading mobility config from {}", self.paths.get_mobility_config_list_path().to_str().unwrap());
+        println!("creating mobility config from");
+        let mut mobile_config_paths = vec![];
+        //let mobility_input_config = MobilityInputConfigList::read_input_from_file(&self.paths.get_mobility_config_list_path())?;
+        let mobility_input_config_path_option = &self.paths.get_mobility_config_list_path();
+        //let mut input_id = max_fixed_id + 1;
+
+        let (mut input_id, mobility_input_config, simulated_reconnects) =
+            if let Some(path) = mobility_input_config_path_option {
+                // let mobility_input_config = MobilityInputConfigList::read_input_from_file(&path)?;
+                // (max_fixed_id + 1, mobility_input_config)
+                println!("trying to create mobility input config from simulated reconnects file");
+                let json_string = std::fs::read_to_string(&path)?;
+                let simulated_reconnects: SimulatedReconnects =
+                    serde_json::from_str(json_string.as_str())?;
+                let mobility_input_config = get_mobility_input_config_list(&simulated_reconnects);
+                (
+                    max_fixed_id + 1,
+                    mobility_input_config,
+                    simulated_reconnects,
+                )
+            } else if let Some(quadrant_config) = self.paths.get_quadrant_config() {
+                println!("generating simulated reconnects from quadrant rotation");
+                let first_mobile_worker_id = WorkerIds::STATEFUL.first_mobile_worker_id(&topology);
+                let simulated_reconnects = quadrant_config
+                    .get_simulated_reconnects(self.parameters.reconnect_runtime, first_mobile_worker_id)?;
+                fs::write(
+                    output_trajectory_directory.join("topology_updates.json"),
+                    serde_json::to_string_pretty(&simulated_reconnects)?,
+                )?;
+                let mobility_input_config = get_mobility_input_config_list(&simulated_reconnects);
+                (
+                    max_fixed_id + 1,
+                    mobility_input_config,
+                    simulated_reconnects,
+                )
+            } else if let Some(model_config) = self.paths.get_mobility_model_config() {
+                println!("generating simulated reconnects from {:?} mobility model", model_config.model);
+                let simulated_reconnects = model_config.get_simulated_reconnects(
+                    &topology,
+                    self.parameters.reconnect_runtime,
+                    WorkerIds::STATEFUL,
+                )?;
+                fs::write(
+                    output_trajectory_directory.join("topology_updates.json"),
+                    serde_json::to_string_pretty(&simulated_reconnects)?,
+                )?;
+                let mobility_input_config = get_mobility_input_config_list(&simulated_reconnects);
+                (
+                    max_fixed_id + 1,
+                    mobility_input_config,
+                    simulated_reconnects,
+                )
+            } else {
+                return Err("No path set for mobility input config".into());
+            };
+        let update_offsets = self.get_update_offsets(&simulated_reconnects.topology_updates);
+
         println!("generating fixed worker configs");
         let mut next_free_port = 5000;
         let mut fixed_config_paths = vec![];
-        let num_buffers = self.get_data_production_time().as_millis()
-            / self.default_source_input.gathering_interval.as_millis();
+        let num_buffers = self
+            .get_rate_profile(&simulated_reconnects.topology_updates)
+            .buffer_count(self.default_source_input.gathering_interval, self.get_data_production_time()) as u128;
         let mut total_number_of_tuples_to_emit = 0;
-        let mut max_fixed_id = 0;
+        let mut input_sources = vec![];
         for (input_id, location) in &topology.nodes {
-            if input_id > &max_fixed_id {
-                max_fixed_id = *input_id;
-            }
             let (physical_sources, number_of_slots) = self.get_physical_sources_for_node(
                 numberOfTuplesToProducePerBuffer,
-                num_buffers,
                 &mut total_number_of_tuples_to_emit,
+                &mut input_sources,
                 *input_id + 1,
-            );
+                &update_offsets,
+            )?;
             let worker_config = FixedWorkerConfig {
                 // rpcPort: next_free_port,
                 // dataPort: next_free_port + 1,
@@ -1196,7 +1410,10 @@ impl InputConfig {
                 dataPort: None,
                 //numberOfSlots: 6000, //todo: set to 1 to stress test the plan creation
                 //numberOfSlots: number_of_slots,
-                numberOfSlots: number_of_slots.unwrap_or(*topology.slots.get(input_id).unwrap()),
+                numberOfSlots: match number_of_slots {
+                    Some(number_of_slots) => number_of_slots,
+                    None => *topology.slots.get(input_id).ok_or(format!("no slots set for node {} in the fixed topology", input_id))?,
+                },
                 nodeSpatialType: "FIXED_LOCATION".to_string(),
                 fieldNodeLocationCoordinates: format!("{}, {}", location[0], location[1]),
                 workerId: *input_id + 1,
@@ -1215,30 +1432,6 @@ impl InputConfig {
             fixed_config_paths.push(yaml_path);
         }
 
-        //println!("reading mobility config from {}", self.paths.get_mobility_config_list_path().to_str().unwrap());
-        println!("creating mobility config from");
-        let mut mobile_config_paths = vec![];
-        //let mobility_input_config = MobilityInputConfigList::read_input_from_file(&self.paths.get_mobility_config_list_path())?;
-        let mobility_input_config_path_option = &self.paths.get_mobility_config_list_path();
-        //let mut input_id = max_fixed_id + 1;
-
-        let (mut input_id, mobility_input_config, simulated_reconnects) =
-            if let Some(path) = mobility_input_config_path_option {
-                // let mobility_input_config = MobilityInputConfigList::read_input_from_file(&path)?;
-                // (max_fixed_id + 1, mobility_input_config)
-                println!("trying to create mobility input config from simulated reconnects file");
-                let json_string = std::fs::read_to_string(&path)?;
-                let simulated_reconnects: SimulatedReconnects =
-                    serde_json::from_str(json_string.as_str())?;
-                let mobility_input_config = simulated_reconnects.get_mobility_input_config_list();
-                (
-                    max_fixed_id + 1,
-                    mobility_input_config,
-                    simulated_reconnects,
-                )
-            } else {
-                panic!("No path set for mobility input config")
-            };
 
         let mut generated_mobility_configs = vec![];
         let mut central_topology_update_list = rest_node_relocation::TopologyUpdateList::new();
@@ -1261,10 +1454,11 @@ impl InputConfig {
 
             let (physical_sources, number_of_slots) = self.get_physical_sources_for_node(
                 numberOfTuplesToProducePerBuffer,
-                num_buffers,
                 &mut total_number_of_tuples_to_emit,
+                &mut input_sources,
                 input_id + 1,
-            );
+                &update_offsets,
+            )?;
 
             //create config
             let worker_config = MobileWorkerConfig {
@@ -1317,11 +1511,6 @@ impl InputConfig {
             }
         }
 
-        let total_number_of_tuples_to_emit = if JOIN_QUERY{
-            total_number_of_tuples_to_emit / 2
-        } else {
-            total_number_of_tuples_to_emit
-        };
         Ok(ExperimentSetup {
             output_config_directory,
             output_source_input_directory,
@@ -1341,6 +1530,7 @@ impl InputConfig {
             num_buffers,
             generated_folder: generated_folder.to_path_buf(),
             simulated_reconnects,
+            input_sources,
             //central_topology_updates: cvec,
             // central_topology_updates,
             // initial_topology_update,
@@ -1350,12 +1540,13 @@ impl InputConfig {
     fn get_physical_sources_for_node(
         &mut self,
         numberOfTuplesToProducePerBuffer: u64,
-        num_buffers: u128,
         total_number_of_tuples_to_ingest: &mut u64,
+        input_sources: &mut Vec<InputSourceConfig>,
         input_id: u64,
-    ) -> (Vec<PhysicalSource>, Option<u16>) {
+        update_offsets: &[Duration],
+    ) -> Result<(Vec<PhysicalSource>, Option<u16>), Box<dyn Error>> {
         let place_default_sources_on_node_ids =
-            fs::read_to_string(&self.parameters.place_default_sources_on_node_ids_path)
+            fs::read_to_string(self.get_place_default_sources_on_node_ids_path())
                 .expect("Failed to read place_default_sources_on_node_ids");
         let place_default_sources_on_node_ids: HashMap<u64, Vec<u64>> =
             serde_json::from_str(&place_default_sources_on_node_ids)
@@ -1373,8 +1564,6 @@ impl InputConfig {
         let (physical_sources, number_of_slots) = if let Some((_, logical_source_names)) =
             place_default_sources_on_node_ids.get_key_value(&input_id.to_string())
         {
-            let num_tuples =
-                num_buffers as u64 * self.default_source_input.tuples_per_buffer as u64;
             let mut sources = vec![];
 
             //iterate over logical source names
@@ -1385,30 +1574,41 @@ impl InputConfig {
                     .entry(logical_source_name.clone())
                     .or_insert(0);
                 *source_count += 1;
-
-                //if this is a join query, modify the desired line count
-                let num_tuples = if JOIN_QUERY {
-                    get_expected_join_output_count(num_tuples, self.parameters.window_size, self.parameters.join_match_interval)
+                let source_count = *source_count;
+                let source_name = format!("{}s{}", logical_source_name, source_count);
+                let input_source = self.get_source_input_defaults(update_offsets).add_source(input_sources, &source_name, input_id)?;
+
+                //if this is a join query, modify the desired line count. sources are joined as {name}s1 with {name}s2,
+                //{name}s3 with {name}s4 and so on, so the output of a pair is counted once its second source is added
+                let num_tuples = if !JOIN_QUERY {
+                    input_source.tuple_count()
+                } else if source_count % 2 == 0 {
+                    let partner_name = format!("{}s{}", logical_source_name, source_count - 1);
+                    let partner = input_sources
+                        .iter()
+                        .find(|source| source.logical_source_name == partner_name)
+                        .expect("first source of the join pair was not added");
+                    let join_keys = JoinKeyRule::Interval(self.parameters.join_match_interval);
+                    JoinPairModel {
+                        left: JoinInput { source_id: partner.source_id, tuple_count: partner.tuple_count(), join_keys },
+                        right: JoinInput { source_id: input_source.source_id, tuple_count: input_source.tuple_count(), join_keys },
+                        window_size: self.parameters.window_size,
+                    }
+                    .expected_count()
                 } else {
-                    num_tuples
+                    0
                 };
 
-                println!("Adding source: {}, (add {} to {})", logical_source_name, num_tuples, *total_number_of_tuples_to_ingest);
+                println!("Adding source: {}, (add {} to {})", source_name, num_tuples, *total_number_of_tuples_to_ingest);
                 *total_number_of_tuples_to_ingest += num_tuples;
                 // println!("{}, {}: raise number of tuples to ingest by {} to {}", index, logical_source_name, num_tuples, *total_number_of_tuples_to_ingest);
-                let logical_source_name = format!("{}s{}", logical_source_name, source_count);
-                println!("{}", logical_source_name);
-                sources.push(PhysicalSource {
-                    logicalSourceName: logical_source_name,
-                    physicalSourceName: format!("physical_{}", index).to_owned(),
-                    Type: PhysicalSourceType::CSV_SOURCE,
-                    configuration: PhysicalSourceConfiguration {
-                        filePath: self.parameters.source_input_server_port.to_string(),
-                        skipHeader: false,
-                        sourceGatheringInterval: time::Duration::from_millis(0),
-                        numberOfTuplesToProducePerBuffer,
-                    },
-                });
+                println!("{} has source id {} on port {}", source_name, input_source.source_id, input_source.port);
+                sources.push(PhysicalSource::for_input_source(
+                    source_name,
+                    format!("physical_{}", index),
+                    &input_source,
+                    numberOfTuplesToProducePerBuffer,
+                ));
             }
 
             let source_count = sources.len() as u16;
@@ -1416,7 +1616,7 @@ impl InputConfig {
         } else {
             (vec![], None)
         };
-        (physical_sources, number_of_slots)
+        Ok((physical_sources, number_of_slots))
     }
 }
 
@@ -1445,18 +1645,6 @@ impl MobilityInputConfigList {
     }
 }
 
-#[serde_as]
-#[derive(Debug, Deserialize, Serialize)]
-struct MobileWorkerWaypoint {
-    #[serde(rename = "column1")]
-    latitude: f64,
-    #[serde(rename = "column2")]
-    longitude: f64,
-    #[serde_as(as = "DurationNanoSeconds<u64>")]
-    #[serde(rename = "column3")]
-    offset: Duration,
-}
-
 #[serde_as]
 #[derive(Debug, Deserialize, Serialize)]
 pub struct PrecalculatedReconnect {
@@ -1467,14 +1655,6 @@ pub struct PrecalculatedReconnect {
     pub offset: Duration,
 }
 
-#[derive(Deserialize, Debug)]
-pub struct FixedTopology {
-    //todo: check if we can just make that a tuple
-    pub nodes: HashMap<u64, Vec<f64>>,
-    pub slots: HashMap<u64, u16>,
-    pub children: HashMap<u64, Vec<u64>>,
-}
-
 #[derive(Deserialize, Debug)]
 struct ActualTopology {
     edges: Vec<Edge>,
@@ -1511,6 +1691,9 @@ struct ConnectivityReply {
 #[derive(Debug, Serialize, Deserialize)]
 enum PhysicalSourceType {
     CSV_SOURCE,
+    TCP_SOURCE,
+    UDP_SOURCE,
+    MQTT_SOURCE,
 }
 
 #[serde_as]
@@ -1557,6 +1740,36 @@ struct TCPSourceConfiguration {
     flushIntervalMS: u64,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct UDPSourceConfiguration {
+    host: String,
+    port: u16,
+    format: SourceInputFormat,
+    tupleSeparator: char,
+    flushIntervalMS: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MQTTSourceConfiguration {
+    url: String,
+    clientId: String,
+    userName: String,
+    topic: String,
+    qos: u8,
+    cleanSession: bool,
+    flushIntervalMS: u64,
+    inputFormat: SourceInputFormat,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum SourceConfiguration {
+    CSV(PhysicalSourceConfiguration),
+    TCP(TCPSourceConfiguration),
+    UDP(UDPSourceConfiguration),
+    MQTT(MQTTSourceConfiguration),
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct PhysicalSource {
     logicalSourceName: String,
@@ -1564,7 +1777,68 @@ struct PhysicalSource {
     #[serde(rename(deserialize = "type"))]
     #[serde(rename(serialize = "type"))]
     Type: PhysicalSourceType,
-    configuration: PhysicalSourceConfiguration,
+    configuration: SourceConfiguration,
+}
+
+impl PhysicalSource {
+    //source reading the tuples the input server emits for the source with the protocol of the source
+    fn for_input_source(logical_source_name: String, physical_source_name: String, input_source: &InputSourceConfig, tuples_per_buffer: u64) -> Self {
+        let host = "127.0.0.1".to_string();
+        let flush_interval = input_source.gathering_interval.as_millis() as u64;
+        let (source_type, configuration) = match input_source.protocol {
+            SourceProtocol::BINARY => (
+                PhysicalSourceType::CSV_SOURCE,
+                SourceConfiguration::CSV(PhysicalSourceConfiguration {
+                    filePath: input_source.port.to_string(),
+                    skipHeader: false,
+                    sourceGatheringInterval: time::Duration::from_millis(0),
+                    numberOfTuplesToProducePerBuffer: tuples_per_buffer,
+                }),
+            ),
+            SourceProtocol::TCP => (
+                PhysicalSourceType::TCP_SOURCE,
+                SourceConfiguration::TCP(TCPSourceConfiguration {
+                    socketDomain: SocketDomain::AF_INET,
+                    socketType: SocketType::SOCKET_STREAM,
+                    port: input_source.port,
+                    host,
+                    format: SourceInputFormat::CSV,
+                    decideMessageSize: DecidedmMessageSize::TUPLE_SEPARATOR,
+                    tupleSeparator: input_source.tuple_separator,
+                    flushIntervalMS: flush_interval,
+                }),
+            ),
+            SourceProtocol::UDP => (
+                PhysicalSourceType::UDP_SOURCE,
+                SourceConfiguration::UDP(UDPSourceConfiguration {
+                    host,
+                    port: input_source.port,
+                    format: SourceInputFormat::CSV,
+                    tupleSeparator: input_source.tuple_separator,
+                    flushIntervalMS: flush_interval,
+                }),
+            ),
+            SourceProtocol::MQTT => (
+                PhysicalSourceType::MQTT_SOURCE,
+                SourceConfiguration::MQTT(MQTTSourceConfiguration {
+                    url: format!("tcp://{}:{}", host, input_source.port),
+                    clientId: format!("worker{}_{}", input_source.node_id, physical_source_name),
+                    userName: "nes".to_string(),
+                    topic: logical_source_name.clone(),
+                    qos: 0,
+                    cleanSession: true,
+                    flushIntervalMS: flush_interval,
+                    inputFormat: SourceInputFormat::CSV,
+                }),
+            ),
+        };
+        Self {
+            logicalSourceName: logical_source_name,
+            physicalSourceName: physical_source_name,
+            Type: source_type,
+            configuration,
+        }
+    }
 }
 
 //todo: also add the coordinator port
@@ -1660,12 +1934,6 @@ enum LocationProviderType {
     INVALID,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-enum FieldType {
-    FLOAT64,
-    UINT64,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 struct LogicalSourceField {
     name: String,
@@ -1674,6 +1942,18 @@ struct LogicalSourceField {
     Type: FieldType,
 }
 
+impl LogicalSourceField {
+    fn from_source_fields(fields: &[SourceField]) -> Vec<Self> {
+        fields
+            .iter()
+            .map(|field| LogicalSourceField {
+                name: field.name.to_string(),
+                Type: field.field_type,
+            })
+            .collect()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct LogicalSource {
     logicalSourceName: String,
@@ -1767,68 +2047,20 @@ enum WorkerConfigType {
     Mobile(MobileWorkerConfig),
 }
 
-#[derive(Debug, Deserialize, Copy, Clone)]
+#[derive(Debug, Deserialize, Serialize, Copy, Clone)]
 pub enum OutputType {
     CSV,
     AVRO,
+    PARQUET,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct OutputTuple {
-    win_start: u64,
-    win_end: u64,
-    id_1: u64,
-    join_id_1: u64,
-    sequence_number_1: u64,
-    event_time_1: u64,
-    processing_time_1: u64,
-    emission_time_1: u64,
-    id_2: u64,
-    join_id_2: u64,
-    sequence_number_2: u64,
-    event_time_2: u64,
-    processing_time_2: u64,
-    emission_time_2: u64,
-}
-
-impl OutputTuple {
-    fn from_bytes(bytes: &[u8]) -> Self {
-        let mut cursor = Cursor::new(bytes);
-        let win_start = byteorder::ReadBytesExt::read_u64::<LittleEndian>(&mut cursor).unwrap();
-        let win_end = byteorder::ReadBytesExt::read_u64::<LittleEndian>(&mut cursor).unwrap();
-        let id_1 = byteorder::ReadBytesExt::read_u64::<LittleEndian>(&mut cursor).unwrap();
-        let join_id_1 = byteorder::ReadBytesExt::read_u64::<LittleEndian>(&mut cursor).unwrap();
-        let sequence_number_1 =
-            byteorder::ReadBytesExt::read_u64::<LittleEndian>(&mut cursor).unwrap();
-        let event_time_1 = byteorder::ReadBytesExt::read_u64::<LittleEndian>(&mut cursor).unwrap();
-        let processing_time_1 =
-            byteorder::ReadBytesExt::read_u64::<LittleEndian>(&mut cursor).unwrap();
-        let emission_time_1 =
-            byteorder::ReadBytesExt::read_u64::<LittleEndian>(&mut cursor).unwrap();
-        let id_2 = byteorder::ReadBytesExt::read_u64::<LittleEndian>(&mut cursor).unwrap();
-        let join_id_2 = byteorder::ReadBytesExt::read_u64::<LittleEndian>(&mut cursor).unwrap();
-        let sequence_number_2 =
-            byteorder::ReadBytesExt::read_u64::<LittleEndian>(&mut cursor).unwrap();
-        let event_time_2 = byteorder::ReadBytesExt::read_u64::<LittleEndian>(&mut cursor).unwrap();
-        let processing_time_2 =
-            byteorder::ReadBytesExt::read_u64::<LittleEndian>(&mut cursor).unwrap();
-        let emission_time_2 =
-            byteorder::ReadBytesExt::read_u64::<LittleEndian>(&mut cursor).unwrap();
-        Self {
-            win_start,
-            win_end,
-            id_1,
-            join_id_1,
-            sequence_number_1,
-            event_time_1,
-            processing_time_1,
-            emission_time_1,
-            id_2,
-            join_id_2,
-            sequence_number_2,
-            event_time_2,
-            processing_time_2,
-            emission_time_2,
+impl OutputType {
+    //extension of the result files written in this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputType::CSV => "csv",
+            OutputType::AVRO => "avro",
+            OutputType::PARQUET => "parquet",
         }
     }
 }
@@ -1856,31 +2088,8 @@ impl OutputWriter for AvroOutputWriter {
     }
 
     fn flush(&mut self) -> Result<(), Box<dyn Error>> {
-        let raw_schema = r#"
-            {
-                "type": "record",
-                "name": "experiment_output",
-                "fields": [
-                    {"name": "win_start", "type": "long"},
-                    {"name": "win_end", "type": "long"},
-                    {"name": "id_1", "type": "long"},
-                    {"name": "join_id_1", "type": "long"},
-                    {"name": "sequence_number_1", "type": "long"},
-                    {"name": "event_time_1", "type": "long"},
-                    {"name": "processing_time_1", "type": "long"},
-                    {"name": "emission_time_1", "type": "long"},
-                    {"name": "id_2", "type": "long"},
-                    {"name": "join_id_2", "type": "long"},
-                    {"name": "sequence_number_2", "type": "long"},
-                    {"name": "event_time_2", "type": "long"},
-                    {"name": "processing_time_2", "type": "long"},
-                    {"name": "emission_time_2", "type": "long"}
-                ]
-            }
-            "#;
-        let schema = Schema::parse_str(raw_schema).unwrap();
-
-        println!("{:?}", schema);
+        let raw_schema = OutputTuple::avro_schema();
+        let schema = Schema::parse_str(&raw_schema).unwrap();
 
         let mut writer = Writer::new(&schema, Vec::new());
 
@@ -1895,17 +2104,58 @@ impl OutputWriter for AvroOutputWriter {
     }
 }
 
+pub struct ParquetOutputWriter {
+    file: File,
+    buffer: Vec<OutputTuple>,
+}
+
+impl ParquetOutputWriter {
+    pub fn new(file: File) -> Self {
+        Self {
+            file,
+            buffer: Vec::new(),
+        }
+    }
+}
+
+impl OutputWriter for ParquetOutputWriter {
+    fn write(&mut self, tuple: OutputTuple) -> Result<(), Box<dyn Error>> {
+        self.buffer.push(tuple);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+        let message_type = OutputTuple::parquet_message_type();
+        let rows = self.buffer.iter().map(|tuple| tuple.to_values()).collect_vec();
+        write_u64_rows(&mut self.file, &message_type, &rows, PARQUET_ROW_GROUP_SIZE)?;
+        Ok(())
+    }
+}
+
 pub struct FileOutputWriter {
     pub file: File,
+    header_written: bool,
+}
+
+impl FileOutputWriter {
+    pub fn new(file: File) -> Self {
+        Self {
+            file,
+            header_written: false,
+        }
+    }
 }
 
 impl OutputWriter for FileOutputWriter {
     fn write(&mut self, tuple: OutputTuple) -> Result<(), Box<dyn Error>> {
-        todo!()
-        // let tuple_string = format!("{},{},{},{},{}", tuple.id, tuple.sequence_number, tuple.event_time, tuple.processing_time, tuple.emission_time);
-        // self.file.write_all(tuple_string.as_bytes())?;
-        // self.file.write_all(b"\n")?;
-        // Ok(())
+        if !self.header_written {
+            self.file.write_all(OutputTuple::csv_header().as_bytes())?;
+            self.file.write_all(b"\n")?;
+            self.header_written = true;
+        }
+        self.file.write_all(tuple.to_csv_row().as_bytes())?;
+        self.file.write_all(b"\n")?;
+        Ok(())
     }
 
     fn flush(&mut self) -> Result<(), Box<dyn Error>> {
@@ -1929,85 +2179,37 @@ pub async fn handle_connection<W: ?Sized + OutputWriter>(
     start_time: SystemTime,
     experiment_duration: Duration,
     output_type: OutputType,
-) -> Result<(), Box<dyn Error>> {
-    // Create a buffer reader for the incoming data
-    //let mut reader = tokio::io::BufReader::new(stream);
-    //let mut line = String::new();
-    let mut buf = vec![];
+    validator: Arc<Mutex<JoinValidator>>,
+    completeness: Arc<Mutex<CompletenessAnalyzer>>,
+) -> Result<ConnectionStats, Box<dyn Error>> {
     let mut lines = 0;
-    let tuple_size = std::mem::size_of::<OutputTuple>();
-    // Iterate over the lines received from the client and write them to the CSV file
-    loop {
-        //while let Ok(bytes_read) = reader.read_line(&mut line).await {
-        //exit if shutdown was triggered
-        if shutdown_triggered.load(SeqCst) {
-            println!("shutdown triggered, exiting tuple reader loop");
-            break;
-        }
-        
-        if buf.len() / tuple_size >= desired_line_count as usize {
-            println!("All tuples received for thread, exiting tuple reader loop");
-            break;
-        }
-
-        let current_time = SystemTime::now();
-        if let Ok(elapsed_time) = current_time.duration_since(start_time) {
-            if elapsed_time > experiment_duration {
-                println!("Timeout reached, exiting tuple reader loop");
-                break;
-            }
-        }
-
-        // Increment the line count
-        //line_count.fetch_add(1, Ordering::SeqCst);
-        // Check if the maximum number of lines has been written
-        // if line_count.load(SeqCst) >= desired_line_count as usize {
-        //     break;
-        // }
-        // if bytes_read == 0 {
-        //     break; // EOF, so end the loop
-        // }
-        if let Ok(_bytes_read) = stream.try_read_buf(&mut buf) {}
-        tokio::time::sleep(Duration::from_millis(100)).await;
-    }
-
-    //let mut reader = tokio::io::BufReader::new(&*buf);
+    let tuple_size = OutputTuple::tuple_size();
+    //only complete tuples are returned, a partial tuple at the end of the stream is dropped and reported in the stats
+    let (buf, mut stats) = receive_tuples(
+        stream,
+        tuple_size,
+        Some(desired_line_count),
+        shutdown_triggered,
+        start_time,
+        experiment_duration,
+    )
+    .await;
 
     println!("Counting tuples an writing file");
 
-    //count all line breaks in the received buffer
-    // let mut current_valid_byte_count = 0;
-    // let mut total_valid_byte_count = 0;
-    // for &byte in &buf {
-    //     current_valid_byte_count += 1;
-    //     if byte == 10u8 {
-    //         line_count.fetch_add(1, Ordering::SeqCst);
-    //         total_valid_byte_count += current_valid_byte_count;
-    //         current_valid_byte_count = 0;
-    //     }
-    // }
-    //
-    // // sanity check
-    // for &byte in &buf[total_valid_byte_count..] {
-    //     if byte == 10u8 {
-    //         panic!("Sanity check failed, found line break after valid byte count");
-    //     }
-    // }
-    //file.write_all(&buf[0..total_valid_byte_count])?;
-
-
-    let valid_bytes = buf.len() - (buf.len() % tuple_size);
     let mut lock = file.lock().unwrap();
-    
-    let mut seen_seq_nunbers = HashSet::new();
-    
+    let mut lock_validator = validator.lock().unwrap();
+    let mut lock_completeness = completeness.lock().unwrap();
 
     match output_type {
         OutputType::CSV => {
             //todo: reactivate
-            for i in (0..valid_bytes).step_by(tuple_size) {
+            for binary_tuple in buf.chunks(tuple_size) {
+                let Some(output_tuple) = OutputTuple::from_bytes(binary_tuple) else {
+                    stats.decode_errors += 1;
+                    continue;
+                };
                 line_count.fetch_add(1, Ordering::SeqCst);
-                let binary_tuple = &buf[i..i + tuple_size];
                 // let tuple_string = get_tuple_string(tuple);
                 // lock.write_all(tuple_string.as_bytes())?;
                 // lock.write_all(b"\n")?;
@@ -2019,30 +2221,33 @@ pub async fn handle_connection<W: ?Sized + OutputWriter>(
                 //     processing_time: u64::from_le_bytes([binary_tuple[24], binary_tuple[25], binary_tuple[26], binary_tuple[27], binary_tuple[28], binary_tuple[29], binary_tuple[30], binary_tuple[31]]),
                 //     emission_time: u64::from_le_bytes([binary_tuple[32], binary_tuple[33], binary_tuple[34], binary_tuple[35], binary_tuple[36], binary_tuple[37], binary_tuple[38], binary_tuple[39]]),
                 // };
-                let output_tuple = OutputTuple::from_bytes(binary_tuple);
+                let violations = lock_validator.check(&output_tuple);
+                if !violations.is_empty() {
+                    println!("Invalid join tuple {:?} at tuple count {}: {:?}", output_tuple, lines, violations);
+                }
+                lock_completeness.record(output_tuple.id_1, output_tuple.sequence_number_1, output_tuple.event_time_1);
+                lock_completeness.record(output_tuple.id_2, output_tuple.sequence_number_2, output_tuple.event_time_2);
 
                 // let mut lock = file.lock().unwrap();
-                lock.write(output_tuple)?
+                lock.write(output_tuple)?;
+                lines += 1;
             }
         }
-        OutputType::AVRO => {
+        OutputType::AVRO | OutputType::PARQUET => {
 
-            for i in (0..valid_bytes).step_by(tuple_size) {
+            for binary_tuple in buf.chunks(tuple_size) {
+                let Some(output_tuple) = OutputTuple::from_bytes(binary_tuple) else {
+                    stats.decode_errors += 1;
+                    continue;
+                };
                 line_count.fetch_add(1, Ordering::SeqCst);
-                let binary_tuple = &buf[i..i + tuple_size];
-
-                let output_tuple = OutputTuple::from_bytes(binary_tuple);
-                
-                assert_eq!(output_tuple.sequence_number_1, output_tuple.sequence_number_2);
-                assert_eq!(output_tuple.join_id_1, output_tuple.sequence_number_1 * 1000);
-                assert_eq!(output_tuple.join_id_2, output_tuple.sequence_number_2 * 1000);
-                assert_ne!(output_tuple.id_1, output_tuple.id_2);
-                let seen_check = (output_tuple.id_1, output_tuple.sequence_number_1);
-                if seen_seq_nunbers.contains(&seen_check) {
-                    // dbg!(seen_seq_nunbers);
-                    println!("Duplicate sequence number found: {} tuple count {}", output_tuple.sequence_number_1, lines);
+
+                let violations = lock_validator.check(&output_tuple);
+                if !violations.is_empty() {
+                    println!("Invalid join tuple {:?} at tuple count {}: {:?}", output_tuple, lines, violations);
                 }
-                seen_seq_nunbers.insert(seen_check);
+                lock_completeness.record(output_tuple.id_1, output_tuple.sequence_number_1, output_tuple.event_time_1);
+                lock_completeness.record(output_tuple.id_2, output_tuple.sequence_number_2, output_tuple.event_time_2);
 
                 lock.write(output_tuple)?;
                 lines += 1;
@@ -2068,7 +2273,7 @@ pub async fn handle_connection<W: ?Sized + OutputWriter>(
                 // record.put("output_timestamp", i64::try_from(output_timestamp)?);
                 // writer.append(record)?;
             }
-            println!("{} lines converted to avro", lines);
+            println!("{} lines converted to {:?}", lines, output_type);
         }
     }
 
@@ -2080,7 +2285,7 @@ pub async fn handle_connection<W: ?Sized + OutputWriter>(
         desired_line_count_total,
     );
 
-    Ok(())
+    Ok(stats)
 }
 
 fn get_tuple_string(binary_tuple: &[u8]) -> String {
@@ -2278,23 +2483,19 @@ fn create_input_source_data(
     Ok(file_path)
 }
 
+//expected output of joining two sources that each emit num_tuples tuples
 pub fn get_expected_join_output_count(
     num_tuples: u64,
     window_size: u64,
     join_match_interval: u64,
 ) -> u64 {
-    println!("num tuples: {}", num_tuples);
-    let finished_windows = ((num_tuples  - 1) / window_size) - 1;
-    println!("finished windows: {}", finished_windows);
-    let processed_tuples = finished_windows * window_size;
-    println!("processed tuples: {}", processed_tuples);
-    
-    //the seq nr starts at zero so the index that we match on is actually one less than the number of tuples
-    //we always match the zero so we need to always add one
-    let matched_tuples = ((processed_tuples - 1) / join_match_interval) + 1;
-    println!("matched tuples: {}", matched_tuples);
-
-    matched_tuples
+    let join_keys = JoinKeyRule::Interval(join_match_interval);
+    JoinPairModel {
+        left: JoinInput { source_id: 1, tuple_count: num_tuples, join_keys },
+        right: JoinInput { source_id: 2, tuple_count: num_tuples, join_keys },
+        window_size,
+    }
+    .expected_count()
 }
 
 #[cfg(test)]
@@ -2345,4 +2546,25 @@ mod tests {
             get_expected_join_output_count(num_tuples, window_size, join_match_interval);
         assert_eq!(expected_output_count, 299);
     }
+
+    #[test]
+    fn test_reading_generated_scenario_config() {
+        let topology: FixedTopology = serde_json::from_str(r#"{"nodes": {"0": [53.35, -6.3], "1": [53.36, -6.3]}, "children": {"0": [1]}}"#).unwrap();
+        let reconnects = SimulatedReconnects { initial_parents: vec![(2, 3)], topology_updates: vec![] };
+        let placements = HashMap::from([(2, vec![1]), (3, vec![1])]);
+        let directory = std::env::temp_dir().join(format!("stateful_scenario_{}", std::process::id()));
+        let config_path = simulation_runner_rs::mobility_scenario::write_scenario(&directory, &topology, &reconnects, &placements, WorkerIds::STATEFUL).unwrap();
+        let mut config = MultiSimulationInputConfig::read_input_from_file(&config_path).unwrap();
+        assert!(config.default_config.parameters.place_default_sources_on_node_ids_path.is_relative());
+        let directory = directory.canonicalize().unwrap();
+        config.default_config.paths.set_base_path(directory.clone());
+        assert!(config.default_config.get_place_default_sources_on_node_ids_path().exists());
+        assert_eq!(config.default_config.paths.get_mobility_config_list_path(), Some(directory.join("topology_updates.json")));
+        let placements = placements.iter().map(|(worker, sources)| (worker.to_string(), sources.iter().map(|source| source.to_string()).collect())).collect();
+        let queries = config.default_config.get_query_strings(&placements).unwrap();
+        assert_eq!(queries.len(), 1);
+        assert!(queries[0].starts_with("Query::sink2("));
+        assert!(["{INPUT", "{SINK}", "{WINDOW_SIZE}", "{JOINS}"].iter().all(|placeholder| !queries[0].contains(placeholder)));
+        fs::remove_dir_all(directory).unwrap();
+    }
 }
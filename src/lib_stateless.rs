@@ -29,7 +29,6 @@ use nes_tools::query::SubmitQueryResponse;
 use nes_tools::topology::{AddEdgeReply, AddEdgeRequest, ExecuteQueryRequest, PlacementStrategyType};
 use serde_with::serde_as;
 use yaml_rust::{YamlEmitter, YamlLoader};
-use FieldType::UINT64;
 use WorkerConfigType::Fixed;
 use serde_with::DurationMilliSeconds;
 use serde_with::DurationNanoSeconds;
@@ -38,44 +37,53 @@ use tokio::io::AsyncBufReadExt;
 use regex::Regex;
 use relative_path::RelativePathBuf;
 use itertools::Itertools;
-use crate::rest_node_relocation::TopologyUpdate;
+use simulation_runner_rs::topology::{write_device_placements, FixedTopology, SimulatedReconnects, TopologyUpdate, WorkerIds};
 use crate::LogLevel;
+use crate::parquet_output::{write_u64_rows, PARQUET_ROW_GROUP_SIZE};
+use crate::tuple_schema::{FieldType, SinkTuple, SourceField, StatelessOutputTuple as OutputTuple, STATELESS_SOURCE_FIELDS};
+use crate::sink_reader::{receive_tuples, ConnectionStats};
+use crate::completeness::CompletenessAnalyzer;
+use simulation_runner_rs::data_generation::DataGenerationSpec;
+use simulation_runner_rs::dataset_replay::DatasetReplaySpec;
+use simulation_runner_rs::emission_pipeline::{BackpressurePolicy, DEFAULT_CHANNEL_CAPACITY};
+use simulation_runner_rs::ingest_stats::EmissionState;
+use simulation_runner_rs::input_server::InputServerConfig;
+use simulation_runner_rs::input_sources::{default_tuple_separator, resolve_reconnect_bursts, InputSourceConfig, SourceInputDefaults, SourceInputOverride};
+use simulation_runner_rs::source_protocol::SourceProtocol;
+use simulation_runner_rs::rate_profile::RateProfile;
 
 
 const INPUT_FOLDER_SUB_PATH: &'static str = "nes_experiment_input";
 const INPUT_CONFIG_NAME: &'static str = "input_data_config.toml";
 //const PORT_RANGE: std::ops::Range<u16> = 10_000..20_000;
 const PORT_RANGE: std::ops::Range<u16> = 7000..8000;
-
-
-#[derive(Serialize, Deserialize, Clone)]
-pub struct SimulatedReconnects {
-    pub initial_parents: Vec<(u64, u64)>,
-    pub topology_updates: Vec<TopologyUpdate>,
-}
-
-impl SimulatedReconnects {
-    pub fn get_mobility_input_config_list(&self) -> MobilityInputConfigList {
-        let mut mobility_configs = vec![];
-        for initial in &self.initial_parents {
-            let generated_mobility_config = InputMobilityconfig {
-                mobility_base_path: None,
-                //locationProviderConfig: output_trajectory_path,
-                locationProviderConfig: RelativePathBuf::from_path("invalid").unwrap(),
-                //locationProviderType: "CSV".to_owned(),
-                locationProviderType: "BASE".to_owned(),
-                // locationProviderConfig: String::from(output_trajectory_path.to_str()
-                //     .ok_or("Could not get output trajectory path")?),
-                reconnectPredictorType: PRECALCULATED,
-                //precalcReconnectPath: output_precalculated_reconnects,
-                precalcReconnectPath: RelativePathBuf::from_path("invalid").unwrap(),
-            };
-            mobility_configs.push(generated_mobility_config.clone());
-        }
-        MobilityInputConfigList {
-            worker_mobility_configs: mobility_configs,
-            central_topology_update_list_path: None,
-        }
+const REST_PORT: u16 = 8081;
+//rpc and data port of the coordinator, which are not set in the coordinator config and keep the nes defaults
+const COORDINATOR_PORTS: std::ops::Range<u16> = 4000..4002;
+//the source ports of the input server must not overlap the ports of the nes processes
+const RESERVED_PORTS: [std::ops::Range<u16>; 3] = [PORT_RANGE, REST_PORT..REST_PORT + 1, COORDINATOR_PORTS];
+
+
+fn get_mobility_input_config_list(simulated_reconnects: &SimulatedReconnects) -> MobilityInputConfigList {
+    let mut mobility_configs = vec![];
+    for initial in &simulated_reconnects.initial_parents {
+        let generated_mobility_config = InputMobilityconfig {
+            mobility_base_path: None,
+            //locationProviderConfig: output_trajectory_path,
+            locationProviderConfig: RelativePathBuf::from_path("invalid").unwrap(),
+            //locationProviderType: "CSV".to_owned(),
+            locationProviderType: "BASE".to_owned(),
+            // locationProviderConfig: String::from(output_trajectory_path.to_str()
+            //     .ok_or("Could not get output trajectory path")?),
+            reconnectPredictorType: PRECALCULATED,
+            //precalcReconnectPath: output_precalculated_reconnects,
+            precalcReconnectPath: RelativePathBuf::from_path("invalid").unwrap(),
+        };
+        mobility_configs.push(generated_mobility_config.clone());
+    }
+    MobilityInputConfigList {
+        worker_mobility_configs: mobility_configs,
+        central_topology_update_list_path: None,
     }
 }
 
@@ -318,7 +326,7 @@ impl SimulationConfig {
 
         //iterate over all subfolders in retrial path and check for tuple_count files
         let mut setups = vec![];
-        let re = Regex::new(r"out_run:(\d+)\.csvtuple_count\.csv").unwrap();
+        let re = Regex::new(r"out_run:(\d+)\.(?:csv|avro|parquet)tuple_count\.csv").unwrap();
         for entry in fs::read_dir(self.run_for_retrial_path.as_ref().unwrap())? {
             let entry = entry?;
             let path = entry.path();
@@ -414,7 +422,7 @@ impl SimulationConfig {
         //let input_config_list = multi_simulation_config.generate_input_configs();
         println!("writing setups");
         let mut setups = vec![];
-        for (short_name, input_config, runs) in input_config_list {
+        for (short_name, mut input_config, runs) in input_config_list {
             let generated_folder = generated_main_folder.join(short_name);
             fs::create_dir_all(&generated_folder)?;
             let input_config_copy_path = generated_folder.join("input_config_copy.toml");
@@ -484,6 +492,9 @@ pub struct Parameters {
     placementAmendmentThreadCount: u16,
     #[serde(default)]
     pub query_duplication_factor: usize,
+    //overrides the output type of the simulation config for this experiment
+    #[serde(default)]
+    pub output_type: Option<OutputType>,
 }
 
 
@@ -494,6 +505,33 @@ pub struct DefaultSourceInput {
     #[serde_as(as = "DurationMilliSeconds<u64>")]
     pub gathering_interval: Duration,
     pub source_input_method: SourceInputMethod,
+    //toml file describing how the values of the source fields are generated, see data_generation. the input server uses
+    //its default distributions if unset
+    #[serde(default)]
+    pub data_generation_spec: Option<PathBuf>,
+    #[serde(default)]
+    pub rate_profile: RateProfile,
+    //replay a recorded dataset instead of generating tuples
+    #[serde(default)]
+    pub dataset_replay: Option<DatasetReplaySpec>,
+    //input parameters of single sources by logical source name
+    #[serde(default)]
+    pub source_overrides: HashMap<String, SourceInputOverride>,
+    //what the input server does with buffers the socket can not take, see emission_pipeline
+    #[serde(default)]
+    pub backpressure_policy: BackpressurePolicy,
+    #[serde(default = "default_channel_capacity")]
+    pub channel_capacity: usize,
+    //how the tuples reach the workers and which nes source type the worker configs use, see source_protocol
+    #[serde(default)]
+    pub protocol: SourceProtocol,
+    //ends every tuple of the text based protocols
+    #[serde(default = "default_tuple_separator")]
+    pub tuple_separator: char,
+}
+
+fn default_channel_capacity() -> usize {
+    DEFAULT_CHANNEL_CAPACITY
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -519,10 +557,11 @@ where
 }
 
 pub mod config {
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
     use relative_path::RelativePathBuf;
     use serde::{Deserialize, Serialize};
     use crate::MobileDeviceQuadrants::QuadrantConfig;
+    use simulation_runner_rs::mobility_models::MobilityModelConfig;
 
     #[derive(Debug, Deserialize, Serialize, Clone)]
     pub struct Paths {
@@ -538,6 +577,7 @@ pub mod config {
     #[derive(Debug, Deserialize, Serialize, Clone)]
     pub enum MobileTopologyInput {
         Quadrants(QuadrantConfig),
+        MobilityModel(MobilityModelConfig),
         #[serde(deserialize_with = "super::deserialize_relative_path")]
         TrajectoriesDir(RelativePathBuf),
 
@@ -559,6 +599,14 @@ pub mod config {
             }
         }
 
+        pub fn get_mobility_model_config(&self) -> Option<MobilityModelConfig> {
+            if let MobileTopologyInput::MobilityModel(config) = &self.mobile_trajectories_directory {
+                Some(config.clone())
+            } else {
+                None
+            }
+        }
+
         pub fn get_mobile_trajectories_directory(&self) -> Option<PathBuf> {
             if let MobileTopologyInput::TrajectoriesDir(dir) = &self.mobile_trajectories_directory {
                 Some(dir.to_path(self.base_path.as_ref().expect("base path not set")))
@@ -581,6 +629,11 @@ pub mod config {
         pub fn set_base_path(&mut self, base_path: PathBuf) {
             self.base_path = Some(base_path);
         }
+
+        //a path of the config relative to the folder of the config file, absolute paths are kept as they are
+        pub fn resolve_path(&self, path: &Path) -> PathBuf {
+            self.base_path.as_ref().expect("base path not set").join(path)
+        }
     }
 }
 
@@ -611,6 +664,7 @@ pub struct ExperimentSetup {
     pub total_number_of_tuples_to_ingest: u64,
     pub num_buffers: u128,
     pub simulated_reconnects: SimulatedReconnects,
+    pub input_sources: Vec<InputSourceConfig>,
     // pub central_topology_updates: Vec<TopologyUpdate>,
     // pub initial_topology_update: Option<Vec<(u64, u64)>>,
 }
@@ -636,13 +690,39 @@ pub fn get_reconnect_list(rest_port: u16) -> Result<ReconnectList, Box<dyn Error
 }
 
 impl ExperimentSetup {
+    //input server of an attempt, hosted by the runner. the ingestion start is the time since the unix epoch
+    pub fn input_server_config(&self, ingestion_start: Duration) -> Result<InputServerConfig, Box<dyn Error>> {
+        let input_config = &self.input_config;
+        let topology_updates = &self.simulated_reconnects.topology_updates;
+        let mut spec = match &input_config.default_source_input.data_generation_spec {
+            Some(path) => DataGenerationSpec::from_file(path)?,
+            None => DataGenerationSpec::default_stateless(),
+        };
+        spec.resolve_seed();
+        Ok(InputServerConfig {
+            hostname: "127.0.0.1".to_string(),
+            port: input_config.parameters.source_input_server_port,
+            num_buffers: self.num_buffers as usize,
+            buffer_size: input_config.default_source_input.tuples_per_buffer,
+            gathering_interval: input_config.default_source_input.gathering_interval,
+            deadline: ingestion_start,
+            spec,
+            rate_profile: input_config.get_rate_profile(topology_updates),
+            dataset_replay: input_config.get_dataset_replay(),
+            sources: Some(resolve_reconnect_bursts(&self.input_sources, &input_config.get_update_offsets(topology_updates))),
+            initial_state: EmissionState::RUNNING,
+            backpressure: input_config.default_source_input.backpressure_policy,
+            channel_capacity: input_config.default_source_input.channel_capacity,
+        })
+    }
+
     pub fn start(&mut self, executable_paths: &NesExecutablePaths, shutdown_triggered: Arc<AtomicBool>, log_level: &LogLevel) -> Result<(), Box<dyn Error>> {
         self.kill_processes();
         self.fixed_worker_processes = vec![];
         self.mobile_worker_processes = vec![];
 
         //let rest_port = get_available_port(PORT_RANGE).ok_or("Could not find available port")?;
-        let rest_port = 8081;
+        let rest_port = REST_PORT;
 
         self.start_coordinator(&executable_paths.coordinator_path, Arc::clone(&shutdown_triggered), rest_port, &log_level)?;
 
@@ -822,14 +902,118 @@ impl InputConfig {
     //     }
     // }
 
+    //send times of the topology updates measured from the ingestion deadline, which is also the origin of rate profiles
+    pub fn get_update_offsets(&self, topology_updates: &[TopologyUpdate]) -> Vec<Duration> {
+        let reconnect_end = self.parameters.warmup + self.parameters.reconnect_runtime;
+        topology_updates
+            .iter()
+            .map(|update| self.parameters.warmup + update.timestamp.mul_f64(self.parameters.speedup_factor))
+            .filter(|offset| *offset <= reconnect_end)
+            .collect()
+    }
+
+    //rate profile of an attempt with reconnect bursts placed at the send times of the topology updates
+    pub fn get_rate_profile(&self, topology_updates: &[TopologyUpdate]) -> RateProfile {
+        self.default_source_input.rate_profile.resolve_reconnect_bursts(&self.get_update_offsets(topology_updates))
+    }
+
+    fn get_source_input_defaults<'a>(&'a self, update_offsets: &'a [Duration]) -> SourceInputDefaults<'a> {
+        SourceInputDefaults {
+            tuples_per_buffer: self.default_source_input.tuples_per_buffer,
+            gathering_interval: self.default_source_input.gathering_interval,
+            data_generation_spec: self.default_source_input.data_generation_spec.as_ref(),
+            rate_profile: &self.default_source_input.rate_profile,
+            protocol: self.default_source_input.protocol,
+            tuple_separator: self.default_source_input.tuple_separator,
+            overrides: &self.default_source_input.source_overrides,
+            data_production_time: self.get_data_production_time(),
+            update_offsets,
+            first_port: self.parameters.source_input_server_port,
+            reserved_ports: &RESERVED_PORTS,
+        }
+    }
+
+    //the dataset replay with the speedup factor of the experiment applied to the gaps between rows
+    pub fn get_dataset_replay(&self) -> Option<DatasetReplaySpec> {
+        self.default_source_input.dataset_replay.clone().map(|mut replay| {
+            replay.speedup_factor = self.parameters.speedup_factor;
+            replay
+        })
+    }
+
+    pub fn get_place_default_sources_on_node_ids_path(&self) -> PathBuf {
+        self.paths.resolve_path(&self.parameters.place_default_sources_on_node_ids_path)
+    }
+
     pub fn get_data_production_time(&self) -> Duration {
         self.parameters.warmup + self.parameters.reconnect_runtime + self.parameters.cooldown_time
     }
     pub fn get_total_time(&self) -> Duration {
         self.parameters.deployment_time_offset + self.parameters.warmup + self.parameters.reconnect_runtime + self.parameters.cooldown_time + self.parameters.post_cooldown_time
     }
-    fn generate_output_config(&self, generated_folder: &Path) -> Result<ExperimentSetup, Box<dyn Error>> {
+    //one query per logical source, duplicated with null sinks by the query duplication factor. the placements map
+    //worker ids to the logical source names
+    pub fn get_query_strings(&self, place_default_sources_on_node_ids: &HashMap<String, Vec<String>>) -> Vec<String> {
+        let mut query_strings = vec![];
+        for id in place_default_sources_on_node_ids
+            .values()
+            .flatten()
+            .unique()
+        {
+            let input_replaced = self.parameters.query_string.replace("{INPUT}", &id.to_string());
+            let sink_string = format!("FileSinkDescriptor::create(\"{}:{{OUTPUT}}\", \"CSV_FORMAT\", \"true\")", id);
+            let tcp_sink = input_replaced.replace("{SINK}", &sink_string);
+            let null_sink =
+                input_replaced.replace("{SINK}", "NullOutputSinkDescriptor::create()");
+            query_strings.push(tcp_sink);
+            for _i in 0..self.parameters.query_duplication_factor {
+                query_strings.push(null_sink.clone());
+            }
+        }
+        query_strings
+    }
+
+    //custom data generation specs and dataset replays have to produce the tuples of the logical sources registered at
+    //the coordinator
+    fn validate_data_generation_specs(&self) -> Result<(), Box<dyn Error>> {
+        let overrides = self.default_source_input.source_overrides.values().filter_map(|source| source.data_generation_spec.as_ref());
+        for path in self.default_source_input.data_generation_spec.iter().chain(overrides) {
+            DataGenerationSpec::from_file(path)?
+                .validate_source_fields(STATELESS_SOURCE_FIELDS)
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+        if let Some(replay) = &self.default_source_input.dataset_replay {
+            replay
+                .validate()
+                .and_then(|_| replay.validate_source_fields(STATELESS_SOURCE_FIELDS))
+                .map_err(|e| format!("dataset replay {}: {}", replay.path.display(), e))?;
+        }
+        Ok(())
+    }
+
+    //in the quadrant and mobility model modes the sources of the devices are added to the configured placements. the
+    //merged placements are written to the generated folder and replace the configured path, so everything reading them
+    //later sees the devices
+    fn place_device_sources(&mut self, generated_folder: &Path) -> Result<(), Box<dyn Error>> {
+        let (device_ids, device_sources) = if let Some(quadrant_config) = self.paths.get_quadrant_config() {
+            (quadrant_config.device_ids(), quadrant_config.device_sources)
+        } else if let Some(model_config) = self.paths.get_mobility_model_config() {
+            let topology: FixedTopology = serde_json::from_str(&fs::read_to_string(self.paths.get_fixed_topology_nodes_path())?)?;
+            let first_mobile_worker_id = WorkerIds::STATELESS.first_mobile_worker_id(&topology);
+            (first_mobile_worker_id..first_mobile_worker_id + model_config.num_devices as u64, model_config.device_sources)
+        } else {
+            return Ok(());
+        };
+        let generated_path = generated_folder.join("place_default_sources_on_node_ids.json");
+        write_device_placements(&self.get_place_default_sources_on_node_ids_path(), &generated_path, device_ids, &device_sources)?;
+        //the generated folder is not next to the config, so the merged placements are referred to by absolute path
+        self.parameters.place_default_sources_on_node_ids_path = generated_path.canonicalize()?;
+        Ok(())
+    }
+
+    fn generate_output_config(&mut self, generated_folder: &Path) -> Result<ExperimentSetup, Box<dyn Error>> {
         println!("generating output config");
+        self.place_device_sources(generated_folder)?;
         // let input_trajectories_directory = &self.paths.mobile_trajectories_directory;
         let output_config_directory = generated_folder.join("config");
         fs::create_dir_all(&output_config_directory).expect("Failed to create folder");
@@ -848,35 +1032,15 @@ impl InputConfig {
         let mut logicalSources = vec![];
 
         println!("generating logical sources");
-        let place_default_sources_on_node_ids = fs::read_to_string(&self.parameters.place_default_sources_on_node_ids_path).expect("Failed to read place_default_sources_on_node_ids");
+        self.validate_data_generation_specs()?;
+        let place_default_sources_on_node_ids = fs::read_to_string(self.get_place_default_sources_on_node_ids_path()).expect("Failed to read place_default_sources_on_node_ids");
         let place_default_sources_on_node_ids: HashMap<u64, Vec<u64>> = serde_json::from_str(&place_default_sources_on_node_ids).expect("could not parse map of sourcees to nodes");
         let place_default_sources_on_node_ids: HashMap<String, Vec<String>> = place_default_sources_on_node_ids.iter().map(|(k, v)| (k.to_string(), v.clone().iter().map(|x| x.to_string()).collect())).collect();
         for name in place_default_sources_on_node_ids.values().flatten().unique() {
             // for name in &self.parameters.logical_source_names {
             logicalSources.push(LogicalSource {
                 logicalSourceName: name.to_string(),
-                fields: vec![
-                    LogicalSourceField {
-                        name: "id".to_string(),
-                        Type: UINT64,
-                    },
-                    LogicalSourceField {
-                        name: "value".to_string(),
-                        Type: UINT64,
-                    },
-                    LogicalSourceField {
-                        name: "ingestion_timestamp".to_string(),
-                        Type: UINT64,
-                    },
-                    LogicalSourceField {
-                        name: "processing_timestamp".to_string(),
-                        Type: UINT64,
-                    },
-                    LogicalSourceField {
-                        name: "output_timestamp".to_string(),
-                        Type: UINT64,
-                    },
-                ],
+                fields: LogicalSourceField::from_source_fields(STATELESS_SOURCE_FIELDS),
             });
         }
 
@@ -905,17 +1069,51 @@ impl InputConfig {
             SourceInputMethod::TCP => { 0 }
         };
 
+        //the reconnects are generated before the sources are added, the rate profiles of the sources place their bursts
+        //at the topology updates
+        let max_fixed_id = *topology.nodes.keys().max().unwrap_or(&0);
+        //println!("reading mobility config from {}", self.paths.get_mobility_config_list_path().to_str().unwrap());
+        println!("creating mobility config from");
+        let mut mobile_config_paths = vec![];
+        //let mobility_input_config = MobilityInputConfigList::read_input_from_file(&self.paths.get_mobility_config_list_path())?;
+        let mobility_input_config_path_option = &self.paths.get_mobility_config_list_path();
+        //let mut input_id = max_fixed_id + 1;
+
+        let (mut input_id, mobility_input_config, simulated_reconnects) = if let Some(path) = mobility_input_config_path_option {
+            // let mobility_input_config = MobilityInputConfigList::read_input_from_file(&path)?;
+            // (max_fixed_id + 1, mobility_input_config)
+            println!("trying to create mobility input config from simulated reconnects file");
+            let json_string = std::fs::read_to_string(&path)?;
+            let simulated_reconnects: SimulatedReconnects = serde_json::from_str(json_string.as_str())?;
+            let mobility_input_config = get_mobility_input_config_list(&simulated_reconnects);
+            (max_fixed_id + 1, mobility_input_config, simulated_reconnects)
+        } else if let Some(quadrant_config) = self.paths.get_quadrant_config() {
+            println!("generating simulated reconnects from quadrant rotation");
+            let simulated_reconnects = quadrant_config.get_simulated_reconnects(self.parameters.reconnect_runtime, WorkerIds::STATELESS.first_mobile_worker_id(&topology))?;
+            fs::write(output_trajectory_directory.join("topology_updates.json"), serde_json::to_string_pretty(&simulated_reconnects)?)?;
+            let mobility_input_config = get_mobility_input_config_list(&simulated_reconnects);
+            (max_fixed_id + 1, mobility_input_config, simulated_reconnects)
+        } else if let Some(model_config) = self.paths.get_mobility_model_config() {
+            println!("generating simulated reconnects from {:?} mobility model", model_config.model);
+            let simulated_reconnects = model_config.get_simulated_reconnects(&topology, self.parameters.reconnect_runtime, WorkerIds::STATELESS)?;
+            fs::write(output_trajectory_directory.join("topology_updates.json"), serde_json::to_string_pretty(&simulated_reconnects)?)?;
+            let mobility_input_config = get_mobility_input_config_list(&simulated_reconnects);
+            (max_fixed_id + 1, mobility_input_config, simulated_reconnects)
+        } else {
+            return Err("No path set for mobility input config".into());
+        };
+        let update_offsets = self.get_update_offsets(&simulated_reconnects.topology_updates);
+
         println!("generating fixed worker configs");
         let mut next_free_port = 5000;
         let mut fixed_config_paths = vec![];
-        let num_buffers = self.get_data_production_time().as_millis() / self.default_source_input.gathering_interval.as_millis();
+        let num_buffers = self
+            .get_rate_profile(&simulated_reconnects.topology_updates)
+            .buffer_count(self.default_source_input.gathering_interval, self.get_data_production_time()) as u128;
         let mut total_number_of_tuples_to_ingest = 0;
-        let mut max_fixed_id = 0;
+        let mut input_sources = vec![];
         for (input_id, location) in &topology.nodes {
-            if input_id > &max_fixed_id {
-                max_fixed_id = *input_id;
-            }
-            let (physical_sources, number_of_slots) = self.get_physical_sources_for_node(numberOfTuplesToProducePerBuffer, num_buffers, &mut total_number_of_tuples_to_ingest, *input_id);
+            let (physical_sources, number_of_slots) = self.get_physical_sources_for_node(numberOfTuplesToProducePerBuffer, &mut total_number_of_tuples_to_ingest, &mut input_sources, *input_id, &update_offsets)?;
             let worker_config = FixedWorkerConfig {
                 // rpcPort: next_free_port,
                 // dataPort: next_free_port + 1,
@@ -923,7 +1121,10 @@ impl InputConfig {
                 dataPort: None,
                 //numberOfSlots: 6000, //todo: set to 1 to stress test the plan creation
                 //numberOfSlots: number_of_slots,
-                numberOfSlots: number_of_slots.unwrap_or(*topology.slots.get(input_id).unwrap()),
+                numberOfSlots: match number_of_slots {
+                    Some(number_of_slots) => number_of_slots,
+                    None => *topology.slots.get(input_id).ok_or(format!("no slots set for node {} in the fixed topology", input_id))?,
+                },
                 nodeSpatialType: "FIXED_LOCATION".to_string(),
                 fieldNodeLocationCoordinates: format!("{}, {}", location[0], location[1]),
                 workerId: *input_id,
@@ -941,24 +1142,6 @@ impl InputConfig {
         }
 
 
-        //println!("reading mobility config from {}", self.paths.get_mobility_config_list_path().to_str().unwrap());
-        println!("creating mobility config from");
-        let mut mobile_config_paths = vec![];
-        //let mobility_input_config = MobilityInputConfigList::read_input_from_file(&self.paths.get_mobility_config_list_path())?;
-        let mobility_input_config_path_option = &self.paths.get_mobility_config_list_path();
-        //let mut input_id = max_fixed_id + 1;
-
-        let (mut input_id, mobility_input_config, simulated_reconnects) = if let Some(path) = mobility_input_config_path_option {
-            // let mobility_input_config = MobilityInputConfigList::read_input_from_file(&path)?;
-            // (max_fixed_id + 1, mobility_input_config)
-            println!("trying to create mobility input config from simulated reconnects file");
-            let json_string = std::fs::read_to_string(&path)?;
-            let simulated_reconnects: SimulatedReconnects = serde_json::from_str(json_string.as_str())?;
-            let mobility_input_config = simulated_reconnects.get_mobility_input_config_list();
-            (max_fixed_id + 1, mobility_input_config, simulated_reconnects)
-        } else {
-            panic!("No path set for mobility input config")
-        };
 
 
         let mut generated_mobility_configs = vec![];
@@ -980,7 +1163,7 @@ impl InputConfig {
             };
             generated_mobility_configs.push(generated_mobility_config.clone());
 
-            let (physical_sources, number_of_slots) = self.get_physical_sources_for_node(numberOfTuplesToProducePerBuffer, num_buffers, &mut total_number_of_tuples_to_ingest, input_id);
+            let (physical_sources, number_of_slots) = self.get_physical_sources_for_node(numberOfTuplesToProducePerBuffer, &mut total_number_of_tuples_to_ingest, &mut input_sources, input_id, &update_offsets)?;
 
             //create config
             let worker_config = MobileWorkerConfig {
@@ -1046,34 +1229,31 @@ impl InputConfig {
             num_buffers,
             generated_folder: generated_folder.to_path_buf(),
             simulated_reconnects,
+            input_sources,
             //central_topology_updates: cvec,
             // central_topology_updates,
             // initial_topology_update,
         })
     }
 
-    fn get_physical_sources_for_node(&self, numberOfTuplesToProducePerBuffer: u64, num_buffers: u128, total_number_of_tuples_to_ingest: &mut u64, input_id: u64) -> (Vec<PhysicalSource>, Option<u16>) {
-        let place_default_sources_on_node_ids = fs::read_to_string(&self.parameters.place_default_sources_on_node_ids_path).expect("Failed to read place_default_sources_on_node_ids");
+    fn get_physical_sources_for_node(&self, numberOfTuplesToProducePerBuffer: u64, total_number_of_tuples_to_ingest: &mut u64, input_sources: &mut Vec<InputSourceConfig>, input_id: u64, update_offsets: &[Duration]) -> Result<(Vec<PhysicalSource>, Option<u16>), Box<dyn Error>> {
+        let place_default_sources_on_node_ids = fs::read_to_string(self.get_place_default_sources_on_node_ids_path()).expect("Failed to read place_default_sources_on_node_ids");
         let place_default_sources_on_node_ids: HashMap<u64, Vec<u64>> = serde_json::from_str(&place_default_sources_on_node_ids).expect("could not parse map of sourcees to nodes");
         let place_default_sources_on_node_ids: HashMap<String, Vec<String>> = place_default_sources_on_node_ids.iter().map(|(k, v)| (k.to_string(), v.clone().iter().map(|x| x.to_string()).collect())).collect();
         let (physical_sources, number_of_slots) = if let Some((_, logical_source_names)) = place_default_sources_on_node_ids.get_key_value(&input_id.to_string()) {
-            let num_tuples = num_buffers as u64 * self.default_source_input.tuples_per_buffer as u64;
+            let source_input_defaults = self.get_source_input_defaults(update_offsets);
             let mut sources = vec![];
 
             //iterate over logical source names
             for (index, logical_source_name) in logical_source_names.iter().enumerate() {
-                *total_number_of_tuples_to_ingest += num_tuples;
-                sources.push(PhysicalSource {
-                    logicalSourceName: logical_source_name.to_string(),
-                    physicalSourceName: format!("physical_{}", index).to_owned(),
-                    Type: PhysicalSourceType::CSV_SOURCE,
-                    configuration: PhysicalSourceConfiguration {
-                        filePath: self.parameters.source_input_server_port.to_string(),
-                        skipHeader: false,
-                        sourceGatheringInterval: time::Duration::from_millis(0),
-                        numberOfTuplesToProducePerBuffer,
-                    },
-                });
+                let input_source = source_input_defaults.add_source(input_sources, logical_source_name, input_id)?;
+                *total_number_of_tuples_to_ingest += input_source.tuple_count();
+                sources.push(PhysicalSource::for_input_source(
+                    logical_source_name.to_string(),
+                    format!("physical_{}", index),
+                    &input_source,
+                    numberOfTuplesToProducePerBuffer,
+                ));
             }
 
             let source_count = sources.len() as u16;
@@ -1081,7 +1261,7 @@ impl InputConfig {
         } else {
             (vec![], None)
         };
-        (physical_sources, number_of_slots)
+        Ok((physical_sources, number_of_slots))
     }
 }
 
@@ -1111,18 +1291,6 @@ impl MobilityInputConfigList {
     }
 }
 
-#[serde_as]
-#[derive(Debug, Deserialize, Serialize)]
-struct MobileWorkerWaypoint {
-    #[serde(rename = "column1")]
-    latitude: f64,
-    #[serde(rename = "column2")]
-    longitude: f64,
-    #[serde_as(as = "DurationNanoSeconds<u64>")]
-    #[serde(rename = "column3")]
-    offset: Duration,
-}
-
 #[serde_as]
 #[derive(Debug, Deserialize, Serialize)]
 pub struct PrecalculatedReconnect {
@@ -1133,15 +1301,6 @@ pub struct PrecalculatedReconnect {
     pub offset: Duration,
 }
 
-
-#[derive(Deserialize, Debug)]
-pub struct FixedTopology {
-    //todo: check if we can just make that a tuple
-    pub nodes: HashMap<u64, Vec<f64>>,
-    pub slots: HashMap<u64, u16>,
-    pub children: HashMap<u64, Vec<u64>>,
-}
-
 #[derive(Deserialize, Debug)]
 struct ActualTopology {
     edges: Vec<Edge>,
@@ -1177,7 +1336,10 @@ struct ConnectivityReply {
 
 #[derive(Debug, Serialize, Deserialize)]
 enum PhysicalSourceType {
-    CSV_SOURCE
+    CSV_SOURCE,
+    TCP_SOURCE,
+    UDP_SOURCE,
+    MQTT_SOURCE,
 }
 
 #[serde_as]
@@ -1199,17 +1361,17 @@ enum SocketDomain {
 
 #[derive(Debug, Serialize, Deserialize)]
 enum SocketType {
-    SOCKET_STREAM
+    SOCKET_STREAM,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 enum SourceInputFormat {
-    CSV
+    CSV,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 enum DecidedmMessageSize {
-    TUPLE_SEPARATOR
+    TUPLE_SEPARATOR,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -1224,6 +1386,36 @@ struct TCPSourceConfiguration {
     flushIntervalMS: u64,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct UDPSourceConfiguration {
+    host: String,
+    port: u16,
+    format: SourceInputFormat,
+    tupleSeparator: char,
+    flushIntervalMS: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MQTTSourceConfiguration {
+    url: String,
+    clientId: String,
+    userName: String,
+    topic: String,
+    qos: u8,
+    cleanSession: bool,
+    flushIntervalMS: u64,
+    inputFormat: SourceInputFormat,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum SourceConfiguration {
+    CSV(PhysicalSourceConfiguration),
+    TCP(TCPSourceConfiguration),
+    UDP(UDPSourceConfiguration),
+    MQTT(MQTTSourceConfiguration),
+}
+
 
 #[derive(Debug, Serialize, Deserialize)]
 struct PhysicalSource {
@@ -1232,7 +1424,68 @@ struct PhysicalSource {
     #[serde(rename(deserialize = "type"))]
     #[serde(rename(serialize = "type"))]
     Type: PhysicalSourceType,
-    configuration: PhysicalSourceConfiguration,
+    configuration: SourceConfiguration,
+}
+
+impl PhysicalSource {
+    //source reading the tuples the input server emits for the source with the protocol of the source
+    fn for_input_source(logical_source_name: String, physical_source_name: String, input_source: &InputSourceConfig, tuples_per_buffer: u64) -> Self {
+        let host = "127.0.0.1".to_string();
+        let flush_interval = input_source.gathering_interval.as_millis() as u64;
+        let (source_type, configuration) = match input_source.protocol {
+            SourceProtocol::BINARY => (
+                PhysicalSourceType::CSV_SOURCE,
+                SourceConfiguration::CSV(PhysicalSourceConfiguration {
+                    filePath: input_source.port.to_string(),
+                    skipHeader: false,
+                    sourceGatheringInterval: time::Duration::from_millis(0),
+                    numberOfTuplesToProducePerBuffer: tuples_per_buffer,
+                }),
+            ),
+            SourceProtocol::TCP => (
+                PhysicalSourceType::TCP_SOURCE,
+                SourceConfiguration::TCP(TCPSourceConfiguration {
+                    socketDomain: SocketDomain::AF_INET,
+                    socketType: SocketType::SOCKET_STREAM,
+                    port: input_source.port,
+                    host,
+                    format: SourceInputFormat::CSV,
+                    decideMessageSize: DecidedmMessageSize::TUPLE_SEPARATOR,
+                    tupleSeparator: input_source.tuple_separator,
+                    flushIntervalMS: flush_interval,
+                }),
+            ),
+            SourceProtocol::UDP => (
+                PhysicalSourceType::UDP_SOURCE,
+                SourceConfiguration::UDP(UDPSourceConfiguration {
+                    host,
+                    port: input_source.port,
+                    format: SourceInputFormat::CSV,
+                    tupleSeparator: input_source.tuple_separator,
+                    flushIntervalMS: flush_interval,
+                }),
+            ),
+            SourceProtocol::MQTT => (
+                PhysicalSourceType::MQTT_SOURCE,
+                SourceConfiguration::MQTT(MQTTSourceConfiguration {
+                    url: format!("tcp://{}:{}", host, input_source.port),
+                    clientId: format!("worker{}_{}", input_source.node_id, physical_source_name),
+                    userName: "nes".to_string(),
+                    topic: logical_source_name.clone(),
+                    qos: 0,
+                    cleanSession: true,
+                    flushIntervalMS: flush_interval,
+                    inputFormat: SourceInputFormat::CSV,
+                }),
+            ),
+        };
+        Self {
+            logicalSourceName: logical_source_name,
+            physicalSourceName: physical_source_name,
+            Type: source_type,
+            configuration,
+        }
+    }
 }
 
 
@@ -1319,12 +1572,6 @@ enum LocationProviderType {
     INVALID,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-enum FieldType {
-    FLOAT64,
-    UINT64,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 struct LogicalSourceField {
     name: String,
@@ -1333,6 +1580,12 @@ struct LogicalSourceField {
     Type: FieldType,
 }
 
+impl LogicalSourceField {
+    fn from_source_fields(fields: &[SourceField]) -> Vec<Self> {
+        fields.iter().map(|field| LogicalSourceField { name: field.name.to_string(), Type: field.field_type }).collect()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct LogicalSource {
     logicalSourceName: String,
@@ -1421,19 +1674,22 @@ enum WorkerConfigType {
     Mobile(MobileWorkerConfig),
 }
 
-#[derive(Debug, Deserialize, Copy, Clone)]
+#[derive(Debug, Deserialize, Serialize, Copy, Clone)]
 pub enum OutputType {
     CSV,
     AVRO,
+    PARQUET,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct OutputTuple {
-    id: u64,
-    sequence_number: u64,
-    event_time: u64,
-    processing_time: u64,
-    emission_time: u64,
+impl OutputType {
+    //extension of the result files written in this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputType::CSV => "csv",
+            OutputType::AVRO => "avro",
+            OutputType::PARQUET => "parquet",
+        }
+    }
 }
 
 pub struct AvroOutputWriter {
@@ -1456,22 +1712,8 @@ impl OutputWriter for AvroOutputWriter {
     }
 
     fn flush(&mut self) -> Result<(), Box<dyn Error>> {
-        let raw_schema = r#"
-            {
-                "type": "record",
-                "name": "experiment_output",
-                "fields": [
-                    {"name": "id", "type": "long"},
-                    {"name": "sequence_number", "type": "long"},
-                    {"name": "event_time", "type": "long"},
-                    {"name": "processing_time", "type": "long"},
-                    {"name": "emission_time", "type": "long"}
-                ]
-            }
-            "#;
-        let schema = Schema::parse_str(raw_schema).unwrap();
-
-        println!("{:?}", schema);
+        let raw_schema = OutputTuple::avro_schema();
+        let schema = Schema::parse_str(&raw_schema).unwrap();
 
         let mut writer = Writer::new(&schema, Vec::new());
 
@@ -1486,14 +1728,50 @@ impl OutputWriter for AvroOutputWriter {
     }
 }
 
+pub struct ParquetOutputWriter {
+    file: File,
+    buffer: Vec<OutputTuple>,
+}
+
+impl ParquetOutputWriter {
+    pub fn new(file: File) -> Self {
+        Self { file, buffer: Vec::new() }
+    }
+}
+
+impl OutputWriter for ParquetOutputWriter {
+    fn write(&mut self, tuple: OutputTuple) -> Result<(), Box<dyn Error>> {
+        self.buffer.push(tuple);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+        let message_type = OutputTuple::parquet_message_type();
+        let rows = self.buffer.iter().map(|tuple| tuple.to_values()).collect_vec();
+        write_u64_rows(&mut self.file, &message_type, &rows, PARQUET_ROW_GROUP_SIZE)?;
+        Ok(())
+    }
+}
+
 pub struct FileOutputWriter {
     pub file: File,
+    header_written: bool,
+}
+
+impl FileOutputWriter {
+    pub fn new(file: File) -> Self {
+        Self { file, header_written: false }
+    }
 }
 
 impl OutputWriter for FileOutputWriter {
     fn write(&mut self, tuple: OutputTuple) -> Result<(), Box<dyn Error>> {
-        let tuple_string = format!("{},{},{},{},{}", tuple.id, tuple.sequence_number, tuple.event_time, tuple.processing_time, tuple.emission_time);
-        self.file.write_all(tuple_string.as_bytes())?;
+        if !self.header_written {
+            self.file.write_all(OutputTuple::csv_header().as_bytes())?;
+            self.file.write_all(b"\n")?;
+            self.header_written = true;
+        }
+        self.file.write_all(tuple.to_csv_row().as_bytes())?;
         self.file.write_all(b"\n")?;
         Ok(())
     }
@@ -1511,104 +1789,46 @@ pub trait OutputWriter {
     fn flush(&mut self) -> Result<(), Box<dyn Error>>;
 }
 
-pub async fn handle_connection<W: ?Sized + OutputWriter>(stream: tokio::net::TcpStream, line_count: Arc<AtomicUsize>, desired_line_count: u64, file: Arc<Mutex<W>>, shutdown_triggered: Arc<AtomicBool>, start_time: SystemTime, experiment_duration: Duration, output_type: OutputType) -> Result<(), Box<dyn Error>> {
-    // Create a buffer reader for the incoming data
-    //let mut reader = tokio::io::BufReader::new(stream);
-    //let mut line = String::new();
-    let mut buf = vec![];
-
-    // Iterate over the lines received from the client and write them to the CSV file
-    loop {
-        //while let Ok(bytes_read) = reader.read_line(&mut line).await {
-        //exit if shutdown was triggered
-        if shutdown_triggered.load(SeqCst) {
-            break;
-        }
-        let current_time = SystemTime::now();
-        if let Ok(elapsed_time) = current_time.duration_since(start_time) {
-            if elapsed_time > experiment_duration {
-                break;
-            }
-        }
-
-
-        // Increment the line count
-        //line_count.fetch_add(1, Ordering::SeqCst);
-        // Check if the maximum number of lines has been written
-        // if line_count.load(SeqCst) >= desired_line_count as usize {
-        //     break;
-        // }
-        // if bytes_read == 0 {
-        //     break; // EOF, so end the loop
-        // }
-        if let Ok(_bytes_read) = stream.try_read_buf(&mut buf) {}
-        tokio::time::sleep(Duration::from_millis(100)).await;
-    }
-
-    //let mut reader = tokio::io::BufReader::new(&*buf);
+pub async fn handle_connection<W: ?Sized + OutputWriter>(stream: tokio::net::TcpStream, line_count: Arc<AtomicUsize>, desired_line_count: u64, file: Arc<Mutex<W>>, shutdown_triggered: Arc<AtomicBool>, start_time: SystemTime, experiment_duration: Duration, output_type: OutputType, completeness: Arc<Mutex<CompletenessAnalyzer>>) -> Result<ConnectionStats, Box<dyn Error>> {
+    let tuple_size = OutputTuple::tuple_size();
+    //the stateless sink is read until the experiment ends, a partial tuple at the end of the stream is dropped and reported in the stats
+    let (buf, mut stats) = receive_tuples(stream, tuple_size, None, shutdown_triggered, start_time, experiment_duration).await;
 
-    println!("Timeout, counting tuples an writing file");
+    println!("Counting tuples an writing file");
 
-    //count all line breaks in the received buffer
-    // let mut current_valid_byte_count = 0;
-    // let mut total_valid_byte_count = 0;
-    // for &byte in &buf {
-    //     current_valid_byte_count += 1;
-    //     if byte == 10u8 {
-    //         line_count.fetch_add(1, Ordering::SeqCst);
-    //         total_valid_byte_count += current_valid_byte_count;
-    //         current_valid_byte_count = 0;
-    //     }
-    // }
-    //
-    // // sanity check
-    // for &byte in &buf[total_valid_byte_count..] {
-    //     if byte == 10u8 {
-    //         panic!("Sanity check failed, found line break after valid byte count");
-    //     }
-    // }
-    //file.write_all(&buf[0..total_valid_byte_count])?;
-
-    let tuple_size = 40;
-    let valid_bytes = buf.len() - (buf.len() % tuple_size);
     let mut lock = file.lock().unwrap();
+    let mut lock_completeness = completeness.lock().unwrap();
 
     match output_type {
         OutputType::CSV => {
             //todo: reactivate
-            for i in (0..valid_bytes).step_by(tuple_size) {
+            for binary_tuple in buf.chunks(tuple_size) {
+                let Some(output_tuple) = OutputTuple::from_bytes(binary_tuple) else {
+                    stats.decode_errors += 1;
+                    continue;
+                };
                 line_count.fetch_add(1, Ordering::SeqCst);
-                let binary_tuple = &buf[i..i + tuple_size];
                 // let tuple_string = get_tuple_string(tuple);
                 // lock.write_all(tuple_string.as_bytes())?;
                 // lock.write_all(b"\n")?;
 
-                let output_tuple = OutputTuple {
-                    id: u64::from_le_bytes([binary_tuple[0], binary_tuple[1], binary_tuple[2], binary_tuple[3], binary_tuple[4], binary_tuple[5], binary_tuple[6], binary_tuple[7]]),
-                    sequence_number: u64::from_le_bytes([binary_tuple[8], binary_tuple[9], binary_tuple[10], binary_tuple[11], binary_tuple[12], binary_tuple[13], binary_tuple[14], binary_tuple[15]]),
-                    event_time: u64::from_le_bytes([binary_tuple[16], binary_tuple[17], binary_tuple[18], binary_tuple[19], binary_tuple[20], binary_tuple[21], binary_tuple[22], binary_tuple[23]]),
-                    processing_time: u64::from_le_bytes([binary_tuple[24], binary_tuple[25], binary_tuple[26], binary_tuple[27], binary_tuple[28], binary_tuple[29], binary_tuple[30], binary_tuple[31]]),
-                    emission_time: u64::from_le_bytes([binary_tuple[32], binary_tuple[33], binary_tuple[34], binary_tuple[35], binary_tuple[36], binary_tuple[37], binary_tuple[38], binary_tuple[39]]),
-                };
+                lock_completeness.record(output_tuple.id, output_tuple.sequence_number, output_tuple.event_time);
 
                 // let mut lock = file.lock().unwrap();
                 lock.write(output_tuple)?
             }
         }
-        OutputType::AVRO => {
+        OutputType::AVRO | OutputType::PARQUET => {
 
             let mut lines = 0;
-            for i in (0..valid_bytes).step_by(tuple_size) {
-                line_count.fetch_add(1, Ordering::SeqCst);
-                let binary_tuple = &buf[i..i + tuple_size];
-
-                let output_tuple = OutputTuple {
-                    id: u64::from_le_bytes([binary_tuple[0], binary_tuple[1], binary_tuple[2], binary_tuple[3], binary_tuple[4], binary_tuple[5], binary_tuple[6], binary_tuple[7]]),
-                    sequence_number: u64::from_le_bytes([binary_tuple[8], binary_tuple[9], binary_tuple[10], binary_tuple[11], binary_tuple[12], binary_tuple[13], binary_tuple[14], binary_tuple[15]]),
-                    event_time: u64::from_le_bytes([binary_tuple[16], binary_tuple[17], binary_tuple[18], binary_tuple[19], binary_tuple[20], binary_tuple[21], binary_tuple[22], binary_tuple[23]]),
-                    processing_time: u64::from_le_bytes([binary_tuple[24], binary_tuple[25], binary_tuple[26], binary_tuple[27], binary_tuple[28], binary_tuple[29], binary_tuple[30], binary_tuple[31]]),
-                    emission_time: u64::from_le_bytes([binary_tuple[32], binary_tuple[33], binary_tuple[34], binary_tuple[35], binary_tuple[36], binary_tuple[37], binary_tuple[38], binary_tuple[39]]),
+            for binary_tuple in buf.chunks(tuple_size) {
+                let Some(output_tuple) = OutputTuple::from_bytes(binary_tuple) else {
+                    stats.decode_errors += 1;
+                    continue;
                 };
+                line_count.fetch_add(1, Ordering::SeqCst);
+
+                lock_completeness.record(output_tuple.id, output_tuple.sequence_number, output_tuple.event_time);
 
                 lock.write(output_tuple)?;
                 lines += 1;
@@ -1635,14 +1855,14 @@ pub async fn handle_connection<W: ?Sized + OutputWriter>(stream: tokio::net::Tcp
                 // writer.append(record)?;
 
             }
-            println!("{} lines converted to avro", lines);
+            println!("{} lines converted to {:?}", lines, output_type);
         }
     }
 
 
     println!("Received {} lines of {}", line_count.load(SeqCst), desired_line_count);
 
-    Ok(())
+    Ok(stats)
 }
 
 
@@ -1786,4 +2006,40 @@ mod tests {
         let log_level: LogLevel = serde_json::from_str("\"LOG_DEBUG\"").unwrap();
         assert_eq!(log_level, LogLevel::LOG_DEBUG);
     }
+
+    #[test]
+    fn test_reading_generated_scenario_config() {
+        let topology: FixedTopology = serde_json::from_str(r#"{"nodes": {"0": [53.35, -6.3], "1": [53.36, -6.3]}, "children": {"0": [1]}}"#).unwrap();
+        let reconnects = SimulatedReconnects { initial_parents: vec![(1, 2)], topology_updates: vec![] };
+        let placements = HashMap::from([(1, vec![1]), (2, vec![1])]);
+        let directory = std::env::temp_dir().join(format!("stateless_scenario_{}", std::process::id()));
+        let config_path = simulation_runner_rs::mobility_scenario::write_scenario(&directory, &topology, &reconnects, &placements, WorkerIds::STATELESS).unwrap();
+        let mut config = MultiSimulationInputConfig::read_input_from_file(&config_path).unwrap();
+        assert!(config.default_config.parameters.place_default_sources_on_node_ids_path.is_relative());
+        let directory = directory.canonicalize().unwrap();
+        config.default_config.paths.set_base_path(directory.clone());
+        assert!(config.default_config.get_place_default_sources_on_node_ids_path().exists());
+        assert_eq!(config.default_config.paths.get_mobility_config_list_path(), Some(directory.join("topology_updates.json")));
+        let placements = placements.iter().map(|(worker, sources)| (worker.to_string(), sources.iter().map(|source| source.to_string()).collect())).collect();
+        let queries = config.default_config.get_query_strings(&placements);
+        assert_eq!(queries.len(), 1);
+        assert!(queries[0].starts_with("Query::from(\"1\")"));
+        assert!(["{INPUT", "{SINK}", "{WINDOW_SIZE}", "{JOINS}"].iter().all(|placeholder| !queries[0].contains(placeholder)));
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_parquet_round_trip() {
+        let path = std::env::temp_dir().join(format!("stateless_out_run:{}.{}", std::process::id(), OutputType::PARQUET.extension()));
+        let tuples = (0..3).map(|i| OutputTuple { id: 1, sequence_number: i, event_time: 10 + i, processing_time: 20 + i, emission_time: 30 + i }).collect_vec();
+        let mut writer = ParquetOutputWriter::new(File::create(&path).unwrap());
+        for tuple in &tuples {
+            writer.write(tuple.clone()).unwrap();
+        }
+        writer.flush().unwrap();
+        let output = crate::analyze::read_output_rows(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(output.field_names.iter().map(String::as_str).eq(OutputTuple::FIELD_NAMES.iter().copied()));
+        assert_eq!(output.rows, tuples.iter().map(|tuple| tuple.to_values()).collect_vec());
+    }
 }
@@ -18,6 +18,15 @@ pub mod lib_stateful;
 pub mod analyze;
 pub mod rest_node_relocation;
 pub mod MobileDeviceQuadrants;
+pub mod parquet_output;
+pub use simulation_runner_rs::tuple_schema;
+pub mod sink_reader;
+pub mod join_validation;
+pub mod completeness;
+pub mod reconnect_analysis;
+pub mod compare;
+pub mod plots;
+pub mod expected_output;
 
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
@@ -39,8 +48,20 @@ pub enum ExperimentType {
 
 fn main() -> Result<(), Box<dyn Error>> {
     let args: Vec<String> = env::args().collect();
-    if args.len() < 5 || args.len() > 8 {
-        eprintln!("Usage: {} <STATEFUL/STATELESS> <nes directory> <experiment input config path> <output directory> <tcp input server executable> <number of runs> <log level (optional)>, <experiment path for retrial (optional)>", args[0]);
+    if args.get(1).map(String::as_str) == Some("analyze") {
+        return analyze::run_analyze_command(&args[2..]);
+    }
+    if args.get(1).map(String::as_str) == Some("reconnects") {
+        return reconnect_analysis::run_reconnects_command(&args[2..]);
+    }
+    if args.get(1).map(String::as_str) == Some("compare") {
+        return compare::run_compare_command(&args[2..]);
+    }
+    if args.len() < 6 || args.len() > 8 {
+        eprintln!("Usage: {} analyze <result file>... (see analyze.rs for options)", args[0]);
+        eprintln!("Usage: {} reconnects <configuration folder>... (see reconnect_analysis.rs for options)", args[0]);
+        eprintln!("Usage: {} compare <sweep folder>", args[0]);
+        eprintln!("Usage: {} <STATEFUL/STATELESS> <nes directory> <experiment input config path> <output directory> <number of runs> <log level (optional)>, <experiment path for retrial (optional)>", args[0]);
         std::process::exit(1);
     }
 
@@ -48,9 +69,8 @@ fn main() -> Result<(), Box<dyn Error>> {
     let nes_root_dir = PathBuf::from(&args[2]);
     let input_config_path = PathBuf::from(&args[3]);
     let output_directory = PathBuf::from(&args[4]);
-    let input_server_path = PathBuf::from(&args[5]);
-    let runs: u64 = args[6].parse().unwrap();
-    let log_level: LogLevel = if args.len() >= 8 {
+    let runs: u64 = args[5].parse().unwrap();
+    let log_level: LogLevel = if args.len() >= 7 {
         println!("Log level: {}", &args[6]);
         serde_json::from_str::<LogLevel>(&format!("\"{}\"", &args[6])).unwrap_or_else(|e| {
             eprintln!("Could not parse log level: {}", e);
@@ -60,7 +80,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         LogLevel::LOG_ERROR
     };
     let run_for_retrial_path = if args.len() == 8 {
-        Some(PathBuf::from(&args[8]))
+        Some(PathBuf::from(&args[7]))
     } else {
         None
     };
@@ -68,10 +88,10 @@ fn main() -> Result<(), Box<dyn Error>> {
     match experiment_type {
         ExperimentType::STATEFUL => {
 
-            run_stateful_simulation(nes_root_dir, input_config_path, output_directory, input_server_path, runs, log_level, run_for_retrial_path)?;
+            run_stateful_simulation(nes_root_dir, input_config_path, output_directory, runs, log_level, run_for_retrial_path)?;
         }
         ExperimentType::STATELESS => {
-            run_stateless_simulation(nes_root_dir, input_config_path, &output_directory, &input_server_path, runs, &log_level, run_for_retrial_path)?;
+            run_stateless_simulation(nes_root_dir, input_config_path, &output_directory, runs, &log_level, run_for_retrial_path)?;
         }
     }
     Ok(())
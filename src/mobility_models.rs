@@ -0,0 +1,332 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use serde_with::DurationMilliSeconds;
+
+use crate::topology::{FixedTopology, SimulatedReconnects, TopologyUpdateList, WorkerIds};
+
+//synthetic movement of mobile devices over the fixed topology. devices move in a plane around the fixed nodes and
+//always connect to the nearest eligible node, only the random walk moves on the topology graph itself. every model
+//is driven by a seeded rng, so the same config always produces the same reconnects
+
+//random walks give up after this many hops without reaching another eligible node
+const MAX_WALK_HOPS: usize = 10_000;
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum MobilityModel {
+    //every device travels to random points in the area of the eligible nodes with the speed in m/s
+    RANDOM_WAYPOINT { speed: f64 },
+    //every step a device walks along the edges of the fixed topology to another eligible node
+    RANDOM_WALK,
+    //groups of devices follow a common random waypoint, each device keeps its offset of up to spread meters
+    GROUP { group_size: usize, speed: f64, spread: f64 },
+    //devices converge on a few random eligible nodes and stay there
+    HOTSPOT { hotspots: usize, speed: f64 },
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MobilityModelConfig {
+    pub model: MobilityModel,
+    pub num_devices: usize,
+    pub seed: u64,
+    //devices move on and reconnect in steps of this interval
+    #[serde_as(as = "DurationMilliSeconds<u64>")]
+    pub step_interval: Duration,
+    //fixed nodes the devices connect to, the leaves of the fixed topology if not set
+    #[serde(default)]
+    pub eligible_parents: Option<Vec<u64>>,
+    //logical sources placed on every device in addition to place_default_sources_on_node_ids_path
+    #[serde(default)]
+    pub device_sources: Vec<u64>,
+}
+
+type Point = (f64, f64);
+
+//positions of the eligible nodes in meters around their centroid, precise enough for the size of a city
+struct Area {
+    nodes: Vec<(u64, Point)>,
+    min: Point,
+    max: Point,
+}
+
+impl Area {
+    fn new(topology: &FixedTopology, eligible_parents: &[u64]) -> Result<Self, Box<dyn Error>> {
+        let locations = eligible_parents
+            .iter()
+            .map(|node| topology.location(*node).map(|location| (*node, location)).ok_or(format!("no location for node {}", node)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if locations.len() < 2 {
+            return Err("mobility models need at least two eligible parents".into());
+        }
+        let center_latitude = locations.iter().map(|(_, location)| location.0).sum::<f64>() / locations.len() as f64;
+        let center_longitude = locations.iter().map(|(_, location)| location.1).sum::<f64>() / locations.len() as f64;
+        let meters_per_degree = 6_371_000.0f64.to_radians();
+        let nodes = locations
+            .into_iter()
+            .map(|(node, (latitude, longitude))| {
+                let x = (longitude - center_longitude) * meters_per_degree * center_latitude.to_radians().cos();
+                let y = (latitude - center_latitude) * meters_per_degree;
+                (node, (x, y))
+            })
+            .collect::<Vec<_>>();
+        let min = nodes.iter().fold((f64::MAX, f64::MAX), |min, (_, point)| (min.0.min(point.0), min.1.min(point.1)));
+        let max = nodes.iter().fold((f64::MIN, f64::MIN), |max, (_, point)| (max.0.max(point.0), max.1.max(point.1)));
+        Ok(Self { nodes, min, max })
+    }
+
+    fn nearest(&self, point: Point) -> u64 {
+        self.nodes
+            .iter()
+            .min_by(|a, b| distance(a.1, point).total_cmp(&distance(b.1, point)))
+            .unwrap()
+            .0
+    }
+
+    fn random_point(&self, rng: &mut StdRng) -> Point {
+        (rng.gen_range(self.min.0..=self.max.0), rng.gen_range(self.min.1..=self.max.1))
+    }
+
+    fn random_node(&self, rng: &mut StdRng) -> (u64, Point) {
+        *self.nodes.choose(rng).unwrap()
+    }
+}
+
+fn distance(from: Point, to: Point) -> f64 {
+    ((to.0 - from.0).powi(2) + (to.1 - from.1).powi(2)).sqrt()
+}
+
+//moves towards the target by at most the given distance
+fn move_towards(from: Point, to: Point, step: f64) -> Point {
+    let remaining = distance(from, to);
+    if remaining <= step {
+        return to;
+    }
+    (from.0 + (to.0 - from.0) * step / remaining, from.1 + (to.1 - from.1) * step / remaining)
+}
+
+struct Waypoint {
+    position: Point,
+    target: Point,
+}
+
+impl Waypoint {
+    fn advance(&mut self, area: &Area, step: f64, rng: &mut StdRng) {
+        self.position = move_towards(self.position, self.target, step);
+        if self.position == self.target {
+            self.target = area.random_point(rng);
+        }
+    }
+}
+
+//undirected adjacency of all nodes of the fixed topology
+fn neighbours(topology: &FixedTopology) -> HashMap<u64, Vec<u64>> {
+    let mut neighbours: HashMap<u64, Vec<u64>> = HashMap::new();
+    let mut parents = topology.children.keys().copied().collect::<Vec<_>>();
+    parents.sort();
+    for parent in parents {
+        for child in &topology.children[&parent] {
+            neighbours.entry(parent).or_default().push(*child);
+            neighbours.entry(*child).or_default().push(parent);
+        }
+    }
+    neighbours
+}
+
+impl MobilityModelConfig {
+    //parents of the devices after every step, the first entry is the initial parent of every device
+    fn parents_per_step(&self, topology: &FixedTopology, steps: usize) -> Result<Vec<Vec<u64>>, Box<dyn Error>> {
+        let eligible_parents = self.eligible_parents.clone().unwrap_or_else(|| topology.leaf_nodes());
+        let area = Area::new(topology, &eligible_parents)?;
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let step_seconds = self.step_interval.as_secs_f64();
+        let mut parents = vec![];
+        match &self.model {
+            MobilityModel::RANDOM_WAYPOINT { speed } => {
+                let mut devices = (0..self.num_devices)
+                    .map(|_| Waypoint { position: area.random_node(&mut rng).1, target: area.random_point(&mut rng) })
+                    .collect::<Vec<_>>();
+                for _ in 0..=steps {
+                    parents.push(devices.iter().map(|device| area.nearest(device.position)).collect());
+                    devices.iter_mut().for_each(|device| device.advance(&area, speed * step_seconds, &mut rng));
+                }
+            }
+            MobilityModel::RANDOM_WALK => {
+                let neighbours = neighbours(topology);
+                let mut current = (0..self.num_devices).map(|_| area.random_node(&mut rng).0).collect::<Vec<_>>();
+                for _ in 0..=steps {
+                    parents.push(current.clone());
+                    for node in current.iter_mut() {
+                        let mut position = *node;
+                        for _ in 0..MAX_WALK_HOPS {
+                            let Some(next) = neighbours.get(&position).and_then(|next| next.choose(&mut rng)) else {
+                                break;
+                            };
+                            position = *next;
+                            if position != *node && eligible_parents.contains(&position) {
+                                *node = position;
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+            MobilityModel::GROUP { group_size, speed, spread } => {
+                if *group_size == 0 {
+                    return Err("group size must be positive".into());
+                }
+                let mut groups = (0..self.num_devices.div_ceil(*group_size))
+                    .map(|_| Waypoint { position: area.random_node(&mut rng).1, target: area.random_point(&mut rng) })
+                    .collect::<Vec<_>>();
+                let offsets = (0..self.num_devices)
+                    .map(|_| (rng.gen_range(-spread..=*spread), rng.gen_range(-spread..=*spread)))
+                    .collect::<Vec<Point>>();
+                for _ in 0..=steps {
+                    parents.push(
+                        offsets
+                            .iter()
+                            .enumerate()
+                            .map(|(device, offset)| {
+                                let reference = groups[device / group_size].position;
+                                area.nearest((reference.0 + offset.0, reference.1 + offset.1))
+                            })
+                            .collect(),
+                    );
+                    groups.iter_mut().for_each(|group| group.advance(&area, speed * step_seconds, &mut rng));
+                }
+            }
+            MobilityModel::HOTSPOT { hotspots, speed } => {
+                let hotspots = area.nodes.choose_multiple(&mut rng, *hotspots).map(|(_, point)| *point).collect::<Vec<_>>();
+                if hotspots.is_empty() {
+                    return Err("hotspot model without hotspots".into());
+                }
+                let mut devices = (0..self.num_devices)
+                    .map(|_| (area.random_node(&mut rng).1, *hotspots.choose(&mut rng).unwrap()))
+                    .collect::<Vec<_>>();
+                for _ in 0..=steps {
+                    parents.push(devices.iter().map(|(position, _)| area.nearest(*position)).collect());
+                    for (position, hotspot) in devices.iter_mut() {
+                        *position = move_towards(*position, *hotspot, speed * step_seconds);
+                    }
+                }
+            }
+        }
+        Ok(parents)
+    }
+
+    //reconnects of the devices within the runtime, the devices are the mobile workers of the runner
+    pub fn get_simulated_reconnects(&self, topology: &FixedTopology, runtime: Duration, worker_ids: WorkerIds) -> Result<SimulatedReconnects, Box<dyn Error>> {
+        if self.step_interval.is_zero() {
+            return Err("mobility model step interval must be positive".into());
+        }
+        match self.model {
+            MobilityModel::RANDOM_WAYPOINT { speed } | MobilityModel::GROUP { speed, .. } | MobilityModel::HOTSPOT { speed, .. }
+                if !(speed.is_finite() && speed > 0.0) =>
+            {
+                return Err(format!("mobility model speed must be positive, got {}", speed).into());
+            }
+            MobilityModel::GROUP { spread, .. } if !(spread.is_finite() && spread >= 0.0) => {
+                return Err(format!("group spread must not be negative, got {}", spread).into());
+            }
+            _ => {}
+        }
+        let steps = (runtime.as_nanos().saturating_sub(1) / self.step_interval.as_nanos()) as usize;
+        let parents = self.parents_per_step(topology, steps)?;
+        let first_mobile_worker_id = worker_ids.first_mobile_worker_id(topology);
+        let initial_parents = parents[0]
+            .iter()
+            .enumerate()
+            .map(|(device, parent)| (worker_ids.fixed_worker_id(*parent), first_mobile_worker_id + device as u64))
+            .collect();
+        let mut updates = TopologyUpdateList::new();
+        for (step, (previous, current)) in parents.iter().zip(parents.iter().skip(1)).enumerate() {
+            let timestamp = self.step_interval * (step as u32 + 1);
+            for (device, (old_parent, new_parent)) in previous.iter().zip(current).enumerate() {
+                if old_parent != new_parent {
+                    let child = first_mobile_worker_id + device as u64;
+                    updates.add_reconnect(timestamp, child, worker_ids.fixed_worker_id(*old_parent), worker_ids.fixed_worker_id(*new_parent));
+                }
+            }
+        }
+        Ok(SimulatedReconnects {
+            initial_parents,
+            topology_updates: updates.into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn topology() -> FixedTopology {
+        //root 0 with the inner nodes 1 and 2, each with three leaves on a line from west to east
+        let nodes = (0..9u64).map(|node| (node, vec![53.35, -6.30 + 0.01 * node as f64])).collect();
+        FixedTopology {
+            nodes,
+            slots: HashMap::new(),
+            children: HashMap::from([(0, vec![1, 2]), (1, vec![3, 4, 5]), (2, vec![6, 7, 8])]),
+        }
+    }
+
+    fn config(model: MobilityModel) -> MobilityModelConfig {
+        MobilityModelConfig {
+            model,
+            num_devices: 6,
+            seed: 7,
+            step_interval: Duration::from_secs(10),
+            eligible_parents: None,
+            device_sources: vec![],
+        }
+    }
+
+    #[test]
+    fn test_models_are_seeded_and_connect_to_eligible_parents() {
+        let topology = topology();
+        let models = [
+            MobilityModel::RANDOM_WAYPOINT { speed: 30.0 },
+            MobilityModel::RANDOM_WALK,
+            MobilityModel::GROUP { group_size: 3, speed: 30.0, spread: 100.0 },
+            MobilityModel::HOTSPOT { hotspots: 1, speed: 30.0 },
+        ];
+        for model in models {
+            let config = config(model);
+            let reconnects = config.get_simulated_reconnects(&topology, Duration::from_secs(600), WorkerIds::STATEFUL).unwrap();
+            let again = config.get_simulated_reconnects(&topology, Duration::from_secs(600), WorkerIds::STATEFUL).unwrap();
+            assert_eq!(serde_json::to_string(&reconnects).unwrap(), serde_json::to_string(&again).unwrap());
+
+            //the leaves 3 to 8 are the workers 4 to 9, the devices follow as 10 to 15
+            let children = reconnects.initial_parents.iter().map(|(_, child)| *child).collect::<Vec<_>>();
+            assert_eq!(children, (10..16).collect::<Vec<_>>());
+            assert!(reconnects.initial_parents.iter().all(|(parent, _)| (4..10).contains(parent)));
+            assert!(!reconnects.topology_updates.is_empty(), "{:?} does not move", config.model);
+            for update in &reconnects.topology_updates {
+                assert!(update.timestamp < Duration::from_secs(600) && update.timestamp.as_secs() % 10 == 0);
+                assert!(update.events.iter().all(|event| (4..10).contains(&event.parent_id)));
+            }
+        }
+
+        for model in [
+            MobilityModel::GROUP { group_size: 3, speed: 30.0, spread: -1.0 },
+            MobilityModel::GROUP { group_size: 3, speed: 30.0, spread: f64::NAN },
+            MobilityModel::RANDOM_WAYPOINT { speed: 0.0 },
+        ] {
+            assert!(config(model).get_simulated_reconnects(&topology, Duration::from_secs(600), WorkerIds::STATEFUL).is_err());
+        }
+    }
+
+    #[test]
+    fn test_hotspot_devices_converge() {
+        let config = config(MobilityModel::HOTSPOT { hotspots: 1, speed: 100.0 });
+        let parents = config.parents_per_step(&topology(), 60).unwrap();
+        let last = parents.last().unwrap();
+        assert!(last.iter().all(|parent| *parent == last[0]));
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::topology::{FixedTopology, SimulatedReconnects, WorkerIds};
+
+//self-contained experiment input for a mobility scenario. the directory holds the fixed topology, the reconnects as
+//topology_updates.json, the source placements and a multi simulation input config that points at them, so running the
+//scenario only needs the config path
+
+pub const FIXED_TOPOLOGY_FILE: &str = "fixed_topology.json";
+pub const TOPOLOGY_UPDATES_FILE: &str = "topology_updates.json";
+pub const SOURCE_PLACEMENT_FILE: &str = "place_default_sources_on_node_ids.json";
+pub const INPUT_CONFIG_FILE: &str = "input_config.toml";
+
+//the values besides the paths and the reconnect runtime are defaults to adjust to the scenario. fields only one of
+//the runners knows are ignored by the other one
+const INPUT_CONFIG_TEMPLATE: &str = r#"enable_query_reconfiguration = [true]
+enable_proactive_deployment = [false]
+tuples_per_buffer = [10]
+speedup_factor = [1.0]
+placementAmendmentThreadCount = [4]
+gathering_interval = [100]
+
+[default_config.parameters]
+enable_query_reconfiguration = true
+enable_proactive_deployment = false
+speedup_factor = 1.0
+deployment_time_offset = 10
+warmup = 10
+reconnect_runtime = {RECONNECT_RUNTIME}
+cooldown_time = 10
+post_cooldown_time = 10
+reconnect_input_type = "PRECALCULATED"
+source_input_server_port = 3000
+query_string = '{QUERY_STRING}'
+reconnect_start_offset = 0
+place_default_sources_on_node_ids_path = {SOURCE_PLACEMENT_PATH}
+num_worker_threads = 1
+placementAmendmentThreadCount = 4
+join_match_interval = 1
+window_size = 10
+
+[default_config.default_source_input]
+tuples_per_buffer = 10
+gathering_interval = 100
+source_input_method = "TCP"
+
+[default_config.paths]
+fixed_topology_nodes = "{FIXED_TOPOLOGY}"
+mobile_trajectories_directory = { TrajectoriesDir = "." }
+"#;
+
+//the stateful runner joins the sources of a logical source pairwise and wraps the joins into a single sink, the
+//stateless runner submits the query once per logical source
+const JOIN_QUERY_STRING: &str = r#"Query::from("{INPUT1}").joinWith(Query::from("{INPUT2}")).where(Attribute("join_id") == Attribute("join_id")).window(TumblingWindow::of(EventTime(Attribute("event_timestamp")), Milliseconds({WINDOW_SIZE})))"#;
+const STATELESS_QUERY_STRING: &str = r#"Query::from("{INPUT}").sink({SINK});"#;
+
+fn query_string(worker_ids: WorkerIds) -> &'static str {
+    match worker_ids {
+        WorkerIds::STATEFUL => JOIN_QUERY_STRING,
+        WorkerIds::STATELESS => STATELESS_QUERY_STRING,
+    }
+}
+
+//checks that the reconnects and the placements use the worker ids the runner assigns to the fixed topology
+pub fn validate_scenario(
+    topology: &FixedTopology,
+    reconnects: &SimulatedReconnects,
+    placements: &HashMap<u64, Vec<u64>>,
+    worker_ids: WorkerIds,
+) -> Result<(), Box<dyn Error>> {
+    let fixed_workers = topology.nodes.keys().map(|node| worker_ids.fixed_worker_id(*node)).collect::<HashSet<_>>();
+    let first_mobile_worker_id = worker_ids.first_mobile_worker_id(topology);
+    let devices = reconnects.initial_parents.iter().map(|(_, child)| *child).collect::<HashSet<_>>();
+    let expected_devices = (first_mobile_worker_id..first_mobile_worker_id + devices.len() as u64).collect::<HashSet<_>>();
+    if devices != expected_devices || devices.len() != reconnects.initial_parents.len() {
+        return Err(format!("the mobile workers have to be numbered consecutively from {}", first_mobile_worker_id).into());
+    }
+    for (parent, child) in &reconnects.initial_parents {
+        if !fixed_workers.contains(parent) {
+            return Err(format!("initial parent {} of worker {} is not a fixed worker", parent, child).into());
+        }
+    }
+    for update in &reconnects.topology_updates {
+        for event in &update.events {
+            if !fixed_workers.contains(&event.parent_id) || !devices.contains(&event.child_id) {
+                return Err(format!("reconnect at {:?} between {} and {} does not connect a mobile to a fixed worker", update.timestamp, event.child_id, event.parent_id).into());
+            }
+        }
+    }
+    for worker in placements.keys() {
+        if !fixed_workers.contains(worker) && !devices.contains(worker) {
+            return Err(format!("sources are placed on the unknown worker {}", worker).into());
+        }
+    }
+    if worker_ids == WorkerIds::STATEFUL {
+        let mut source_counts = HashMap::<u64, usize>::new();
+        for source in placements.values().flatten() {
+            *source_counts.entry(*source).or_default() += 1;
+        }
+        if let Some((source, count)) = source_counts.iter().find(|(_, count)| *count % 2 != 0) {
+            return Err(format!("logical source {} is placed {} times, the join needs an even number of sources", source, count).into());
+        }
+    }
+    Ok(())
+}
+
+//all paths of the config are relative to the scenario directory, the runners resolve them against the folder of the
+//config file so the directory can be moved
+fn input_config(reconnects: &SimulatedReconnects, worker_ids: WorkerIds) -> String {
+    let last_update = reconnects.topology_updates.iter().map(|update| update.timestamp).max().unwrap_or_default();
+    INPUT_CONFIG_TEMPLATE
+        .replace("{QUERY_STRING}", query_string(worker_ids))
+        .replace("{FIXED_TOPOLOGY}", FIXED_TOPOLOGY_FILE)
+        .replace("{RECONNECT_RUNTIME}", &(last_update.as_secs() + 1).to_string())
+        .replace("{SOURCE_PLACEMENT_PATH}", &toml::Value::String(SOURCE_PLACEMENT_FILE.to_string()).to_string())
+}
+
+//writes the scenario files for the runner to the directory and returns the path of the input config
+pub fn write_scenario(
+    directory: &Path,
+    topology: &FixedTopology,
+    reconnects: &SimulatedReconnects,
+    placements: &HashMap<u64, Vec<u64>>,
+    worker_ids: WorkerIds,
+) -> Result<PathBuf, Box<dyn Error>> {
+    fs::create_dir_all(directory)?;
+    fs::write(directory.join(FIXED_TOPOLOGY_FILE), serde_json::to_string_pretty(topology)?)?;
+    fs::write(directory.join(TOPOLOGY_UPDATES_FILE), serde_json::to_string_pretty(reconnects)?)?;
+    fs::write(directory.join(SOURCE_PLACEMENT_FILE), serde_json::to_string_pretty(placements)?)?;
+    let input_config_path = directory.join(INPUT_CONFIG_FILE);
+    fs::write(&input_config_path, input_config(reconnects, worker_ids))?;
+    Ok(input_config_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::topology::TopologyUpdateList;
+
+    #[test]
+    fn test_write_valid_scenario() {
+        let topology = FixedTopology {
+            nodes: (0..3).map(|node| (node, vec![53.35, -6.3])).collect(),
+            slots: HashMap::new(),
+            children: HashMap::from([(0, vec![1, 2])]),
+        };
+        let mut updates = TopologyUpdateList::new();
+        updates.add_reconnect(Duration::from_millis(2500), 4, 2, 3);
+        let reconnects = SimulatedReconnects {
+            initial_parents: vec![(2, 4)],
+            topology_updates: updates.into(),
+        };
+        let placements = HashMap::from([(4, vec![1, 2]), (3, vec![1, 2])]);
+        validate_scenario(&topology, &reconnects, &placements, WorkerIds::STATEFUL).unwrap();
+        //the stateless runner starts the first mobile worker as 3
+        assert!(validate_scenario(&topology, &reconnects, &placements, WorkerIds::STATELESS).is_err());
+        assert!(validate_scenario(&topology, &reconnects, &HashMap::from([(9, vec![1])]), WorkerIds::STATEFUL).is_err());
+        //the join pairs the sources of a logical source
+        assert!(validate_scenario(&topology, &reconnects, &HashMap::from([(4, vec![1, 2]), (3, vec![1])]), WorkerIds::STATEFUL).is_err());
+
+        let directory = std::env::temp_dir().join(format!("mobility_scenario_{}", std::process::id()));
+        let config_path = write_scenario(&directory, &topology, &reconnects, &placements, WorkerIds::STATEFUL).unwrap();
+        let config: toml::Value = toml::from_str(&fs::read_to_string(&config_path).unwrap()).unwrap();
+        let parameters = &config["default_config"]["parameters"];
+        assert_eq!(parameters["reconnect_runtime"].as_integer(), Some(3));
+        assert!(parameters["query_string"].as_str().unwrap().contains("{INPUT2}"));
+        let placement_path = Path::new(parameters["place_default_sources_on_node_ids_path"].as_str().unwrap());
+        assert!(placement_path.is_relative());
+        let written: HashMap<u64, Vec<u64>> = serde_json::from_str(&fs::read_to_string(directory.join(placement_path)).unwrap()).unwrap();
+        assert_eq!(written, placements);
+        let written: SimulatedReconnects = serde_json::from_str(&fs::read_to_string(directory.join(TOPOLOGY_UPDATES_FILE)).unwrap()).unwrap();
+        assert_eq!(written.initial_parents, reconnects.initial_parents);
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,190 @@
+use std::io;
+use std::sync::Arc;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+
+use crate::emission_pipeline::MessageWriter;
+
+//stand-in for an mqtt broker with a single subscriber. every mqtt source gets its own port on the input server, so
+//the broker only has to accept the subscription of the worker and publish the tuples of the source to it. only the
+//parts of mqtt 3.1.1 a subscribing client needs are implemented, everything is published with qos 0
+
+const CONNECT: u8 = 1;
+const SUBSCRIBE: u8 = 8;
+const PINGREQ: u8 = 12;
+const DISCONNECT: u8 = 14;
+
+const PUBLISH_QOS_0: u8 = 0x30;
+const CONNACK: [u8; 4] = [0x20, 0x02, 0x00, 0x00];
+//connack return code for protocol levels other than mqtt 3.1 and 3.1.1
+const CONNACK_UNACCEPTABLE_PROTOCOL: [u8; 4] = [0x20, 0x02, 0x00, 0x01];
+const SUBACK: u8 = 0x90;
+const PINGRESP: [u8; 2] = [0xD0, 0x00];
+
+//packet type and the packet without its fixed header, none once the client closed the connection
+async fn read_packet<R: AsyncRead + Unpin>(reader: &mut R) -> io::Result<Option<(u8, Vec<u8>)>> {
+    let first_byte = match reader.read_u8().await {
+        Ok(byte) => byte,
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    //the remaining length is encoded in up to four bytes of seven bits each
+    let mut remaining_length = 0usize;
+    for shift in (0..28).step_by(7) {
+        let byte = reader.read_u8().await?;
+        remaining_length |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            let mut packet = vec![0; remaining_length];
+            reader.read_exact(&mut packet).await?;
+            return Ok(Some((first_byte >> 4, packet)));
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::InvalidData, "malformed remaining length"))
+}
+
+fn encode_remaining_length(mut length: usize, output: &mut Vec<u8>) {
+    loop {
+        let byte = (length % 128) as u8;
+        length /= 128;
+        if length == 0 {
+            output.push(byte);
+            return;
+        }
+        output.push(byte | 0x80);
+    }
+}
+
+fn invalid_packet(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+pub fn publish_packet(topic: &str, payload: &[u8]) -> Vec<u8> {
+    let mut packet = vec![PUBLISH_QOS_0];
+    encode_remaining_length(2 + topic.len() + payload.len(), &mut packet);
+    packet.extend_from_slice(&(topic.len() as u16).to_be_bytes());
+    packet.extend_from_slice(topic.as_bytes());
+    packet.extend_from_slice(payload);
+    packet
+}
+
+//publishes the messages written to it on the connection of the subscriber
+pub struct MqttPublisher {
+    writer: Arc<Mutex<OwnedWriteHalf>>,
+    topic: String,
+}
+
+impl MqttPublisher {
+    //complete the connect and the subscribe handshake of a client, the first topic filter it subscribes to is the
+    //topic the tuples are published on
+    pub async fn accept_subscriber(stream: TcpStream) -> io::Result<Self> {
+        let (mut reader, mut writer) = stream.into_split();
+        match read_packet(&mut reader).await? {
+            Some((CONNECT, packet)) => {
+                //protocol name with its two byte length, followed by the protocol level
+                let protocol_level = packet
+                    .get(..2)
+                    .and_then(|length| packet.get(2 + u16::from_be_bytes([length[0], length[1]]) as usize));
+                if !matches!(protocol_level, Some(3) | Some(4)) {
+                    writer.write_all(&CONNACK_UNACCEPTABLE_PROTOCOL).await?;
+                    return Err(invalid_packet("unsupported mqtt protocol level"));
+                }
+                writer.write_all(&CONNACK).await?;
+            }
+            _ => return Err(invalid_packet("expected connect packet")),
+        }
+        let topic = loop {
+            match read_packet(&mut reader).await? {
+                Some((SUBSCRIBE, packet)) if packet.len() < 2 => return Err(invalid_packet("subscribe without packet id")),
+                Some((SUBSCRIBE, packet)) => {
+                    //packet id followed by topic filters, each with its length and the requested qos
+                    let mut topics = vec![];
+                    let mut position = 2;
+                    while position + 2 <= packet.len() {
+                        let length = u16::from_be_bytes([packet[position], packet[position + 1]]) as usize;
+                        let topic = packet.get(position + 2..position + 2 + length).ok_or_else(|| invalid_packet("truncated topic filter"))?;
+                        topics.push(String::from_utf8_lossy(topic).to_string());
+                        position += 2 + length + 1;
+                    }
+                    let mut suback = vec![SUBACK];
+                    encode_remaining_length(2 + topics.len(), &mut suback);
+                    suback.extend_from_slice(&packet[..2]);
+                    suback.extend(topics.iter().map(|_| 0u8));
+                    writer.write_all(&suback).await?;
+                    break topics.into_iter().next().ok_or_else(|| invalid_packet("subscribe without topic filter"))?;
+                }
+                Some((PINGREQ, _)) => writer.write_all(&PINGRESP).await?,
+                Some(_) => {}
+                None => return Err(invalid_packet("client disconnected before subscribing")),
+            }
+        };
+
+        //answer the keep alive pings of the subscriber while tuples are published
+        let writer = Arc::new(Mutex::new(writer));
+        let ping_writer = writer.clone();
+        tokio::spawn(async move {
+            while let Ok(Some((packet_type, _))) = read_packet(&mut reader).await {
+                if packet_type == DISCONNECT {
+                    break;
+                }
+                if packet_type == PINGREQ && ping_writer.lock().await.write_all(&PINGRESP).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self { writer, topic })
+    }
+
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+}
+
+impl MessageWriter for MqttPublisher {
+    async fn write_message(&mut self, message: &[u8]) -> io::Result<()> {
+        self.writer.lock().await.write_all(message).await
+    }
+
+    async fn close(&mut self) {
+        let _ = self.writer.lock().await.shutdown().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_subscriber_receives_published_tuples() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let mut client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+            let broker = tokio::spawn(async move { MqttPublisher::accept_subscriber(listener.accept().await.unwrap().0).await });
+
+            //mqtt 3.1.1 connect with client id c and a keep alive of 60 seconds
+            client.write_all(&[0x10, 13, 0, 4, b'M', b'Q', b'T', b'T', 4, 2, 0, 60, 0, 1, b'c']).await.unwrap();
+            let mut connack = [0; 4];
+            client.read_exact(&mut connack).await.unwrap();
+            assert_eq!(connack, CONNACK);
+            //subscribe with packet id 7 to the topic 1s2
+            client.write_all(&[0x82, 8, 0, 7, 0, 3, b'1', b's', b'2', 0]).await.unwrap();
+            let mut suback = [0; 5];
+            client.read_exact(&mut suback).await.unwrap();
+            assert_eq!(suback, [SUBACK, 3, 0, 7, 0]);
+
+            let mut publisher = broker.await.unwrap().unwrap();
+            assert_eq!(publisher.topic(), "1s2");
+            client.write_all(&[PINGREQ << 4, 0]).await.unwrap();
+            let mut pingresp = [0; 2];
+            client.read_exact(&mut pingresp).await.unwrap();
+            assert_eq!(pingresp, PINGRESP);
+
+            publisher.write_message(&publish_packet(publisher.topic(), b"1,2\n")).await.unwrap();
+            assert_eq!(read_packet(&mut client).await.unwrap(), Some((PUBLISH_QOS_0 >> 4, b"\x00\x031s21,2\n".to_vec())));
+        });
+    }
+}
@@ -0,0 +1,44 @@
+use std::error::Error;
+use std::io::Write;
+use std::sync::Arc;
+
+use parquet::basic::Compression;
+use parquet::data_type::Int64Type;
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+
+pub const PARQUET_ROW_GROUP_SIZE: usize = 64 * 1024;
+
+//write rows of unsigned 64 bit values as a snappy compressed parquet file, one column per field of the message type
+pub fn write_u64_rows<W: Write + Send>(
+    writer: W,
+    message_type: &str,
+    rows: &[Vec<u64>],
+    row_group_size: usize,
+) -> Result<(), Box<dyn Error>> {
+    let schema = Arc::new(parse_message_type(message_type)?);
+    let properties = Arc::new(
+        WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .set_max_row_group_size(row_group_size)
+            .build(),
+    );
+    let mut file_writer = SerializedFileWriter::new(writer, schema, properties)?;
+    for chunk in rows.chunks(row_group_size.max(1)) {
+        let mut row_group_writer = file_writer.next_row_group()?;
+        let mut column_index = 0;
+        while let Some(mut column_writer) = row_group_writer.next_column()? {
+            //parquet has no physical unsigned type, the logical type in the schema marks the column as unsigned
+            let values: Vec<i64> = chunk.iter().map(|row| row[column_index] as i64).collect();
+            column_writer
+                .typed::<Int64Type>()
+                .write_batch(&values, None, None)?;
+            column_writer.close()?;
+            column_index += 1;
+        }
+        row_group_writer.close()?;
+    }
+    file_writer.close()?;
+    Ok(())
+}
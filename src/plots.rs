@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use plotly::common::{DashType, Mode, Title};
+use plotly::layout::{Axis, Shape, ShapeLine, ShapeType};
+use plotly::{Bar, Layout, Plot, Scatter};
+use serde::{Deserialize, Serialize};
+
+use crate::analyze::{nanos_to_ms, read_timings, throughput_bins, AnalysisOptions, TupleTiming};
+use crate::rest_node_relocation::{ISQPEventAction, TopologyUpdate};
+use crate::reconnect_analysis::{find_result_files, read_reconnect_times, RECONNECTS_SUFFIX};
+
+//suffixes of the plots written next to the result file of an attempt
+pub const LATENCY_PLOT_SUFFIX: &str = "latency.html";
+pub const THROUGHPUT_PLOT_SUFFIX: &str = "throughput.html";
+pub const TOPOLOGY_FRAMES_SUFFIX: &str = "topology_frames.json";
+pub const LATENCY_CDF_PLOT_FILE: &str = "latency_cdf.html";
+
+const LATENCY_PLOT_BIN: Duration = Duration::from_millis(100);
+//number of points used to draw each latency cdf
+const CDF_POINTS: usize = 1000;
+
+//vertical dashed lines at the given positions on the x axis, spanning the whole plot height
+fn update_markers(positions: &[f64]) -> Vec<Shape> {
+    positions
+        .iter()
+        .map(|position| {
+            Shape::new()
+                .shape_type(ShapeType::Line)
+                .x_ref("x")
+                .y_ref("paper")
+                .x0(*position)
+                .x1(*position)
+                .y0(0.0)
+                .y1(1.0)
+                .line(ShapeLine::new().color("gray").width(1.0).dash(DashType::Dash))
+        })
+        .collect()
+}
+
+fn seconds_since(origin: u64, time: u64) -> f64 {
+    (time as f64 - origin as f64) / 1_000_000_000.0
+}
+
+//mean and maximum latency in fixed bins over time with a marker at each topology update
+pub fn plot_latency_over_time(timings: &[TupleTiming], update_times: &[u64], path: &Path) {
+    let Some(origin) = timings.iter().map(|timing| timing.emission_time).min() else {
+        return;
+    };
+    let bin = LATENCY_PLOT_BIN.as_nanos() as u64;
+    let mut bins: BTreeMap<u64, (f64, i64, usize)> = BTreeMap::new();
+    for timing in timings {
+        let entry = bins.entry((timing.emission_time - origin) / bin).or_insert((0.0, i64::MIN, 0));
+        entry.0 += timing.latency as f64;
+        entry.1 = entry.1.max(timing.latency);
+        entry.2 += 1;
+    }
+    let x = bins.keys().map(|index| seconds_since(0, index * bin)).collect::<Vec<_>>();
+    let mean = bins.values().map(|(sum, _, count)| nanos_to_ms(sum / *count as f64)).collect::<Vec<_>>();
+    let max = bins.values().map(|(_, max, _)| nanos_to_ms(*max as f64)).collect::<Vec<_>>();
+
+    let mut plot = Plot::new();
+    plot.add_trace(Scatter::new(x.clone(), mean).mode(Mode::Lines).name("mean latency"));
+    plot.add_trace(Scatter::new(x, max).mode(Mode::Lines).name("max latency"));
+    let markers = update_times.iter().map(|time| seconds_since(origin, *time)).collect::<Vec<_>>();
+    plot.set_layout(
+        Layout::new()
+            .title(Title::with_text("Latency over time"))
+            .x_axis(Axis::new().title(Title::with_text("time since first output [s]")))
+            .y_axis(Axis::new().title(Title::with_text("latency [ms]")))
+            .shapes(update_markers(&markers)),
+    );
+    plot.write_html(path);
+}
+
+pub fn plot_throughput_timeline(timings: &[TupleTiming], update_times: &[u64], bin_width: Duration, path: &Path) {
+    let Some(origin) = timings.iter().map(|timing| timing.emission_time).min() else {
+        return;
+    };
+    let bins = throughput_bins(timings, bin_width);
+    let x = bins.iter().map(|bin| bin.start_ms as f64 / 1000.0).collect::<Vec<_>>();
+    let y = bins.iter().map(|bin| bin.tuples_per_second).collect::<Vec<_>>();
+
+    let mut plot = Plot::new();
+    plot.add_trace(Bar::new(x, y).name("throughput"));
+    let markers = update_times.iter().map(|time| seconds_since(origin, *time)).collect::<Vec<_>>();
+    plot.set_layout(
+        Layout::new()
+            .title(Title::with_text("Throughput over time"))
+            .x_axis(Axis::new().title(Title::with_text("time since first output [s]")))
+            .y_axis(Axis::new().title(Title::with_text("tuples per second")))
+            .shapes(update_markers(&markers)),
+    );
+    plot.write_html(path);
+}
+
+//latency cdf with one line per configuration
+pub fn plot_latency_cdf(series: &[(String, Vec<i64>)], path: &Path) {
+    let mut plot = Plot::new();
+    for (label, latencies) in series {
+        if latencies.is_empty() {
+            continue;
+        }
+        let mut sorted = latencies.clone();
+        sorted.sort_unstable();
+        let step = (sorted.len() / CDF_POINTS).max(1);
+        let mut indices = (0..sorted.len()).step_by(step).collect::<Vec<_>>();
+        if indices.last() != Some(&(sorted.len() - 1)) {
+            indices.push(sorted.len() - 1);
+        }
+        let x = indices.iter().map(|index| nanos_to_ms(sorted[*index] as f64)).collect::<Vec<_>>();
+        let y = indices.iter().map(|index| (*index + 1) as f64 / sorted.len() as f64).collect::<Vec<_>>();
+        plot.add_trace(Scatter::new(x, y).mode(Mode::Lines).name(label.as_str()));
+    }
+    plot.set_layout(
+        Layout::new()
+            .title(Title::with_text("Latency CDF"))
+            .x_axis(Axis::new().title(Title::with_text("latency [ms]")))
+            .y_axis(Axis::new().title(Title::with_text("fraction of tuples"))),
+    );
+    plot.write_html(path);
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TopologyFrame {
+    pub index: usize,
+    //offset of the update in the reconnect list before the speedup is applied, zero for the initial topology
+    pub scheduled_offset_ms: u64,
+    //time since the unix epoch at which the runner sent the update, none for the initial topology and unsent updates
+    pub sent_at_ns: Option<u64>,
+    //(parent, child) edges of the mobile nodes after the update
+    pub edges: Vec<(u64, u64)>,
+    pub changed_children: Vec<u64>,
+}
+
+//the mobile part of the topology after each update, for animating the reconnects of a run
+pub fn topology_frames(initial_parents: &[(u64, u64)], updates: &[TopologyUpdate], send_times: &[u64]) -> Vec<TopologyFrame> {
+    let mut parents: BTreeMap<u64, u64> = initial_parents.iter().map(|(parent, child)| (*child, *parent)).collect();
+    let mut frames = vec![TopologyFrame {
+        index: 0,
+        scheduled_offset_ms: 0,
+        sent_at_ns: None,
+        edges: parents.iter().map(|(child, parent)| (*parent, *child)).collect(),
+        changed_children: vec![],
+    }];
+    for (index, update) in updates.iter().enumerate() {
+        let mut changed_children = vec![];
+        for event in &update.events {
+            match event.action {
+                ISQPEventAction::remove => {
+                    if parents.get(&event.child_id) == Some(&event.parent_id) {
+                        parents.remove(&event.child_id);
+                    }
+                }
+                ISQPEventAction::add => {
+                    parents.insert(event.child_id, event.parent_id);
+                    changed_children.push(event.child_id);
+                }
+            }
+        }
+        frames.push(TopologyFrame {
+            index: index + 1,
+            scheduled_offset_ms: update.timestamp.as_millis() as u64,
+            sent_at_ns: send_times.get(index).copied(),
+            edges: parents.iter().map(|(child, parent)| (*parent, *child)).collect(),
+            changed_children,
+        });
+    }
+    frames
+}
+
+pub fn write_topology_frames(
+    result_file: &Path,
+    initial_parents: &[(u64, u64)],
+    updates: &[TopologyUpdate],
+    send_times: &[Duration],
+) -> Result<(), Box<dyn Error>> {
+    let send_times = send_times.iter().map(|time| time.as_nanos() as u64).collect::<Vec<_>>();
+    let frames = topology_frames(initial_parents, updates, &send_times);
+    fs::write(
+        format!("{}{}", result_file.display(), TOPOLOGY_FRAMES_SUFFIX),
+        serde_json::to_string_pretty(&frames)?,
+    )?;
+    Ok(())
+}
+
+//write the latency and throughput plots of one attempt next to its result file
+pub fn plot_run(result_file: &Path, options: &AnalysisOptions) -> Result<(), Box<dyn Error>> {
+    let base_path = result_file.display().to_string();
+    let (_, timings) = read_timings(result_file, options)?;
+    let update_times = read_reconnect_times(&PathBuf::from(format!("{}{}", base_path, RECONNECTS_SUFFIX))).unwrap_or_default();
+    plot_latency_over_time(&timings, &update_times, &PathBuf::from(format!("{}{}", base_path, LATENCY_PLOT_SUFFIX)));
+    plot_throughput_timeline(
+        &timings,
+        &update_times,
+        options.bin_width,
+        &PathBuf::from(format!("{}{}", base_path, THROUGHPUT_PLOT_SUFFIX)),
+    );
+    Ok(())
+}
+
+//write a latency cdf with one line per configuration of a sweep. configurations are given as (label, folder)
+pub fn plot_sweep(sweep_folder: &Path, configurations: &[(String, Vec<PathBuf>)], options: &AnalysisOptions) -> Result<(), Box<dyn Error>> {
+    let mut series = vec![];
+    for (label, folders) in configurations {
+        let mut latencies = vec![];
+        for folder in folders {
+            for result_file in find_result_files(folder)? {
+                match read_timings(&result_file, options) {
+                    Ok((_, timings)) => latencies.extend(timings.iter().map(|timing| timing.latency)),
+                    Err(e) => eprintln!("Could not read {}: {}", result_file.display(), e),
+                }
+            }
+        }
+        series.push((label.clone(), latencies));
+    }
+    plot_latency_cdf(&series, &sweep_folder.join(LATENCY_CDF_PLOT_FILE));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rest_node_relocation::TopologyUpdateList;
+
+    #[test]
+    fn test_topology_frames() {
+        let mut updates = TopologyUpdateList::new();
+        updates.add_reconnect(Duration::from_millis(500), 10, 2, 3);
+        updates.add_reconnect(Duration::from_millis(900), 11, 2, 4);
+        let updates: Vec<TopologyUpdate> = updates.into();
+        let frames = topology_frames(&[(2, 10), (2, 11)], &updates, &[1_000]);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0].edges, vec![(2, 10), (2, 11)]);
+        assert_eq!(frames[1].edges, vec![(3, 10), (2, 11)]);
+        assert_eq!(frames[1].sent_at_ns, Some(1_000));
+        assert_eq!(frames[2].edges, vec![(3, 10), (4, 11)]);
+        assert_eq!(frames[2].changed_children, vec![11]);
+        assert_eq!(frames[2].sent_at_ns, None);
+    }
+}
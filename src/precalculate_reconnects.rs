@@ -1,57 +1,55 @@
+use std::collections::HashMap;
 use std::env;
+use std::error::Error;
+use std::fs;
 use std::time::Duration;
-use rand::Rng;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 6 || args.len() > 7 {
-        eprintln!("Usage: {} <min_id> <max_id> <output_file> <experiment_duration> <interval> <amount_of_fixed_nodes_to_place_sources (optional)>", args[0]);
-        std::process::exit(1);
-    }
-    let min_id = args[1].parse::<u64>().expect("Invalid duration");
-    //let min_id = 12;
-    let max_id = args[2].parse::<u64>().expect("Invalid duration");
-    //let max_id = 110;
-    let output_file = &args[3][..];
-    let duration = Duration::from_millis(args[4].parse::<u64>().expect("Invalid duration"));
-    let interval = Duration::from_millis(args[5].parse::<u64>().expect("Invalid interval"));
+use simulation_runner_rs::reconnect_schedule::{generate_schedule, ReconnectScheduleConfig};
+use simulation_runner_rs::topology::{FixedTopology, WorkerIds};
 
-    // let json_string = std::fs::read_to_string(&self.paths.fixed_topology_nodes)?;
-    // let topology: FixedTopology = serde_json::from_str(json_string.as_str())?;
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a String> {
+    args.iter().position(|arg| arg == flag).and_then(|index| args.get(index + 1))
+}
 
-    let mut rng = rand::thread_rng();
-    // let fixed_nodes_hosting_sources = if args.len() == 7 {
-    //     let source_count = args[6].parse::<u64>().expect("Invalid duration");
-    //     let mut numbers: Vec<u64> = vec![];
-    //     for _i in 0..10 {
-    //         let mut number = rng.gen_range(min_id..max_id);
-    //         while numbers.contains(&number) {
-    //             number = rng.gen_range(min_id..max_id);
-    //         }
-    //         numbers.push(number);
-    //     }
-    //     println!("{:?}", numbers);
-    //     numbers
-    // } else {
-    //     vec![]
-    // };
-    let fixed_nodes_hosting_sources =  [7, 9, 11, 13, 15, 17, 19, 21, 23, 25];
+fn parse_ids(ids: &str) -> Result<Vec<u64>, Box<dyn Error>> {
+    ids.split(',')
+        .map(|id| id.trim().parse::<u64>().map_err(|e| format!("invalid node id {}: {}", id, e).into()))
+        .collect()
+}
 
-    let mut timestamp = Duration::new(0, 0);
-    let mut csv_writer = csv::WriterBuilder::new()
-        .has_headers(false)
-        .from_path(&output_file).unwrap();
-    let mut parent= 0;
-    while timestamp < duration {
-        parent = loop {
-            let generated_parent = rng.gen_range(min_id..max_id);
-            if generated_parent != parent && !fixed_nodes_hosting_sources.contains(&generated_parent) {
-                break generated_parent;
-            }
-        };
-        csv_writer.serialize((parent, timestamp.as_nanos()))?;
-        timestamp += interval;
-    }
+fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let topology: FixedTopology = serde_json::from_str(&fs::read_to_string(&args[1])?)?;
+    let placements: HashMap<u64, Vec<u64>> = serde_json::from_str(&fs::read_to_string(&args[2])?)?;
+    let config = ReconnectScheduleConfig {
+        num_devices: args[4].parse().map_err(|e| format!("invalid number of devices: {}", e))?,
+        duration: Duration::from_millis(args[5].parse().map_err(|e| format!("invalid duration: {}", e))?),
+        interval: flag_value(args, "--interval").map_or("fixed:1000", |interval| interval.as_str()).parse()?,
+        seed: match flag_value(args, "--seed") {
+            Some(seed) => seed.parse().map_err(|e| format!("invalid seed: {}", e))?,
+            None => rand::random(),
+        },
+        parents: flag_value(args, "--parents").map(|ids| parse_ids(ids)).transpose()?,
+        excluded_nodes: flag_value(args, "--exclude").map(|ids| parse_ids(ids)).transpose()?.unwrap_or_default(),
+        exclude_source_hosts: !args.iter().any(|arg| arg == "--keep-source-hosts"),
+        worker_ids: if args.iter().any(|arg| arg == "--stateless") { WorkerIds::STATELESS } else { WorkerIds::STATEFUL },
+    };
+    println!("generating reconnects with seed {}", config.seed);
+
+    let schedule = generate_schedule(&topology, &placements, &config)?;
+    let reconnects = schedule.topology_updates.iter().map(|update| update.events.len() / 2).sum::<usize>();
+    println!("generated {} reconnects of {} devices", reconnects, config.num_devices);
+    fs::write(&args[3], serde_json::to_string_pretty(&schedule)?)?;
     Ok(())
+}
 
-}
\ No newline at end of file
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 6 {
+        eprintln!("Usage: {} <fixed topology json> <source placement json> <output json> <devices> <duration ms> [--interval <fixed:ms|uniform:min:max|exponential:mean>] [--seed <seed>] [--parents <id,id,...>] [--exclude <id,id,...>] [--keep-source-hosts] [--stateless]", args[0]);
+        std::process::exit(1);
+    }
+    if let Err(e) = run(&args) {
+        eprintln!("Error generating reconnects: {}", e);
+        std::process::exit(1);
+    }
+}
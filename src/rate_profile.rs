@@ -0,0 +1,217 @@
+use std::error::Error;
+use std::f64::consts::PI;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+//rate profiles scale the base rate of one buffer per gathering interval over the time since the ingestion deadline.
+//a source emits the buffers that fit into the data production time at that rate, see buffer_count
+
+//written to the folder of a configuration and passed to the input server
+pub const RATE_PROFILE_FILE: &str = "rate_profile.json";
+
+//lowest rate factor, keeps the interval between buffers finite when a profile goes to zero
+const MIN_RATE_FACTOR: f64 = 0.01;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct RateStep {
+    pub start_ms: u64,
+    pub factor: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(tag = "kind")]
+pub enum RateProfile {
+    #[default]
+    Constant,
+    //the factor of the last step that started applies, the base rate before the first step
+    Steps {
+        steps: Vec<RateStep>,
+    },
+    //linear change of the factor, which stays at end_factor afterwards
+    Ramp {
+        start_factor: f64,
+        end_factor: f64,
+        duration_ms: u64,
+    },
+    //factor 1 + amplitude * sin(2 pi t / period)
+    Sine {
+        amplitude: f64,
+        period_ms: u64,
+    },
+    //exponentially distributed gaps with the gathering interval as mean
+    Poisson,
+    Bursts {
+        starts_ms: Vec<u64>,
+        duration_ms: u64,
+        factor: f64,
+    },
+    //a burst starting lead_ms before every topology update. the runner resolves this into Bursts because only it knows
+    //the reconnect schedule
+    ReconnectBursts {
+        lead_ms: u64,
+        duration_ms: u64,
+        factor: f64,
+    },
+}
+
+impl RateProfile {
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let profile: Self = serde_json::from_str(&fs::read_to_string(path)?)?;
+        if let RateProfile::ReconnectBursts { .. } = profile {
+            return Err("reconnect bursts have to be resolved by the runner before starting the input server".into());
+        }
+        Ok(profile)
+    }
+
+    pub fn write_to_file(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    //replace reconnect bursts with bursts at the given update times, measured from the ingestion deadline
+    pub fn resolve_reconnect_bursts(&self, update_offsets: &[Duration]) -> Self {
+        match self {
+            RateProfile::ReconnectBursts { lead_ms, duration_ms, factor } => RateProfile::Bursts {
+                starts_ms: update_offsets
+                    .iter()
+                    .map(|offset| (offset.as_millis() as u64).saturating_sub(*lead_ms))
+                    .collect(),
+                duration_ms: *duration_ms,
+                factor: *factor,
+            },
+            profile => profile.clone(),
+        }
+    }
+
+    pub fn rate_factor(&self, elapsed: Duration) -> f64 {
+        let elapsed_ms = elapsed.as_secs_f64() * 1000.0;
+        let factor = match self {
+            RateProfile::Constant | RateProfile::Poisson | RateProfile::ReconnectBursts { .. } => 1.0,
+            RateProfile::Steps { steps } => steps
+                .iter()
+                .filter(|step| step.start_ms as f64 <= elapsed_ms)
+                .max_by_key(|step| step.start_ms)
+                .map_or(1.0, |step| step.factor),
+            RateProfile::Ramp { start_factor, end_factor, duration_ms } => {
+                let progress = if *duration_ms == 0 { 1.0 } else { (elapsed_ms / *duration_ms as f64).min(1.0) };
+                start_factor + (end_factor - start_factor) * progress
+            }
+            RateProfile::Sine { amplitude, period_ms } => {
+                if *period_ms == 0 {
+                    1.0
+                } else {
+                    1.0 + amplitude * (2.0 * PI * elapsed_ms / *period_ms as f64).sin()
+                }
+            }
+            RateProfile::Bursts { starts_ms, duration_ms, factor } => {
+                let in_burst = starts_ms
+                    .iter()
+                    .any(|start| (*start as f64..(*start + *duration_ms) as f64).contains(&elapsed_ms));
+                if in_burst {
+                    *factor
+                } else {
+                    1.0
+                }
+            }
+        };
+        factor.max(MIN_RATE_FACTOR)
+    }
+
+    //number of buffers emitted during the production time, which is the integral of the rate factor over the production
+    //time divided by the gathering interval. the schedule of the input server is replayed, so the count is exactly the
+    //number of buffers it sends before the production time ends. random gaps are counted with their mean
+    pub fn buffer_count(&self, gathering_interval: Duration, production_time: Duration) -> u64 {
+        if gathering_interval.is_zero() {
+            return 0;
+        }
+        if *self == RateProfile::Poisson {
+            return (production_time.as_nanos() / gathering_interval.as_nanos()) as u64;
+        }
+        let mut schedule = EmissionSchedule::new(self.clone(), gathering_interval, 0);
+        let mut offset = Duration::ZERO;
+        let mut buffers = 0;
+        while offset < production_time {
+            buffers += 1;
+            offset += schedule.next_interval(offset);
+        }
+        buffers
+    }
+}
+
+//computes when the input server emits the next buffer of a connection
+pub struct EmissionSchedule {
+    profile: RateProfile,
+    gathering_interval: Duration,
+    rng: StdRng,
+}
+
+impl EmissionSchedule {
+    pub fn new(profile: RateProfile, gathering_interval: Duration, seed: u64) -> Self {
+        Self {
+            profile,
+            gathering_interval,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    //time between the buffer emitted after elapsed and the next buffer
+    pub fn next_interval(&mut self, elapsed: Duration) -> Duration {
+        let interval = self.gathering_interval.div_f64(self.profile.rate_factor(elapsed));
+        match self.profile {
+            RateProfile::Poisson => {
+                //inverse transform sampling of the exponential distribution
+                let sample: f64 = self.rng.gen();
+                interval.mul_f64(-(1.0 - sample).ln())
+            }
+            _ => interval,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_profiles() {
+        let interval = Duration::from_millis(100);
+        let mut constant = EmissionSchedule::new(RateProfile::Constant, interval, 0);
+        assert_eq!(constant.next_interval(Duration::from_secs(5)), interval);
+
+        let steps = RateProfile::Steps {
+            steps: vec![RateStep { start_ms: 1000, factor: 2.0 }, RateStep { start_ms: 2000, factor: 0.5 }],
+        };
+        let mut schedule = EmissionSchedule::new(steps, interval, 0);
+        assert_eq!(schedule.next_interval(Duration::from_millis(500)), interval);
+        assert_eq!(schedule.next_interval(Duration::from_millis(1500)), Duration::from_millis(50));
+        assert_eq!(schedule.next_interval(Duration::from_millis(2500)), Duration::from_millis(200));
+
+        let bursts = RateProfile::ReconnectBursts { lead_ms: 100, duration_ms: 300, factor: 4.0 }
+            .resolve_reconnect_bursts(&[Duration::from_secs(1), Duration::from_secs(3)]);
+        assert_eq!(bursts, RateProfile::Bursts { starts_ms: vec![900, 2900], duration_ms: 300, factor: 4.0 });
+        assert_eq!(bursts.rate_factor(Duration::from_millis(1000)), 4.0);
+        assert_eq!(bursts.rate_factor(Duration::from_millis(1300)), 1.0);
+
+        let ramp = RateProfile::Ramp { start_factor: 1.0, end_factor: 3.0, duration_ms: 1000 };
+        assert_eq!(ramp.rate_factor(Duration::from_millis(500)), 2.0);
+        assert_eq!(ramp.rate_factor(Duration::from_secs(10)), 3.0);
+
+        //a step to twice the rate after half of the production time sends half as many buffers more
+        let production_time = Duration::from_secs(2);
+        assert_eq!(RateProfile::Constant.buffer_count(interval, production_time), 20);
+        let step = RateProfile::Steps { steps: vec![RateStep { start_ms: 1000, factor: 2.0 }] };
+        assert_eq!(step.buffer_count(interval, production_time), 30);
+        let slow = RateProfile::Steps { steps: vec![RateStep { start_ms: 0, factor: 0.5 }] };
+        assert_eq!(slow.buffer_count(interval, production_time), 10);
+        assert_eq!(RateProfile::Poisson.buffer_count(interval, production_time), 20);
+
+        let mut poisson = EmissionSchedule::new(RateProfile::Poisson, interval, 1);
+        let mean = (0..10_000).map(|_| poisson.next_interval(Duration::ZERO).as_secs_f64()).sum::<f64>() / 10_000.0;
+        assert!((mean - 0.1).abs() < 0.01);
+    }
+}
@@ -0,0 +1,381 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::analyze::{nanos_to_ms, parse_width_ms, read_timings, AnalysisOptions, TupleTiming};
+use crate::completeness::CompletenessReport;
+
+//suffixes the runner appends to the result file of an attempt
+pub const RECONNECTS_SUFFIX: &str = "reconnects.csv";
+pub const COMPLETENESS_SUFFIX: &str = "completeness.json";
+pub const RECONNECT_ANALYSIS_SUFFIX: &str = "reconnect_analysis.json";
+pub const RECONNECT_SUMMARY_FILE: &str = "reconnect_summary.json";
+//extensions of the csv, avro and parquet output types
+const RESULT_FILE_EXTENSIONS: &[&str] = &["csv", "avro", "parquet"];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReconnectOptions {
+    //length of the windows before and after each reconnect. windows are cut short at the neighbouring reconnects
+    pub window: Duration,
+    //bin width used to find the peak latency and the recovery after a reconnect
+    pub recovery_bin: Duration,
+    //latency counts as recovered once it is at most this fraction above the baseline before the reconnect
+    pub recovery_tolerance: f64,
+}
+
+impl Default for ReconnectOptions {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(2),
+            recovery_bin: Duration::from_millis(100),
+            recovery_tolerance: 0.1,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ReconnectImpact {
+    pub update_index: usize,
+    pub sent_at_ns: u64,
+    pub pre_tuples: usize,
+    pub post_tuples: usize,
+    pub pre_throughput: f64,
+    pub post_throughput: f64,
+    pub pre_latency_ms: Option<f64>,
+    pub post_latency_ms: Option<f64>,
+    pub peak_latency_ms: Option<f64>,
+    //time between the reconnect and the bin with the peak latency
+    pub peak_offset_ms: Option<u64>,
+    //time between the reconnect and the first bin after the peak whose latency is back at the baseline
+    pub recovery_time_ms: Option<u64>,
+    //missing tuples from the completeness report that were emitted in the window after the reconnect
+    pub lost_tuples: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RunReconnectAnalysis {
+    pub input_file: PathBuf,
+    pub options: ReconnectOptions,
+    pub reconnects: Vec<ReconnectImpact>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConfigurationReconnectSummary {
+    pub configuration: PathBuf,
+    pub runs: usize,
+    pub reconnects: usize,
+    pub mean_pre_latency_ms: Option<f64>,
+    pub mean_post_latency_ms: Option<f64>,
+    pub mean_peak_latency_ms: Option<f64>,
+    pub max_peak_latency_ms: Option<f64>,
+    pub mean_recovery_time_ms: Option<f64>,
+    pub max_recovery_time_ms: Option<u64>,
+    //reconnects after which the latency did not return to the baseline within the window
+    pub unrecovered_reconnects: usize,
+    pub lost_tuples: u64,
+}
+
+//read the send times written by the runner, one duration since the unix epoch in nanoseconds per line
+pub fn read_reconnect_times(path: &Path) -> Result<Vec<u64>, Box<dyn Error>> {
+    let mut times = fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.trim().parse::<u64>())
+        .collect::<Result<Vec<_>, _>>()?;
+    times.sort_unstable();
+    Ok(times)
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = values.fold((0.0, 0usize), |(sum, count), value| (sum + value, count + 1));
+    (count > 0).then(|| sum / count as f64)
+}
+
+fn mean_latency_ms(timings: &[&TupleTiming]) -> Option<f64> {
+    mean(timings.iter().map(|timing| timing.latency as f64)).map(nanos_to_ms)
+}
+
+fn throughput(tuples: usize, start: u64, end: u64) -> f64 {
+    if end > start {
+        tuples as f64 / Duration::from_nanos(end - start).as_secs_f64()
+    } else {
+        0.0
+    }
+}
+
+//compute the impact of each reconnect on the timings. timings and reconnect times are nanoseconds since the unix epoch
+pub fn reconnect_impacts(
+    timings: &[TupleTiming],
+    reconnect_times: &[u64],
+    completeness: Option<&CompletenessReport>,
+    options: &ReconnectOptions,
+) -> Vec<ReconnectImpact> {
+    let window = options.window.as_nanos() as u64;
+    let bin = options.recovery_bin.as_nanos().max(1) as u64;
+    reconnect_times
+        .iter()
+        .enumerate()
+        .map(|(update_index, &sent_at)| {
+            let pre_start = sent_at
+                .saturating_sub(window)
+                .max(if update_index > 0 { reconnect_times[update_index - 1] } else { 0 });
+            let post_end = (sent_at + window).min(reconnect_times.get(update_index + 1).copied().unwrap_or(u64::MAX));
+            let pre = timings
+                .iter()
+                .filter(|timing| (pre_start..sent_at).contains(&timing.emission_time))
+                .collect::<Vec<_>>();
+            let post = timings
+                .iter()
+                .filter(|timing| (sent_at..post_end).contains(&timing.emission_time))
+                .collect::<Vec<_>>();
+
+            let bin_count = ((post_end - sent_at) / bin) as usize;
+            let bins = (0..bin_count)
+                .map(|index| {
+                    let bin_start = sent_at + index as u64 * bin;
+                    let in_bin = post
+                        .iter()
+                        .filter(|timing| (bin_start..bin_start + bin).contains(&timing.emission_time))
+                        .copied()
+                        .collect::<Vec<_>>();
+                    mean_latency_ms(&in_bin)
+                })
+                .collect::<Vec<_>>();
+            let peak = bins
+                .iter()
+                .enumerate()
+                .filter_map(|(index, latency)| latency.map(|latency| (index, latency)))
+                //keep the first bin if several share the highest latency
+                .reduce(|peak, bin| if bin.1 > peak.1 { bin } else { peak });
+
+            let pre_latency_ms = mean_latency_ms(&pre);
+            let recovery_time_ms = match (pre_latency_ms, peak) {
+                (Some(baseline), Some((peak_index, _))) => bins
+                    .iter()
+                    .enumerate()
+                    .skip(peak_index)
+                    .find(|(_, latency)| latency.is_some_and(|latency| latency <= baseline * (1.0 + options.recovery_tolerance)))
+                    .map(|(index, _)| index as u64 * bin / 1_000_000),
+                _ => None,
+            };
+
+            let lost_tuples = completeness.and_then(|report| report.by_update.get(update_index)).map(|update| {
+                update
+                    .by_offset_ms
+                    .range(..(post_end - sent_at) / 1_000_000)
+                    .map(|(_, counts)| counts.missing)
+                    .sum()
+            });
+
+            ReconnectImpact {
+                update_index,
+                sent_at_ns: sent_at,
+                pre_tuples: pre.len(),
+                post_tuples: post.len(),
+                pre_throughput: throughput(pre.len(), pre_start, sent_at),
+                post_throughput: throughput(post.len(), sent_at, post_end),
+                pre_latency_ms,
+                post_latency_ms: mean_latency_ms(&post),
+                peak_latency_ms: peak.map(|(_, latency)| latency),
+                peak_offset_ms: peak.map(|(index, _)| index as u64 * bin / 1_000_000),
+                recovery_time_ms,
+                lost_tuples,
+            }
+        })
+        .collect()
+}
+
+//analyze the result file of one attempt together with its reconnect and completeness files and write
+//<file>reconnect_analysis.json
+pub fn analyze_run(
+    result_file: &Path,
+    analysis_options: &AnalysisOptions,
+    options: &ReconnectOptions,
+) -> Result<RunReconnectAnalysis, Box<dyn Error>> {
+    let base_path = result_file.to_str().ok_or("could not convert result path to string")?;
+    let (_, timings) = read_timings(result_file, analysis_options)?;
+    let reconnect_times = read_reconnect_times(&PathBuf::from(format!("{}{}", base_path, RECONNECTS_SUFFIX)))?;
+    let completeness = fs::read_to_string(format!("{}{}", base_path, COMPLETENESS_SUFFIX))
+        .ok()
+        .and_then(|json| serde_json::from_str::<CompletenessReport>(&json).ok());
+    let analysis = RunReconnectAnalysis {
+        input_file: result_file.to_path_buf(),
+        options: options.clone(),
+        reconnects: reconnect_impacts(&timings, &reconnect_times, completeness.as_ref(), options),
+    };
+    fs::write(
+        format!("{}{}", base_path, RECONNECT_ANALYSIS_SUFFIX),
+        serde_json::to_string_pretty(&analysis)?,
+    )?;
+    Ok(analysis)
+}
+
+pub fn summarize_runs(configuration: &Path, runs: &[RunReconnectAnalysis]) -> ConfigurationReconnectSummary {
+    let reconnects = runs.iter().flat_map(|run| &run.reconnects).collect::<Vec<_>>();
+    ConfigurationReconnectSummary {
+        configuration: configuration.to_path_buf(),
+        runs: runs.len(),
+        reconnects: reconnects.len(),
+        mean_pre_latency_ms: mean(reconnects.iter().filter_map(|impact| impact.pre_latency_ms)),
+        mean_post_latency_ms: mean(reconnects.iter().filter_map(|impact| impact.post_latency_ms)),
+        mean_peak_latency_ms: mean(reconnects.iter().filter_map(|impact| impact.peak_latency_ms)),
+        max_peak_latency_ms: reconnects
+            .iter()
+            .filter_map(|impact| impact.peak_latency_ms)
+            .max_by(f64::total_cmp),
+        mean_recovery_time_ms: mean(reconnects.iter().filter_map(|impact| impact.recovery_time_ms.map(|time| time as f64))),
+        max_recovery_time_ms: reconnects.iter().filter_map(|impact| impact.recovery_time_ms).max(),
+        unrecovered_reconnects: reconnects
+            .iter()
+            .filter(|impact| impact.peak_latency_ms.is_some() && impact.recovery_time_ms.is_none())
+            .count(),
+        lost_tuples: reconnects.iter().filter_map(|impact| impact.lost_tuples).sum(),
+    }
+}
+
+//result files of all attempts in a configuration folder, recognized by the out_run:<attempt>.<extension> name the
+//runner uses. the files derived from a result file append to its name and are not matched
+pub fn find_result_files(configuration: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut files = fs::read_dir(configuration)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix("out_run:"))
+                .and_then(|name| name.split_once('.'))
+                .is_some_and(|(attempt, extension)| {
+                    attempt.parse::<u64>().is_ok() && RESULT_FILE_EXTENSIONS.contains(&extension)
+                })
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+    Ok(files)
+}
+
+//aggregate the reconnect analyses of all attempts of one swept configuration and write reconnect_summary.json
+pub fn summarize_configuration(configuration: &Path) -> Result<ConfigurationReconnectSummary, Box<dyn Error>> {
+    let mut runs = vec![];
+    for result_file in find_result_files(configuration)? {
+        let analysis_path = format!("{}{}", result_file.display(), RECONNECT_ANALYSIS_SUFFIX);
+        if let Ok(json) = fs::read_to_string(&analysis_path) {
+            runs.push(serde_json::from_str::<RunReconnectAnalysis>(&json)?);
+        }
+    }
+    let summary = summarize_runs(configuration, &runs);
+    fs::write(
+        configuration.join(RECONNECT_SUMMARY_FILE),
+        serde_json::to_string_pretty(&summary)?,
+    )?;
+    Ok(summary)
+}
+
+//entry point of the reconnects command: reconnects <configuration folder>... [--window-ms <ms>] [--bin-ms <ms>]
+//[--tolerance <fraction>]. folders without result files are searched one level deep for configuration folders
+pub fn run_reconnects_command(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut options = ReconnectOptions::default();
+    let mut folders = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--window-ms" => options.window = Duration::from_millis(value()?.parse()?),
+            "--bin-ms" => options.recovery_bin = parse_width_ms(arg, value()?)?,
+            "--tolerance" => options.recovery_tolerance = value()?.parse()?,
+            _ => folders.push(PathBuf::from(arg)),
+        }
+    }
+    if folders.is_empty() {
+        return Err("Usage: reconnects <configuration folder>... [--window-ms <ms>] [--bin-ms <ms>] [--tolerance <fraction>]".into());
+    }
+
+    let mut configurations = vec![];
+    for folder in folders {
+        if find_result_files(&folder)?.is_empty() {
+            let mut subfolders = fs::read_dir(&folder)?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.is_dir())
+                .collect::<Vec<_>>();
+            subfolders.sort();
+            configurations.extend(subfolders);
+        } else {
+            configurations.push(folder);
+        }
+    }
+
+    for configuration in configurations {
+        for result_file in find_result_files(&configuration)? {
+            if let Err(e) = analyze_run(&result_file, &AnalysisOptions::default(), &options) {
+                eprintln!("Could not analyze reconnects of {}: {}", result_file.display(), e);
+            }
+        }
+        let summary = summarize_configuration(&configuration)?;
+        println!(
+            "{}: {} reconnects in {} runs, mean peak latency {:?}ms, mean recovery {:?}ms, {} lost tuples",
+            configuration.display(),
+            summary.reconnects,
+            summary.runs,
+            summary.mean_peak_latency_ms,
+            summary.mean_recovery_time_ms,
+            summary.lost_tuples
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_result_files_of_all_output_types() {
+        let folder = std::env::temp_dir().join(format!("reconnect_result_files_{}", std::process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        for name in ["out_run:1.csv", "out_run:2.parquet", "out_run:1.csvanalysis.csv", "out_run:2.parquetreconnects.csv", "error.csv"] {
+            fs::write(folder.join(name), "").unwrap();
+        }
+        let files = find_result_files(&folder).unwrap();
+        fs::remove_dir_all(&folder).unwrap();
+        assert_eq!(files, vec![folder.join("out_run:1.csv"), folder.join("out_run:2.parquet")]);
+    }
+
+    #[test]
+    fn test_reconnect_impact() {
+        let ms = 1_000_000;
+        let reconnect = 10_000 * ms;
+        //one tuple every 10ms with 5ms latency, rising to 50ms for the 300ms after the reconnect
+        let timings = (5_000..15_000)
+            .step_by(10)
+            .map(|time| {
+                let emission_time = time * ms;
+                let spike = (reconnect..reconnect + 300 * ms).contains(&emission_time);
+                TupleTiming {
+                    emission_time,
+                    latency: if spike { 50 * ms as i64 } else { 5 * ms as i64 },
+                }
+            })
+            .collect::<Vec<_>>();
+        let impacts = reconnect_impacts(&timings, &[reconnect], None, &ReconnectOptions::default());
+        assert_eq!(impacts.len(), 1);
+        let impact = &impacts[0];
+        assert_eq!(impact.pre_tuples, 200);
+        assert_eq!(impact.post_tuples, 200);
+        assert_eq!(impact.pre_latency_ms, Some(5.0));
+        assert_eq!(impact.peak_latency_ms, Some(50.0));
+        assert_eq!(impact.peak_offset_ms, Some(0));
+        assert_eq!(impact.recovery_time_ms, Some(300));
+        assert_eq!(impact.lost_tuples, None);
+
+        let run = RunReconnectAnalysis {
+            input_file: PathBuf::from("out_run:1.csv"),
+            options: ReconnectOptions::default(),
+            reconnects: impacts,
+        };
+        let summary = summarize_runs(Path::new("config"), &[run.clone(), run]);
+        assert_eq!(summary.reconnects, 2);
+        assert_eq!(summary.mean_recovery_time_ms, Some(300.0));
+        assert_eq!(summary.unrecovered_reconnects, 0);
+    }
+}
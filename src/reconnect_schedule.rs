@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::str::FromStr;
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::topology::{FixedTopology, SimulatedReconnects, TopologyUpdateList, WorkerIds};
+
+//random reconnect schedules for mobile devices without any notion of movement. every device jumps to another random
+//candidate after an interval drawn from the distribution, candidates are fixed nodes that are not excluded
+
+//shortest interval between two reconnects of a device, keeps the schedule moving when a sample rounds to zero
+const MIN_INTERVAL: Duration = Duration::from_millis(1);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IntervalDistribution {
+    FIXED(Duration),
+    UNIFORM(Duration, Duration),
+    EXPONENTIAL(Duration),
+}
+
+impl IntervalDistribution {
+    fn sample(&self, rng: &mut StdRng) -> Duration {
+        let interval = match self {
+            IntervalDistribution::FIXED(interval) => *interval,
+            IntervalDistribution::UNIFORM(min, max) => rng.gen_range(*min..=*max),
+            IntervalDistribution::EXPONENTIAL(mean) => mean.mul_f64(-(1.0 - rng.gen::<f64>()).ln()),
+        };
+        interval.max(MIN_INTERVAL)
+    }
+}
+
+//fixed:<ms>, uniform:<min ms>:<max ms> or exponential:<mean ms>
+impl FromStr for IntervalDistribution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s.split(':').collect::<Vec<_>>();
+        let millis = |value: &str| {
+            value
+                .parse::<u64>()
+                .map(Duration::from_millis)
+                .map_err(|e| format!("invalid interval {}: {}", value, e))
+        };
+        let distribution = match parts.as_slice() {
+            ["fixed", interval] => IntervalDistribution::FIXED(millis(interval)?),
+            ["uniform", min, max] => IntervalDistribution::UNIFORM(millis(min)?, millis(max)?),
+            ["exponential", mean] => IntervalDistribution::EXPONENTIAL(millis(mean)?),
+            _ => return Err(format!("unknown interval distribution: {}", s)),
+        };
+        match distribution {
+            IntervalDistribution::UNIFORM(min, max) if min > max => Err("uniform interval with min above max".to_string()),
+            IntervalDistribution::FIXED(interval) | IntervalDistribution::UNIFORM(interval, _) | IntervalDistribution::EXPONENTIAL(interval)
+                if interval.is_zero() =>
+            {
+                Err("intervals must be positive".to_string())
+            }
+            distribution => Ok(distribution),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReconnectScheduleConfig {
+    pub num_devices: usize,
+    pub duration: Duration,
+    pub interval: IntervalDistribution,
+    pub seed: u64,
+    //candidate nodes, the leaves of the fixed topology if not set
+    pub parents: Option<Vec<u64>>,
+    //nodes the devices never connect to
+    pub excluded_nodes: Vec<u64>,
+    //keep the devices away from the workers that host sources in the placement map
+    pub exclude_source_hosts: bool,
+    pub worker_ids: WorkerIds,
+}
+
+//candidate node ids after applying the exclusion rules. the placement map is keyed by worker id like
+//place_default_sources_on_node_ids_path of the runners
+pub fn candidate_parents(topology: &FixedTopology, placements: &HashMap<u64, Vec<u64>>, config: &ReconnectScheduleConfig) -> Vec<u64> {
+    let mut candidates = config.parents.clone().unwrap_or_else(|| topology.leaf_nodes());
+    candidates.retain(|node| {
+        let hosts_sources = placements.get(&config.worker_ids.fixed_worker_id(*node)).is_some_and(|sources| !sources.is_empty());
+        topology.nodes.contains_key(node) && !config.excluded_nodes.contains(node) && !(config.exclude_source_hosts && hosts_sources)
+    });
+    candidates
+}
+
+pub fn generate_schedule(
+    topology: &FixedTopology,
+    placements: &HashMap<u64, Vec<u64>>,
+    config: &ReconnectScheduleConfig,
+) -> Result<SimulatedReconnects, Box<dyn Error>> {
+    let candidates = candidate_parents(topology, placements, config);
+    if candidates.len() < 2 {
+        return Err(format!("devices need at least two candidate parents to reconnect, found {:?}", candidates).into());
+    }
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let worker_ids = config.worker_ids;
+    let first_mobile_worker_id = worker_ids.first_mobile_worker_id(topology);
+    let mut initial_parents = vec![];
+    let mut updates = TopologyUpdateList::new();
+    for device in 0..config.num_devices {
+        let child = first_mobile_worker_id + device as u64;
+        let mut parent = *candidates.choose(&mut rng).unwrap();
+        initial_parents.push((worker_ids.fixed_worker_id(parent), child));
+        let mut timestamp = config.interval.sample(&mut rng);
+        while timestamp < config.duration {
+            let new_parent = loop {
+                let candidate = *candidates.choose(&mut rng).unwrap();
+                if candidate != parent {
+                    break candidate;
+                }
+            };
+            updates.add_reconnect(timestamp, child, worker_ids.fixed_worker_id(parent), worker_ids.fixed_worker_id(new_parent));
+            parent = new_parent;
+            timestamp += config.interval.sample(&mut rng);
+        }
+    }
+    Ok(SimulatedReconnects {
+        initial_parents,
+        topology_updates: updates.into(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedule_respects_exclusions() {
+        //root 0 with the leaves 1 to 5, the leaf 2 hosts sources as worker 3
+        let topology = FixedTopology {
+            nodes: (0..6).map(|node| (node, vec![53.35, -6.3])).collect(),
+            slots: HashMap::new(),
+            children: HashMap::from([(0, vec![1, 2, 3, 4, 5])]),
+        };
+        let placements = HashMap::from([(3, vec![1, 2]), (5, vec![])]);
+        let config = ReconnectScheduleConfig {
+            num_devices: 3,
+            duration: Duration::from_secs(10),
+            interval: "fixed:1000".parse().unwrap(),
+            seed: 1,
+            parents: None,
+            excluded_nodes: vec![5],
+            exclude_source_hosts: true,
+            worker_ids: WorkerIds::STATEFUL,
+        };
+        assert_eq!(candidate_parents(&topology, &placements, &config), vec![1, 3, 4]);
+
+        let schedule = generate_schedule(&topology, &placements, &config).unwrap();
+        let allowed = [2, 4, 5];
+        assert_eq!(schedule.initial_parents.iter().map(|(_, child)| *child).collect::<Vec<_>>(), vec![7, 8, 9]);
+        assert!(schedule.initial_parents.iter().all(|(parent, _)| allowed.contains(parent)));
+        //every device reconnects once a second after the first second
+        assert_eq!(schedule.topology_updates.len(), 9);
+        for update in &schedule.topology_updates {
+            assert_eq!(update.events.len(), 6);
+            assert!(update.events.iter().all(|event| allowed.contains(&event.parent_id)));
+        }
+
+        assert!("uniform:5:1".parse::<IntervalDistribution>().is_err());
+        assert!("exponential:0".parse::<IntervalDistribution>().is_err());
+        assert!("uniform:0:5".parse::<IntervalDistribution>().is_err());
+        let mut rng = StdRng::seed_from_u64(1);
+        let exponential = IntervalDistribution::EXPONENTIAL(Duration::from_micros(1));
+        assert!((0..100).all(|_| exponential.sample(&mut rng) >= Duration::from_millis(1)));
+        assert_eq!("uniform:1:5".parse(), Ok(IntervalDistribution::UNIFORM(Duration::from_millis(1), Duration::from_millis(5))));
+    }
+}
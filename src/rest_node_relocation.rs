@@ -1,114 +1,11 @@
 
-use std::collections::{btree_map, BTreeMap};
 use std::error::Error;
 use std::ops::Add;
 use std::time;
-use chrono::Duration;
 use reqwest::{Url};
-use serde::{Deserialize, Serialize};
-
-use serde_with::serde_as;
-use serde_with::DurationMilliSeconds;
-
 
 use crate::lib_stateful::add_edges_from_list;
-
-#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
-pub enum ISQPEventAction {
-    add,
-    remove,
-}
-
-#[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct ISQPEvent {
-    #[serde(rename = "parentId")]
-    pub parent_id: u64,
-    #[serde(rename = "childId")]
-    pub child_id: u64,
-    pub action: ISQPEventAction,
-}
-
-#[serde_as]
-#[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct TopologyUpdate {
-    #[serde_as(as = "DurationMilliSeconds<u64>")]
-    pub timestamp: time::Duration,
-    #[serde(rename = "events")]
-    pub events: Vec<ISQPEvent>,
-}
-
-pub struct TopologyUpdateList {
-    pub events: BTreeMap<time::Duration, Vec<ISQPEvent>>,
-}
-
-impl TopologyUpdateList {
-    pub fn new() -> Self {
-        Self {
-            events: BTreeMap::new(),
-        }
-    }
-    pub fn add(&mut self, timestamp: time::Duration, event: ISQPEvent) {
-        match self.events.entry(timestamp) {
-            btree_map::Entry::Occupied(e) => {
-                e.into_mut().push(event);
-            }
-            btree_map::Entry::Vacant(e) => {
-                e.insert(vec![event]);
-            }
-        }
-    }
-
-    pub fn add_initial_event(&mut self, event: ISQPEvent) {
-        match self.events.entry(time::Duration::new(0, 0)) {
-            btree_map::Entry::Occupied(e) => {
-                e.into_mut().push(event);
-            }
-            btree_map::Entry::Vacant(e) => {
-                e.insert(vec![event]);
-            }
-        }
-    }
-
-    pub fn add_initial_connect(&mut self, child_id: u64, parent_id: u64) {
-        self.add_initial_event(ISQPEvent {
-            parent_id: parent_id,
-            child_id: child_id,
-            action: ISQPEventAction::add,
-        });
-    }
-
-    pub fn add_reconnect(&mut self, timestamp: time::Duration, child_id: u64, old_parent_id: u64, new_parent_id: u64) {
-        self.add(timestamp, ISQPEvent {
-            parent_id: old_parent_id,
-            child_id,
-            action: ISQPEventAction::remove,
-        });
-        self.add(timestamp, ISQPEvent {
-            parent_id: new_parent_id,
-            child_id,
-            action: ISQPEventAction::add,
-        });
-    }
-}
-
-impl From<TopologyUpdateList> for Vec<TopologyUpdate> {
-    fn from(list: TopologyUpdateList) -> Self {
-        let mut updates = vec![];
-        for (timestamp, events) in list.events {
-            updates.push(TopologyUpdate {
-                timestamp,
-                events,
-            });
-        }
-        updates
-    }
-}
-
-impl Default for TopologyUpdateList {
-    fn default() -> Self {
-        Self::new()
-    }
-}
+pub use simulation_runner_rs::topology::{ISQPEvent, ISQPEventAction, TopologyUpdate, TopologyUpdateList};
 
 pub struct REST_topology_updater {
     topology_updates: Vec<TopologyUpdate>,
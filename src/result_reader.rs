@@ -0,0 +1,207 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::path::Path;
+
+use avro_rs::types::Value;
+use avro_rs::{Reader, Schema};
+
+use crate::tuple_schema::{JoinOutputTuple, SinkTuple, StatelessOutputTuple};
+
+//accessors shared by the tuples of both workloads, used to filter result files
+pub trait ResultTuple: SinkTuple {
+    //time at which the input server created the tuple. for joins the later of both inputs
+    fn event_time(&self) -> u64;
+    fn emission_time(&self) -> u64;
+    fn source_ids(&self) -> Vec<u64>;
+}
+
+impl ResultTuple for StatelessOutputTuple {
+    fn event_time(&self) -> u64 {
+        self.event_time
+    }
+
+    fn emission_time(&self) -> u64 {
+        self.emission_time
+    }
+
+    fn source_ids(&self) -> Vec<u64> {
+        vec![self.id]
+    }
+}
+
+impl ResultTuple for JoinOutputTuple {
+    fn event_time(&self) -> u64 {
+        self.event_time_1.max(self.event_time_2)
+    }
+
+    fn emission_time(&self) -> u64 {
+        self.emission_time_1.max(self.emission_time_2)
+    }
+
+    fn source_ids(&self) -> Vec<u64> {
+        vec![self.id_1, self.id_2]
+    }
+}
+
+fn record_field_names(schema: &Schema) -> Option<Vec<String>> {
+    match schema {
+        Schema::Record { fields, .. } => Some(fields.iter().map(|field| field.name.clone()).collect()),
+        _ => None,
+    }
+}
+
+//streams typed tuples out of an avro file written by AvroOutputWriter. filters are applied while reading, so large
+//files do not have to be loaded completely
+pub struct AvroResultReader<R: Read, T: ResultTuple> {
+    reader: Reader<'static, R>,
+    event_time_range: Option<Range<u64>>,
+    emission_time_range: Option<Range<u64>>,
+    source_ids: Option<HashSet<u64>>,
+    tuple_type: PhantomData<T>,
+}
+
+impl<T: ResultTuple> AvroResultReader<BufReader<File>, T> {
+    pub fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read, T: ResultTuple> AvroResultReader<R, T> {
+    pub fn new(input: R) -> Result<Self, Box<dyn Error>> {
+        let reader = Reader::new(input)?;
+        let field_names = record_field_names(reader.writer_schema()).ok_or("result file does not contain records")?;
+        if !field_names.iter().map(String::as_str).eq(T::FIELD_NAMES.iter().copied()) {
+            return Err(format!("result file has fields {:?}, expected {:?}", field_names, T::FIELD_NAMES).into());
+        }
+        Ok(Self {
+            reader,
+            event_time_range: None,
+            emission_time_range: None,
+            source_ids: None,
+            tuple_type: PhantomData,
+        })
+    }
+
+    pub fn with_event_time_range(mut self, range: Range<u64>) -> Self {
+        self.event_time_range = Some(range);
+        self
+    }
+
+    pub fn with_emission_time_range(mut self, range: Range<u64>) -> Self {
+        self.emission_time_range = Some(range);
+        self
+    }
+
+    //only return tuples that contain at least one of the source ids
+    pub fn with_source_ids(mut self, source_ids: impl IntoIterator<Item = u64>) -> Self {
+        self.source_ids = Some(source_ids.into_iter().collect());
+        self
+    }
+
+    fn matches(&self, tuple: &T) -> bool {
+        self.event_time_range.as_ref().is_none_or(|range| range.contains(&tuple.event_time()))
+            && self.emission_time_range.as_ref().is_none_or(|range| range.contains(&tuple.emission_time()))
+            && self
+                .source_ids
+                .as_ref()
+                .is_none_or(|source_ids| tuple.source_ids().iter().any(|id| source_ids.contains(id)))
+    }
+}
+
+fn tuple_from_value<T: SinkTuple>(value: Value) -> Result<T, Box<dyn Error>> {
+    let Value::Record(fields) = value else {
+        return Err("expected avro records in result file".into());
+    };
+    let values = T::FIELD_NAMES
+        .iter()
+        .map(|name| match fields.iter().find(|(field, _)| field == name) {
+            Some((_, Value::Long(value))) => Ok(*value as u64),
+            Some((_, Value::Int(value))) => Ok(*value as u64),
+            Some((_, value)) => Err(format!("unsupported avro value for field {}: {:?}", name, value)),
+            None => Err(format!("field {} missing in record", name)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(T::from_values(&values))
+}
+
+impl<R: Read, T: ResultTuple> Iterator for AvroResultReader<R, T> {
+    type Item = Result<T, Box<dyn Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let tuple = match self.reader.next()? {
+                Ok(value) => tuple_from_value::<T>(value),
+                Err(e) => Err(e.into()),
+            };
+            match tuple {
+                Ok(tuple) if !self.matches(&tuple) => continue,
+                result => return Some(result),
+            }
+        }
+    }
+}
+
+pub enum ResultFile {
+    Stateless(AvroResultReader<BufReader<File>, StatelessOutputTuple>),
+    Join(AvroResultReader<BufReader<File>, JoinOutputTuple>),
+}
+
+//open a result file of either workload, the layout is taken from the schema stored in the file
+pub fn open_result_file(path: &Path) -> Result<ResultFile, Box<dyn Error>> {
+    let schema = Reader::new(BufReader::new(File::open(path)?))?.writer_schema().clone();
+    let field_names = record_field_names(&schema).ok_or("result file does not contain records")?;
+    if field_names.iter().map(String::as_str).eq(StatelessOutputTuple::FIELD_NAMES.iter().copied()) {
+        Ok(ResultFile::Stateless(AvroResultReader::open(path)?))
+    } else if field_names.iter().map(String::as_str).eq(JoinOutputTuple::FIELD_NAMES.iter().copied()) {
+        Ok(ResultFile::Join(AvroResultReader::open(path)?))
+    } else {
+        Err(format!("unknown result layout in {}: {:?}", path.display(), field_names).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use avro_rs::Writer;
+
+    fn tuple(id: u64, sequence_number: u64) -> StatelessOutputTuple {
+        StatelessOutputTuple {
+            id,
+            sequence_number,
+            event_time: sequence_number * 10,
+            processing_time: sequence_number * 10 + 1,
+            emission_time: sequence_number * 10 + 2,
+        }
+    }
+
+    #[test]
+    fn test_read_and_filter_avro_results() {
+        let schema = Schema::parse_str(&StatelessOutputTuple::avro_schema()).unwrap();
+        let mut writer = Writer::new(&schema, Vec::new());
+        let tuples = (1..=2).flat_map(|id| (0..10).map(move |sequence_number| tuple(id, sequence_number))).collect::<Vec<_>>();
+        for tuple in &tuples {
+            writer.append_ser(tuple).unwrap();
+        }
+        let encoded = writer.into_inner().unwrap();
+
+        let read = AvroResultReader::<_, StatelessOutputTuple>::new(encoded.as_slice())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(read, tuples);
+
+        let filtered = AvroResultReader::<_, StatelessOutputTuple>::new(encoded.as_slice())
+            .unwrap()
+            .with_event_time_range(20..50)
+            .with_source_ids([2])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(filtered, vec![tuple(2, 2), tuple(2, 3), tuple(2, 4)]);
+
+        assert!(AvroResultReader::<_, JoinOutputTuple>::new(encoded.as_slice()).is_err());
+    }
+}
@@ -1,3 +1,37 @@
-fn main() {
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use simulation_runner_rs::mobility_scenario::{validate_scenario, write_scenario};
+use simulation_runner_rs::topology::{FixedTopology, SimulatedReconnects, WorkerIds};
+
+fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let reconnects: SimulatedReconnects = serde_json::from_str(&fs::read_to_string(&args[1])?)?;
+    let placements: HashMap<u64, Vec<u64>> = serde_json::from_str(&fs::read_to_string(&args[2])?)?;
+    let topology: FixedTopology = serde_json::from_str(&fs::read_to_string(&args[3])?)?;
+    let worker_ids = if args.iter().any(|arg| arg == "--stateless") { WorkerIds::STATELESS } else { WorkerIds::STATEFUL };
+    validate_scenario(&topology, &reconnects, &placements, worker_ids)?;
 
-}
\ No newline at end of file
+    let input_config_path = write_scenario(Path::new(&args[4]), &topology, &reconnects, &placements, worker_ids)?;
+    println!(
+        "wrote scenario with {} mobile workers and {} topology updates",
+        reconnects.initial_parents.len(),
+        reconnects.topology_updates.len()
+    );
+    println!("adjust the parameters in {} and pass it to start_experiment", input_config_path.display());
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 5 {
+        eprintln!("Usage: {} <simulated reconnects json> <source placement json> <fixed topology json> <output dir> [--stateless]", args[0]);
+        std::process::exit(1);
+    }
+    if let Err(e) = run(&args) {
+        eprintln!("Error writing scenario: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,194 @@
+use std::io;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+//how long to wait for data before checking the shutdown flag and the experiment deadline again
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum ConnectionEnd {
+    //the sink closed the connection
+    Eof,
+    //the connection was reset or aborted by the peer
+    Reset,
+    Error,
+    Shutdown,
+    Timeout,
+    //the desired number of tuples was received
+    Complete,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConnectionStats {
+    pub peer: Option<String>,
+    pub bytes_received: usize,
+    pub complete_tuples: usize,
+    //bytes of an incomplete tuple that were still pending when the connection ended
+    pub truncated_bytes: usize,
+    //complete tuples that could not be decoded into output tuples
+    #[serde(default)]
+    pub decode_errors: usize,
+    pub end: ConnectionEnd,
+}
+
+//reads fixed size tuples from a sink connection, keeping partial tuples until the rest of their bytes arrive
+pub struct FramedTupleReader {
+    stream: TcpStream,
+    tuple_size: usize,
+    pending: Vec<u8>,
+    bytes_received: usize,
+    complete_tuples: usize,
+}
+
+impl FramedTupleReader {
+    pub fn new(stream: TcpStream, tuple_size: usize) -> Self {
+        Self {
+            stream,
+            tuple_size,
+            pending: Vec::with_capacity(READ_CHUNK_SIZE),
+            bytes_received: 0,
+            complete_tuples: 0,
+        }
+    }
+
+    //wait for data and append all tuples that are complete afterwards to the output buffer.
+    //returns the number of bytes read from the socket, 0 means the peer closed the connection
+    pub async fn read_tuples(&mut self, output: &mut Vec<u8>) -> io::Result<usize> {
+        self.pending.reserve(READ_CHUNK_SIZE);
+        let bytes_read = self.stream.read_buf(&mut self.pending).await?;
+        self.bytes_received += bytes_read;
+        let complete_bytes = self.pending.len() - self.pending.len() % self.tuple_size;
+        output.extend(self.pending.drain(..complete_bytes));
+        self.complete_tuples += complete_bytes / self.tuple_size;
+        Ok(bytes_read)
+    }
+
+    pub fn complete_tuples(&self) -> usize {
+        self.complete_tuples
+    }
+
+    pub fn stats(&self, end: ConnectionEnd) -> ConnectionStats {
+        ConnectionStats {
+            peer: self.stream.peer_addr().ok().map(|address| address.to_string()),
+            bytes_received: self.bytes_received,
+            complete_tuples: self.complete_tuples,
+            truncated_bytes: self.pending.len(),
+            decode_errors: 0,
+            end,
+        }
+    }
+}
+
+//receive tuples until the peer closes the connection, the desired number of tuples arrived, the experiment timed out
+//or shutdown was triggered. returns the bytes of all complete tuples
+pub async fn receive_tuples(
+    stream: TcpStream,
+    tuple_size: usize,
+    desired_tuple_count: Option<u64>,
+    shutdown_triggered: Arc<AtomicBool>,
+    start_time: SystemTime,
+    experiment_duration: Duration,
+) -> (Vec<u8>, ConnectionStats) {
+    let mut reader = FramedTupleReader::new(stream, tuple_size);
+    let mut received = vec![];
+    let end = loop {
+        if shutdown_triggered.load(SeqCst) {
+            println!("shutdown triggered, exiting tuple reader loop");
+            break ConnectionEnd::Shutdown;
+        }
+
+        if let Some(desired) = desired_tuple_count {
+            if reader.complete_tuples() >= desired as usize {
+                println!("All tuples received for thread, exiting tuple reader loop");
+                break ConnectionEnd::Complete;
+            }
+        }
+
+        if let Ok(elapsed_time) = SystemTime::now().duration_since(start_time) {
+            if elapsed_time > experiment_duration {
+                println!("Timeout reached, exiting tuple reader loop");
+                break ConnectionEnd::Timeout;
+            }
+        }
+
+        match timeout(POLL_INTERVAL, reader.read_tuples(&mut received)).await {
+            //no data arrived during the poll interval
+            Err(_) => {}
+            Ok(Ok(0)) => {
+                println!("Sink closed the connection, exiting tuple reader loop");
+                break ConnectionEnd::Eof;
+            }
+            Ok(Ok(_)) => {}
+            Ok(Err(e))
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::ConnectionReset
+                        | io::ErrorKind::ConnectionAborted
+                        | io::ErrorKind::BrokenPipe
+                ) =>
+            {
+                println!("Sink connection was reset: {}", e);
+                break ConnectionEnd::Reset;
+            }
+            Ok(Err(e)) => {
+                eprintln!("Error while reading from sink connection: {}", e);
+                break ConnectionEnd::Error;
+            }
+        }
+    };
+
+    let stats = reader.stats(end);
+    if stats.truncated_bytes > 0 {
+        println!(
+            "Connection ended with {} bytes of an incomplete tuple, dropping them",
+            stats.truncated_bytes
+        );
+    }
+    println!("Connection statistics: {:?}", stats);
+    (received, stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+
+    #[test]
+    fn test_partial_tuples_are_carried_across_reads() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            tokio::spawn(async move {
+                let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+                stream.write_all(&[1u8; 20]).await.unwrap();
+                tokio::time::sleep(Duration::from_millis(250)).await;
+                stream.write_all(&[2u8; 23]).await.unwrap();
+            });
+            let (stream, _) = listener.accept().await.unwrap();
+            let (received, stats) = receive_tuples(
+                stream,
+                8,
+                None,
+                Arc::new(AtomicBool::new(false)),
+                SystemTime::now(),
+                Duration::from_secs(10),
+            )
+            .await;
+            assert_eq!(received.len(), 40);
+            assert_eq!(&received[16..24], &[1, 1, 1, 1, 2, 2, 2, 2]);
+            assert_eq!(stats.bytes_received, 43);
+            assert_eq!(stats.complete_tuples, 5);
+            assert_eq!(stats.truncated_bytes, 3);
+            assert_eq!(stats.end, ConnectionEnd::Eof);
+        });
+    }
+}
@@ -0,0 +1,171 @@
+use std::io;
+use std::net::SocketAddr;
+
+use serde::{Deserialize, Serialize};
+use tokio::net::UdpSocket;
+
+use crate::emission_pipeline::MessageWriter;
+use crate::mqtt_broker::publish_packet;
+use crate::tuple_schema::FieldType;
+
+//the input server generates every buffer as fixed size binary tuples. the protocol of a source decides how they are
+//framed on the wire and which nes source type reads them, see get_physical_sources_for_node in the runner libs
+
+pub const DEFAULT_TUPLE_SEPARATOR: char = '\n';
+
+//largest payload of a udp datagram, a datagram only contains complete rows
+pub const MAX_DATAGRAM_SIZE: usize = 65_507;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum SourceProtocol {
+    //binary tuples on a tcp connection, read by the csv source of our nes fork from the port in its file path
+    #[default]
+    BINARY,
+    //csv rows ended by the tuple separator on a tcp connection, read by a tcp source
+    TCP,
+    //csv rows sent as datagrams to the port the udp source of the worker listens on
+    UDP,
+    //csv rows published by a minimal mqtt broker on the port of the source, the mqtt source of the worker subscribes
+    MQTT,
+}
+
+//turns buffers of binary tuples into the messages of a protocol
+#[derive(Debug, Clone)]
+pub struct TupleEncoding {
+    protocol: SourceProtocol,
+    field_types: Vec<FieldType>,
+    tuple_separator: char,
+    //topic the mqtt subscriber subscribed to
+    topic: String,
+}
+
+impl TupleEncoding {
+    pub fn new(protocol: SourceProtocol, field_types: Vec<FieldType>, tuple_separator: char, topic: &str) -> Self {
+        Self {
+            protocol,
+            field_types,
+            tuple_separator,
+            topic: topic.to_string(),
+        }
+    }
+
+    pub fn binary(field_types: Vec<FieldType>) -> Self {
+        Self::new(SourceProtocol::BINARY, field_types, DEFAULT_TUPLE_SEPARATOR, "")
+    }
+
+    pub fn tuple_size(&self) -> usize {
+        self.field_types.len() * std::mem::size_of::<u64>()
+    }
+
+    pub fn tuple_count(&self, tuples: &[u8]) -> u64 {
+        (tuples.len() / self.tuple_size()) as u64
+    }
+
+    //messages that carry the buffer, each one is written at once
+    pub fn encode(&self, tuples: Vec<u8>) -> Vec<Vec<u8>> {
+        match self.protocol {
+            SourceProtocol::BINARY => vec![tuples],
+            SourceProtocol::TCP => vec![self.rows(&tuples).concat()],
+            SourceProtocol::UDP => {
+                let mut datagrams: Vec<Vec<u8>> = vec![];
+                for row in self.rows(&tuples) {
+                    match datagrams.last_mut() {
+                        Some(datagram) if datagram.len() + row.len() <= MAX_DATAGRAM_SIZE => datagram.extend_from_slice(&row),
+                        _ => datagrams.push(row),
+                    }
+                }
+                datagrams
+            }
+            SourceProtocol::MQTT => vec![publish_packet(&self.topic, &self.rows(&tuples).concat())],
+        }
+    }
+
+    //one csv row per tuple, ended by the tuple separator
+    fn rows(&self, tuples: &[u8]) -> Vec<Vec<u8>> {
+        tuples
+            .chunks_exact(self.tuple_size())
+            .map(|tuple| {
+                let values = tuple
+                    .chunks_exact(8)
+                    .zip(&self.field_types)
+                    .map(|(value, field_type)| {
+                        let bytes = value.try_into().unwrap();
+                        match field_type {
+                            FieldType::UINT64 => u64::from_le_bytes(bytes).to_string(),
+                            FieldType::FLOAT64 => f64::from_le_bytes(bytes).to_string(),
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                format!("{}{}", values.join(","), self.tuple_separator).into_bytes()
+            })
+            .collect()
+    }
+}
+
+//sends every message as one datagram. without a connected socket the sender does not learn about datagrams nobody
+//received, they are lost like on a real network and show up as missing tuples in the completeness analysis
+pub struct DatagramWriter {
+    socket: UdpSocket,
+    target: SocketAddr,
+}
+
+impl DatagramWriter {
+    pub async fn bind(target: SocketAddr) -> io::Result<Self> {
+        let local: SocketAddr = if target.is_ipv4() { ([0, 0, 0, 0], 0).into() } else { ([0u16; 8], 0).into() };
+        Ok(Self {
+            socket: UdpSocket::bind(local).await?,
+            target,
+        })
+    }
+}
+
+impl MessageWriter for DatagramWriter {
+    async fn write_message(&mut self, message: &[u8]) -> io::Result<()> {
+        self.socket.send_to(message, self.target).await.map(|_| ())
+    }
+
+    async fn close(&mut self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tuples(values: &[u64]) -> Vec<u8> {
+        values.iter().flat_map(|value| value.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn test_encode_rows() {
+        let field_types = vec![FieldType::UINT64, FieldType::FLOAT64];
+        let buffer = [tuples(&[1]), 2.5f64.to_le_bytes().to_vec(), tuples(&[2]), 3f64.to_le_bytes().to_vec()].concat();
+
+        let binary = TupleEncoding::binary(field_types.clone());
+        assert_eq!(binary.tuple_count(&buffer), 2);
+        assert_eq!(binary.encode(buffer.clone()), vec![buffer.clone()]);
+
+        let tcp = TupleEncoding::new(SourceProtocol::TCP, field_types.clone(), '|', "");
+        assert_eq!(tcp.encode(buffer.clone()), vec![b"1,2.5|2,3|".to_vec()]);
+
+        //datagrams are filled with complete rows
+        let udp = TupleEncoding::new(SourceProtocol::UDP, vec![FieldType::UINT64], '\n', "");
+        let large_values = vec![u64::MAX; 10_000];
+        let datagrams = udp.encode(tuples(&large_values));
+        assert_eq!(datagrams.len(), 4);
+        assert!(datagrams.iter().all(|datagram| datagram.len() <= MAX_DATAGRAM_SIZE && datagram.ends_with(b"\n")));
+        assert_eq!(datagrams.concat(), format!("{}\n", u64::MAX).repeat(10_000).into_bytes());
+    }
+
+    #[test]
+    fn test_datagrams_reach_the_listening_source() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let receiver = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let mut writer = DatagramWriter::bind(receiver.local_addr().unwrap()).await.unwrap();
+            writer.write_message(b"1,2\n").await.unwrap();
+            let mut received = [0; 16];
+            let length = receiver.recv(&mut received).await.unwrap();
+            assert_eq!(&received[..length], b"1,2\n");
+        });
+    }
+}
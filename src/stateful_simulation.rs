@@ -1,9 +1,19 @@
 use avro_rs::{Schema, Writer};
+use simulation_runner_rs::dataset_replay::DATASET_REPLAY_FILE;
+use simulation_runner_rs::ingest_stats::INGEST_STATS_SUFFIX;
+use simulation_runner_rs::input_server::InputServer;
+use simulation_runner_rs::input_sources::{joined_tuple_counts, write_input_sources, INPUT_SOURCES_FILE};
+use simulation_runner_rs::rate_profile::RATE_PROFILE_FILE;
 use chrono::{DateTime, Local};
 use execute::{shell, Execute};
-use itertools::{assert_equal, Itertools};
+use itertools::assert_equal;
 use reqwest::Url;
-use crate::analyze::create_notebook;
+use crate::analyze::{analyze_output_file, create_notebook, AnalysisOptions};
+use crate::sink_reader::ConnectionStats;
+use crate::reconnect_analysis::{analyze_run, summarize_configuration, ReconnectOptions};
+use crate::plots::{plot_run, write_topology_frames};
+use crate::completeness::{CompletenessAnalyzer, GeneratorSpec, JoinSpec, DEFAULT_BUCKET_WIDTH};
+use crate::join_validation::{JoinRules, JoinValidator, DEFAULT_MAX_EXAMPLES};
 use crate::lib_stateful::*;
 use std::collections::HashMap;
 use std::error::Error;
@@ -27,36 +37,35 @@ use crate::{rest_node_relocation, LogLevel};
 
 fn main() -> Result<(), Box<dyn Error>> {
     let args: Vec<String> = env::args().collect();
-    if args.len() < 5 || args.len() > 8 {
-        eprintln!("Usage: {} <nes directory> <experiment input config path> <output directory> <tcp input server executable> <log level (optional)>, <experiment path for retrial (optional)>", args[0]);
+    if args.len() < 5 || args.len() > 7 {
+        eprintln!("Usage: {} <nes directory> <experiment input config path> <output directory> <number of runs> <log level (optional)>, <experiment path for retrial (optional)>", args[0]);
         std::process::exit(1);
     }
 
     let nes_root_dir = PathBuf::from(&args[1]);
     let input_config_path = PathBuf::from(&args[2]);
     let output_directory = PathBuf::from(&args[3]);
-    let input_server_path = PathBuf::from(&args[4]);
-    let runs: u64 = args[5].parse().unwrap();
-    let log_level: LogLevel = if args.len() >= 7 {
-        println!("Log level: {}", &args[6]);
-        serde_json::from_str::<LogLevel>(&format!("\"{}\"", &args[6])).unwrap_or_else(|e| {
+    let runs: u64 = args[4].parse().unwrap();
+    let log_level: LogLevel = if args.len() >= 6 {
+        println!("Log level: {}", &args[5]);
+        serde_json::from_str::<LogLevel>(&format!("\"{}\"", &args[5])).unwrap_or_else(|e| {
             eprintln!("Could not parse log level: {}", e);
             LogLevel::LOG_ERROR
         })
     } else {
         LogLevel::LOG_ERROR
     };
-    let run_for_retrial_path = if args.len() == 8 {
-        Some(PathBuf::from(&args[7]))
+    let run_for_retrial_path = if args.len() == 7 {
+        Some(PathBuf::from(&args[6]))
     } else {
         None
     };
 
-    run_stateful_simulation(nes_root_dir, input_config_path, output_directory, input_server_path, runs, log_level, run_for_retrial_path)?;
+    run_stateful_simulation(nes_root_dir, input_config_path, output_directory, runs, log_level, run_for_retrial_path)?;
     Ok(())
 }
 
-pub fn run_stateful_simulation(nes_root_dir: PathBuf, input_config_path: PathBuf, output_directory: PathBuf, input_server_path: PathBuf, runs: u64, log_level: LogLevel, run_for_retrial_path: Option<PathBuf>) -> Result<(), Box<dyn Error>> {
+pub fn run_stateful_simulation(nes_root_dir: PathBuf, input_config_path: PathBuf, output_directory: PathBuf, runs: u64, log_level: LogLevel, run_for_retrial_path: Option<PathBuf>) -> Result<(), Box<dyn Error>> {
     let relative_worker_path = PathBuf::from("nes-worker/nesWorker");
     let relative_coordinator_path = PathBuf::from("nes-coordinator/nesCoordinator");
     let simulation_config = SimulationConfig {
@@ -115,52 +124,32 @@ pub fn run_stateful_simulation(nes_root_dir: PathBuf, input_config_path: PathBuf
                 println!("Experiment started at {}, begin ingesting tuples at {}, start reconnects at {}", start_date_time, ingestion_start_date_time, reconnect_start_date_time);
                 let now: DateTime<Local> = Local::now();
                 println!("{}: Starting attempt {}", now, attempt);
+                //the files document the input of the attempt, the input server runs on the runtime of the runner
+                let input_server_config = experiment.input_server_config(
+                    ingestion_start
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .expect("Error while subtracting unix epock from ingestion start"),
+                )?;
+                input_server_config
+                    .rate_profile
+                    .write_to_file(&experiment.generated_folder.join(RATE_PROFILE_FILE))?;
+                if let Some(dataset_replay) = &input_server_config.dataset_replay {
+                    dataset_replay.write_to_file(&experiment.generated_folder.join(DATASET_REPLAY_FILE))?;
+                }
+                if let Some(input_sources) = &input_server_config.sources {
+                    write_input_sources(&experiment.generated_folder.join(INPUT_SOURCES_FILE), input_sources)?;
+                }
+                //replayed values do not follow the rules of the generator
+                let source_specs = match &input_server_config.dataset_replay {
+                    Some(_) => None,
+                    None => Some(input_server_config.source_specs()?),
+                };
+                let join_rules = source_specs.as_deref().map_or(JoinRules::default(), JoinRules::from_specs);
+                //the sources with the bursts of their rate profiles resolved, the completeness analysis replays them
+                let input_sources = input_server_config.sources.clone().unwrap_or_default();
                 //start source input server
                 println!("starting input server");
-                let mut source_input_server_process = Command::new(&input_server_path)
-                    .arg("127.0.0.1")
-                    .arg(
-                        experiment
-                            .input_config
-                            .parameters
-                            .source_input_server_port
-                            .to_string(),
-                    )
-                    .arg(experiment.num_buffers.to_string())
-                    .arg(
-                        experiment
-                            .input_config
-                            .default_source_input
-                            .tuples_per_buffer
-                            .to_string(),
-                    )
-                    .arg(
-                        experiment
-                            .input_config
-                            .default_source_input
-                            .gathering_interval
-                            .as_millis()
-                            .to_string(),
-                    )
-                    .arg(
-                        ingestion_start
-                            .duration_since(SystemTime::UNIX_EPOCH)
-                            .expect("Error while subtracting unix epock from ingestion start")
-                            .as_millis()
-                            .to_string(),
-                    )
-                    .arg(
-                        experiment
-                            .input_config
-                            .parameters
-                            .join_match_interval
-                            .to_string(),
-                    )
-                    .spawn()?;
-                println!(
-                    "input server process id {}",
-                    source_input_server_process.id()
-                );
+                let input_server = rt.block_on(InputServer::start(input_server_config))?;
 
                 let rest_port = 8081;
                 // create rest topology updater
@@ -190,26 +179,34 @@ pub fn run_stateful_simulation(nes_root_dir: PathBuf, input_config_path: PathBuf
 
                     let mut line_count = AtomicUsize::new(0); // Counter for the lines written
                     let line_count = Arc::new(line_count);
-                    // Open the CSV file for writing
 
+                    let output_type = experiment
+                        .input_config
+                        .parameters
+                        .output_type
+                        .unwrap_or(simulation_config.output_type);
+                    println!("writing output as {:?}", output_type);
+                    // Open the result file for writing, its extension names the output type
                     let file_path = format!(
-                        "{}_run:{}.csv",
+                        "{}_run:{}.{}",
                         &experiment.experiment_output_path.to_str().unwrap(),
-                        attempt
+                        attempt,
+                        output_type.extension()
                     );
                     let mut file = File::create(&file_path).unwrap();
+                    let mut file: Arc<Mutex<dyn OutputWriter + Send>> = match output_type {
+                        OutputType::CSV => Arc::new(Mutex::new(FileOutputWriter::new(file))),
+                        OutputType::AVRO => Arc::new(Mutex::new(AvroOutputWriter::new(file))),
+                        OutputType::PARQUET => Arc::new(Mutex::new(ParquetOutputWriter::new(file))),
+                    };
 
-                    let mut file = Arc::new(Mutex::new(AvroOutputWriter::new(file)));
-
+                    let connection_stats: Arc<Mutex<Vec<ConnectionStats>>> = Arc::new(Mutex::new(vec![]));
+                    let validator = Arc::new(Mutex::new(JoinValidator::new(DEFAULT_MAX_EXAMPLES, join_rules)));
                     let mut completed_threads = AtomicUsize::new(0);
                     let mut completed_threads = Arc::new(completed_threads);
-                    let query_string = experiment.input_config.parameters.query_string.clone();
 
                     let place_default_sources_on_node_ids = fs::read_to_string(
-                        &experiment
-                            .input_config
-                            .parameters
-                            .place_default_sources_on_node_ids_path,
+                        experiment.input_config.get_place_default_sources_on_node_ids_path(),
                     )
                     .expect("Failed to read place_default_sources_on_node_ids");
                     let place_default_sources_on_node_ids: HashMap<u64, Vec<u64>> =
@@ -225,63 +222,13 @@ pub fn run_stateful_simulation(nes_root_dir: PathBuf, input_config_path: PathBuf
                                 )
                             })
                             .collect();
-                    let mut query_strings = vec![];
-
-                    if JOIN_QUERY {
-                        let mut source_count_map = HashMap::<String, u64>::new();
-
-                        for v in place_default_sources_on_node_ids.values().flatten() {
-
-                            let source_count = source_count_map.entry(v.clone()).or_insert(0);
-                            *source_count += 1;
-                        };
-
-                        for (k, c) in source_count_map.iter() {
-                            assert_eq!(*c % 2, 0);
-                            let mut joins = String::from("{");
-                            for i in 0..*c / 2 {
-                                //replace input 1 and 2 in query string and add to join string
-                                let join_string = query_string
-                                .replace("{INPUT1}", format!("{}s{}", k, i * 2 + 1).as_str())
-                                .replace("{INPUT2}", format!("{}s{}", k, i * 2 + 2).as_str());
-                                joins.push_str(&join_string);
-                                if i < *c / 2 - 1 {
-                                    joins.push_str(", ");
-                                }
-                            }
-                            joins.push('}');
-
-                            let outer_query = "Query::sink2({SINK}, {JOINS});";
-                            //replace joins
-                            let outer_query = outer_query.replace("{JOINS}", &joins);
-                        
-                            let window_size = experiment.input_config.parameters.window_size;
-                            let input_replaced = outer_query.replace("{WINDOW_SIZE}", &window_size.to_string());
-                            let sink_string = format!("FileSinkDescriptor::create(\"{}:{{OUTPUT}}\", \"CSV_FORMAT\", \"true\")", k);
-                            let tcp_sink = input_replaced.replace("{SINK}", &sink_string);
-                            println!("--------------");
-                            println!("Query: {}", tcp_sink);
-                            println!("--------------");
-                            query_strings.push(tcp_sink);
-                        }
-                    } else {
-                        for id in place_default_sources_on_node_ids
-                            .values()
-                            .flatten()
-                            .unique()
-                        {
-                            let input_replaced = query_string.replace("{INPUT}", &id.to_string());
-                            let sink_string = format!("FileSinkDescriptor::create(\"{}:{{OUTPUT}}\", \"CSV_FORMAT\", \"true\")", id);
-                            let tcp_sink = input_replaced.replace("{SINK}", &sink_string);
-                            let null_sink = input_replaced
-                                .replace("{SINK}", "NullOutputSinkDescriptor::create()");
-                            query_strings.push(tcp_sink);
-                            for _i in 0..experiment.input_config.parameters.query_duplication_factor
-                            {
-                                query_strings.push(null_sink.clone());
-                            }
-                        }
-                    }
+                    let completeness = Arc::new(Mutex::new(CompletenessAnalyzer::new(GeneratorSpec::from_input_sources(
+                        &input_sources,
+                        source_specs.as_deref(),
+                        ingestion_start.duration_since(SystemTime::UNIX_EPOCH)?,
+                        Some(JoinSpec { window_size: experiment.input_config.parameters.window_size }),
+                    ))));
+                    let query_strings = experiment.input_config.get_query_strings(&place_default_sources_on_node_ids)?;
                     dbg!(&query_strings);
                     let desired_line_count_per_thread = experiment.total_number_of_tuples_to_emit / query_strings.len() as u64;
                     std::thread::sleep(Duration::from_secs(10));
@@ -313,9 +260,12 @@ pub fn run_stateful_simulation(nes_root_dir: PathBuf, input_config_path: PathBuf
                                         let mut timeout_duration_clone = timeout_duration.clone();
                                         let desired_line_count_copy = desired_line_count;
                                         let completed_threads_clone = completed_threads.clone();
+                                        let connection_stats_clone = connection_stats.clone();
+                                        let completeness_clone = completeness.clone();
+                                        let validator_clone = validator.clone();
                                         num_spawned += 1;
                                         tokio::spawn(async move {
-                                            if let Err(e) = handle_connection(
+                                            match handle_connection(
                                                 stream,
                                                 line_count_clone,
                                                 desired_line_count_per_thread,
@@ -324,11 +274,14 @@ pub fn run_stateful_simulation(nes_root_dir: PathBuf, input_config_path: PathBuf
                                                 shutdown_triggered_clone,
                                                 experiment_start_clone,
                                                 timeout_duration,
-                                                simulation_config.output_type,
+                                                output_type,
+                                                validator_clone,
+                                                completeness_clone,
                                             )
                                             .await
                                             {
-                                                eprintln!("Error handling connection: {}", e);
+                                                Ok(stats) => connection_stats_clone.lock().unwrap().push(stats),
+                                                Err(e) => eprintln!("Error handling connection: {}", e),
                                             }
                                             completed_threads_clone.fetch_add(1, Ordering::SeqCst);
                                         });
@@ -394,8 +347,9 @@ pub fn run_stateful_simulation(nes_root_dir: PathBuf, input_config_path: PathBuf
                             .write_all(error_string.as_bytes())
                             .expect("Error while writing error message to file");
                     }
+                    //stop the emission and collect what the input server actually sent before shutting it down
+                    let ingest_stats = input_server.stop();
                     experiment.kill_processes()?;
-                    source_input_server_process.kill()?;
                     let current_time = SystemTime::now();
                     println!(
                         "Finished attempt for experiment {} of {}. attempt: {} running for {:?}",
@@ -416,6 +370,34 @@ pub fn run_stateful_simulation(nes_root_dir: PathBuf, input_config_path: PathBuf
                     tuple_count_file
                         .write_all(tuple_count_string.as_bytes())
                         .expect("Error while writing tuple count to file");
+                    let connection_stats_path = file_path.clone().add("connections.json");
+                    fs::write(
+                        PathBuf::from(connection_stats_path),
+                        serde_json::to_string_pretty(&*connection_stats.lock().unwrap())?,
+                    )
+                    .expect("Error while writing connection statistics to file");
+                    println!("input server emitted {} tuples", ingest_stats.total_tuples_sent());
+                    let ingest_stats_path = file_path.clone().add(INGEST_STATS_SUFFIX);
+                    fs::write(
+                        PathBuf::from(ingest_stats_path),
+                        serde_json::to_string_pretty(&ingest_stats)?,
+                    )
+                    .expect("Error while writing ingest statistics to file");
+                    completeness
+                        .lock()
+                        .unwrap()
+                        .set_emitted_tuples(joined_tuple_counts(&experiment.input_sources, &ingest_stats.emitted_by_source()));
+                    let validation_report = validator.lock().unwrap().report();
+                    println!(
+                        "{} of {} join tuples passed validation",
+                        validation_report.valid_tuples, validation_report.checked_tuples
+                    );
+                    let validation_path = file_path.clone().add("validation.json");
+                    fs::write(
+                        PathBuf::from(validation_path),
+                        serde_json::to_string_pretty(&validation_report)?,
+                    )
+                    .expect("Error while writing validation report to file");
                     let mut actual_reconnect_calls = rest_topology_updater_thread.join().unwrap();
                     let reconnect_list_path = file_path.clone().add("reconnects.csv");
                     let mut reconnect_list_file =
@@ -430,14 +412,49 @@ pub fn run_stateful_simulation(nes_root_dir: PathBuf, input_config_path: PathBuf
                                 .as_bytes(),
                         )
                         .expect("Error while writing reconnect list to file");
+                    let completeness_report = completeness
+                        .lock()
+                        .unwrap()
+                        .report(&actual_reconnect_calls, DEFAULT_BUCKET_WIDTH);
+                    println!(
+                        "{} of {} expected tuples missing, {} duplicates",
+                        completeness_report.missing_tuples,
+                        completeness_report.expected_tuples,
+                        completeness_report.duplicate_tuples
+                    );
+                    let completeness_path = file_path.clone().add("completeness.json");
+                    fs::write(
+                        PathBuf::from(completeness_path),
+                        serde_json::to_string_pretty(&completeness_report)?,
+                    )
+                    .expect("Error while writing completeness report to file");
+                    if let Err(e) = analyze_output_file(&PathBuf::from(&file_path), &AnalysisOptions::default()) {
+                        eprintln!("Could not analyze output of attempt {}: {}", attempt, e);
+                    }
+                    if let Err(e) = plot_run(&PathBuf::from(&file_path), &AnalysisOptions::default()) {
+                        eprintln!("Could not plot output of attempt {}: {}", attempt, e);
+                    }
+                    if let Err(e) = write_topology_frames(
+                        &PathBuf::from(&file_path),
+                        &experiment.simulated_reconnects.initial_parents,
+                        &experiment.simulated_reconnects.topology_updates,
+                        &actual_reconnect_calls,
+                    ) {
+                        eprintln!("Could not write topology frames of attempt {}: {}", attempt, e);
+                    }
+                    if let Err(e) = analyze_run(&PathBuf::from(&file_path), &AnalysisOptions::default(), &ReconnectOptions::default()) {
+                        eprintln!("Could not analyze reconnects of attempt {}: {}", attempt, e);
+                    }
                     if let Some(notebook_path) = &simulation_config.get_analysis_script_path() {
-                        create_notebook(
+                        if let Err(e) = create_notebook(
                             &PathBuf::from(&file_path),
                             &notebook_path,
                             &experiment
                                 .generated_folder
                                 .join(format!("analysis_run{}.ipynb", attempt)),
-                        )?;
+                        ) {
+                            eprintln!("Could not create analysis notebook: {}", e);
+                        }
                     } else {
                         println!("No analysis script defined")
                     }
@@ -447,7 +464,7 @@ pub fn run_stateful_simulation(nes_root_dir: PathBuf, input_config_path: PathBuf
                 } else {
                     println!("Failed to add all mobile edges");
                 }
-                source_input_server_process.kill()?;
+                input_server.stop();
             } else {
                 println!("Experiment failed to start");
             }
@@ -460,6 +477,9 @@ pub fn run_stateful_simulation(nes_root_dir: PathBuf, input_config_path: PathBuf
             sleep(Duration::from_secs(wait_time));
         }
 
+        if let Err(e) = summarize_configuration(&experiment.generated_folder) {
+            eprintln!("Could not summarize reconnects of {}: {}", experiment.generated_folder.display(), e);
+        }
         experiment.kill_processes()?;
         if (shutdown_triggered.load(Ordering::SeqCst)) {
             break;
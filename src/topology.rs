@@ -0,0 +1,224 @@
+use std::collections::{btree_map, BTreeMap, HashMap};
+use std::error::Error;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+use std::time;
+
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use serde_with::{DurationMilliSeconds, DurationNanoSeconds};
+
+//topology input shared by the runners and the tools generating it. node ids are the ids in the fixed topology file,
+//the events and initial parents use the worker ids the runners assign, see WorkerIds
+
+#[allow(non_camel_case_types)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub enum ISQPEventAction {
+    add,
+    remove,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ISQPEvent {
+    #[serde(rename = "parentId")]
+    pub parent_id: u64,
+    #[serde(rename = "childId")]
+    pub child_id: u64,
+    pub action: ISQPEventAction,
+}
+
+#[serde_as]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TopologyUpdate {
+    #[serde_as(as = "DurationMilliSeconds<u64>")]
+    pub timestamp: time::Duration,
+    #[serde(rename = "events")]
+    pub events: Vec<ISQPEvent>,
+}
+
+pub struct TopologyUpdateList {
+    pub events: BTreeMap<time::Duration, Vec<ISQPEvent>>,
+}
+
+impl TopologyUpdateList {
+    pub fn new() -> Self {
+        Self {
+            events: BTreeMap::new(),
+        }
+    }
+    pub fn add(&mut self, timestamp: time::Duration, event: ISQPEvent) {
+        match self.events.entry(timestamp) {
+            btree_map::Entry::Occupied(e) => {
+                e.into_mut().push(event);
+            }
+            btree_map::Entry::Vacant(e) => {
+                e.insert(vec![event]);
+            }
+        }
+    }
+
+    pub fn add_initial_event(&mut self, event: ISQPEvent) {
+        match self.events.entry(time::Duration::new(0, 0)) {
+            btree_map::Entry::Occupied(e) => {
+                e.into_mut().push(event);
+            }
+            btree_map::Entry::Vacant(e) => {
+                e.insert(vec![event]);
+            }
+        }
+    }
+
+    pub fn add_initial_connect(&mut self, child_id: u64, parent_id: u64) {
+        self.add_initial_event(ISQPEvent {
+            parent_id,
+            child_id,
+            action: ISQPEventAction::add,
+        });
+    }
+
+    pub fn add_reconnect(&mut self, timestamp: time::Duration, child_id: u64, old_parent_id: u64, new_parent_id: u64) {
+        self.add(timestamp, ISQPEvent {
+            parent_id: old_parent_id,
+            child_id,
+            action: ISQPEventAction::remove,
+        });
+        self.add(timestamp, ISQPEvent {
+            parent_id: new_parent_id,
+            child_id,
+            action: ISQPEventAction::add,
+        });
+    }
+}
+
+impl From<TopologyUpdateList> for Vec<TopologyUpdate> {
+    fn from(list: TopologyUpdateList) -> Self {
+        let mut updates = vec![];
+        for (timestamp, events) in list.events {
+            updates.push(TopologyUpdate {
+                timestamp,
+                events,
+            });
+        }
+        updates
+    }
+}
+
+impl Default for TopologyUpdateList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//reconnects of the mobile workers of an experiment. initial parents are (parent, child) pairs of worker ids, the n-th
+//pair belongs to the n-th mobile worker
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SimulatedReconnects {
+    pub initial_parents: Vec<(u64, u64)>,
+    pub topology_updates: Vec<TopologyUpdate>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FixedTopology {
+    //todo: check if we can just make that a tuple
+    pub nodes: HashMap<u64, Vec<f64>>,
+    #[serde(default)]
+    pub slots: HashMap<u64, u16>,
+    pub children: HashMap<u64, Vec<u64>>,
+}
+
+impl FixedTopology {
+    //nodes without children, the mobile workers connect to these
+    pub fn leaf_nodes(&self) -> Vec<u64> {
+        let mut leaves = self
+            .nodes
+            .keys()
+            .filter(|node| self.children.get(node).is_none_or(|children| children.is_empty()))
+            .copied()
+            .collect::<Vec<_>>();
+        leaves.sort();
+        leaves
+    }
+
+    //latitude and longitude of a node
+    pub fn location(&self, node_id: u64) -> Option<(f64, f64)> {
+        self.nodes.get(&node_id).map(|location| (location[0], location[1]))
+    }
+}
+
+//the stateful runner starts the worker of node n with the id n + 1, the stateless runner with the id n. in both the
+//mobile workers get consecutive ids after the last fixed worker
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum WorkerIds {
+    #[default]
+    STATEFUL,
+    STATELESS,
+}
+
+impl WorkerIds {
+    pub fn fixed_worker_id(&self, node_id: u64) -> u64 {
+        match self {
+            WorkerIds::STATEFUL => node_id + 1,
+            WorkerIds::STATELESS => node_id,
+        }
+    }
+
+    pub fn first_mobile_worker_id(&self, topology: &FixedTopology) -> u64 {
+        self.fixed_worker_id(topology.nodes.keys().max().copied().unwrap_or(0)) + 1
+    }
+}
+
+//adds the logical sources placed on every mobile worker to the placements, which map worker ids to logical sources like
+//place_default_sources_on_node_ids_path of the runners
+pub fn add_device_sources(placements: &mut HashMap<u64, Vec<u64>>, device_ids: Range<u64>, device_sources: &[u64]) {
+    if device_sources.is_empty() {
+        return;
+    }
+    for device_id in device_ids {
+        placements.entry(device_id).or_default().extend(device_sources);
+    }
+}
+
+//reads the placements, adds the device sources and writes the merged placements to the output path
+pub fn write_device_placements(
+    placements_path: &Path,
+    output_path: &Path,
+    device_ids: Range<u64>,
+    device_sources: &[u64],
+) -> Result<(), Box<dyn Error>> {
+    let mut placements: HashMap<u64, Vec<u64>> = serde_json::from_str(&fs::read_to_string(placements_path)?)?;
+    add_device_sources(&mut placements, device_ids, device_sources);
+    fs::write(output_path, serde_json::to_string_pretty(&placements)?)?;
+    Ok(())
+}
+
+//a row of a gps trace of a mobile worker
+#[serde_as]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct MobileWorkerWaypoint {
+    #[serde(rename = "column1")]
+    pub latitude: f64,
+    #[serde(rename = "column2")]
+    pub longitude: f64,
+    #[serde_as(as = "DurationNanoSeconds<u64>")]
+    #[serde(rename = "column3")]
+    pub offset: time::Duration,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_device_sources() {
+        let mut placements = HashMap::from([(4, vec![2]), (13, vec![3])]);
+        add_device_sources(&mut placements, 10..14, &[1]);
+        assert_eq!(placements.len(), 5);
+        assert_eq!(placements[&4], vec![2]);
+        assert_eq!(placements[&10], vec![1]);
+        assert_eq!(placements[&13], vec![3, 1]);
+
+        add_device_sources(&mut placements, 20..22, &[]);
+        assert_eq!(placements.len(), 5);
+    }
+}
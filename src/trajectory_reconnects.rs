@@ -0,0 +1,196 @@
+use std::error::Error;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::topology::{FixedTopology, MobileWorkerWaypoint, SimulatedReconnects, TopologyUpdateList, WorkerIds};
+
+//derives the reconnects of the mobile workers from gps traces. every trace is sampled at a fixed interval and the
+//worker connects to the nearest eligible fixed node. the radius of a node limits the area it covers and the
+//hysteresis keeps a worker from flapping between two nodes at about the same distance
+
+const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+
+#[derive(Debug, Clone)]
+pub struct TrajectoryReconnectConfig {
+    //a worker only connects to nodes closer than this
+    pub coverage_radius: f64,
+    //a worker that is still covered by its parent only switches to a node that is this much closer
+    pub hysteresis: f64,
+    pub sample_interval: Duration,
+    //end of the generated schedule relative to the start of the traces, the end of the longest trace if not set
+    pub duration: Option<Duration>,
+    //fixed nodes the workers can connect to, the leaves of the fixed topology if not set
+    pub eligible_parents: Option<Vec<u64>>,
+    //runner the reconnects are generated for
+    pub worker_ids: WorkerIds,
+}
+
+impl Default for TrajectoryReconnectConfig {
+    fn default() -> Self {
+        Self {
+            coverage_radius: 2000.0,
+            hysteresis: 200.0,
+            sample_interval: Duration::from_secs(1),
+            duration: None,
+            eligible_parents: None,
+            worker_ids: WorkerIds::default(),
+        }
+    }
+}
+
+//waypoint csv without header as written for the csv location provider of nes: latitude, longitude, offset in ns
+pub fn read_trajectory(path: &Path) -> Result<Vec<MobileWorkerWaypoint>, Box<dyn Error>> {
+    let mut reader = csv::ReaderBuilder::new().has_headers(false).from_path(path)?;
+    let mut waypoints = vec![];
+    for waypoint in reader.deserialize() {
+        let waypoint: MobileWorkerWaypoint = waypoint?;
+        waypoints.push(waypoint);
+    }
+    if waypoints.is_empty() {
+        return Err(format!("trajectory {} contains no waypoints", path.display()).into());
+    }
+    waypoints.sort_by_key(|waypoint| waypoint.offset);
+    Ok(waypoints)
+}
+
+pub fn haversine_distance(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (from_latitude, to_latitude) = (from.0.to_radians(), to.0.to_radians());
+    let latitude_delta = to_latitude - from_latitude;
+    let longitude_delta = (to.1 - from.1).to_radians();
+    let a = (latitude_delta / 2.0).sin().powi(2)
+        + from_latitude.cos() * to_latitude.cos() * (longitude_delta / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_METERS * a.sqrt().asin()
+}
+
+//position at the offset, interpolated linearly between the surrounding waypoints. before the first and after the
+//last waypoint the worker stays where the trace starts or ends
+pub fn position_at(trajectory: &[MobileWorkerWaypoint], offset: Duration) -> (f64, f64) {
+    let next = trajectory.partition_point(|waypoint| waypoint.offset <= offset);
+    if next == 0 {
+        return (trajectory[0].latitude, trajectory[0].longitude);
+    }
+    let previous = &trajectory[next - 1];
+    let Some(next) = trajectory.get(next) else {
+        return (previous.latitude, previous.longitude);
+    };
+    let fraction = (offset - previous.offset).as_secs_f64() / (next.offset - previous.offset).as_secs_f64();
+    (
+        previous.latitude + (next.latitude - previous.latitude) * fraction,
+        previous.longitude + (next.longitude - previous.longitude) * fraction,
+    )
+}
+
+//the n-th trajectory belongs to the n-th mobile worker, all traces share the time of their earliest waypoint as start
+pub fn generate_reconnects(
+    topology: &FixedTopology,
+    trajectories: &[Vec<MobileWorkerWaypoint>],
+    config: &TrajectoryReconnectConfig,
+) -> Result<SimulatedReconnects, Box<dyn Error>> {
+    if config.sample_interval.is_zero() {
+        return Err("sample interval must be positive".into());
+    }
+    let parents = config.eligible_parents.clone().unwrap_or_else(|| topology.leaf_nodes());
+    let parent_locations = parents
+        .iter()
+        .map(|node| topology.location(*node).map(|location| (*node, location)).ok_or(format!("no location for node {}", node)))
+        .collect::<Result<Vec<_>, _>>()?;
+    if parent_locations.is_empty() {
+        return Err("no eligible parents".into());
+    }
+
+    let start = trajectories
+        .iter()
+        .filter_map(|trajectory| trajectory.first())
+        .map(|waypoint| waypoint.offset)
+        .min()
+        .ok_or("no trajectories")?;
+    let end = trajectories.iter().filter_map(|trajectory| trajectory.last()).map(|waypoint| waypoint.offset).max().unwrap();
+    let duration = config.duration.unwrap_or(end - start);
+
+    let mut initial_parents = vec![];
+    let mut updates = TopologyUpdateList::new();
+    let worker_ids = config.worker_ids;
+    let first_mobile_worker_id = worker_ids.first_mobile_worker_id(topology);
+    for (index, trajectory) in trajectories.iter().enumerate() {
+        let child = first_mobile_worker_id + index as u64;
+        let nearest = |offset: Duration| {
+            let position = position_at(trajectory, start + offset);
+            parent_locations
+                .iter()
+                .map(|(node, location)| (*node, haversine_distance(position, *location)))
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .unwrap()
+        };
+
+        //a worker outside of every coverage area still needs a parent to start with
+        let mut parent = nearest(Duration::ZERO).0;
+        initial_parents.push((worker_ids.fixed_worker_id(parent), child));
+        let mut offset = config.sample_interval;
+        while offset < duration {
+            let position = position_at(trajectory, start + offset);
+            let parent_distance = haversine_distance(position, topology.location(parent).unwrap());
+            let (candidate, candidate_distance) = nearest(offset);
+            let covered = parent_distance <= config.coverage_radius;
+            if candidate != parent
+                && candidate_distance <= config.coverage_radius
+                && (!covered || parent_distance - candidate_distance > config.hysteresis)
+            {
+                updates.add_reconnect(offset, child, worker_ids.fixed_worker_id(parent), worker_ids.fixed_worker_id(candidate));
+                parent = candidate;
+            }
+            offset += config.sample_interval;
+        }
+    }
+    Ok(SimulatedReconnects {
+        initial_parents,
+        topology_updates: updates.into(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::topology::ISQPEventAction;
+
+    fn waypoint(latitude: f64, longitude: f64, offset_secs: u64) -> MobileWorkerWaypoint {
+        MobileWorkerWaypoint {
+            latitude,
+            longitude,
+            offset: Duration::from_secs(1_000 + offset_secs),
+        }
+    }
+
+    #[test]
+    fn test_reconnect_to_nearest_covering_node() {
+        //root 0 with the leaves 1 and 2, about 5.6 km apart on the same latitude
+        let topology = FixedTopology {
+            nodes: HashMap::from([(0, vec![53.35, -6.30]), (1, vec![53.35, -6.26]), (2, vec![53.35, -6.18])]),
+            slots: HashMap::new(),
+            children: HashMap::from([(0, vec![1, 2])]),
+        };
+        //the bus drives from leaf 1 to leaf 2 within 100 seconds
+        let trajectory = vec![waypoint(53.35, -6.26, 0), waypoint(53.35, -6.18, 100)];
+        assert_eq!(position_at(&trajectory, Duration::from_secs(1_050)), (53.35, -6.22));
+
+        let config = TrajectoryReconnectConfig {
+            coverage_radius: 3000.0,
+            hysteresis: 1000.0,
+            sample_interval: Duration::from_secs(10),
+            ..Default::default()
+        };
+        let reconnects = generate_reconnects(&topology, std::slice::from_ref(&trajectory), &config).unwrap();
+        assert_eq!(reconnects.initial_parents, vec![(2, 4)]);
+        //halfway both leaves are equally far away, the switch waits until leaf 2 is 1 km closer
+        assert_eq!(reconnects.topology_updates.len(), 1);
+        let update = &reconnects.topology_updates[0];
+        assert_eq!(update.timestamp, Duration::from_secs(60));
+        let events = update.events.iter().map(|event| (event.parent_id, event.child_id, event.action.clone())).collect::<Vec<_>>();
+        assert_eq!(events, vec![(2, 4, ISQPEventAction::remove), (3, 4, ISQPEventAction::add)]);
+
+        //the stateless runner uses the node ids as worker ids
+        let config = TrajectoryReconnectConfig { worker_ids: WorkerIds::STATELESS, ..config };
+        let reconnects = generate_reconnects(&topology, &[trajectory], &config).unwrap();
+        assert_eq!(reconnects.initial_parents, vec![(1, 3)]);
+    }
+}
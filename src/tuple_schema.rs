@@ -0,0 +1,181 @@
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+//single definition of the tuple layouts used by the experiments. the binary sink decoder, the avro, parquet and csv
+//writers and the logical sources registered at the coordinator are all derived from the definitions in this file
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum FieldType {
+    FLOAT64,
+    UINT64,
+}
+
+pub struct SourceField {
+    pub name: &'static str,
+    pub field_type: FieldType,
+}
+
+//a tuple received from a sink. all sink fields are little endian u64 values in declaration order
+pub trait SinkTuple: Sized {
+    const FIELD_NAMES: &'static [&'static str];
+
+    fn from_values(values: &[u64]) -> Self;
+
+    fn to_values(&self) -> Vec<u64>;
+
+    fn tuple_size() -> usize {
+        Self::FIELD_NAMES.len() * std::mem::size_of::<u64>()
+    }
+
+    //none if the bytes are too short to hold a tuple
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < Self::tuple_size() {
+            return None;
+        }
+        let values = bytes
+            .chunks_exact(std::mem::size_of::<u64>())
+            .take(Self::FIELD_NAMES.len())
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+            .collect_vec();
+        Some(Self::from_values(&values))
+    }
+
+    fn avro_schema() -> String {
+        let fields = Self::FIELD_NAMES
+            .iter()
+            .map(|name| serde_json::json!({"name": name, "type": "long"}))
+            .collect_vec();
+        serde_json::json!({
+            "type": "record",
+            "name": "experiment_output",
+            "fields": fields,
+        })
+        .to_string()
+    }
+
+    fn parquet_message_type() -> String {
+        let fields = Self::FIELD_NAMES
+            .iter()
+            .map(|name| format!("required int64 {} (INTEGER(64,false));", name))
+            .join(" ");
+        format!("message experiment_output {{ {} }}", fields)
+    }
+
+    fn csv_header() -> String {
+        Self::FIELD_NAMES.join(",")
+    }
+
+    fn to_csv_row(&self) -> String {
+        self.to_values().iter().join(",")
+    }
+}
+
+//the last fields of every source are the timestamps the worker adds to the tuples it ingests, the input servers only
+//write the fields before them
+pub const WORKER_TIMESTAMP_FIELD_COUNT: usize = 2;
+
+//fields an input server writes for a logical source with the given layout
+pub fn generated_source_fields(source_fields: &[SourceField]) -> &[SourceField] {
+    &source_fields[..source_fields.len().saturating_sub(WORKER_TIMESTAMP_FIELD_COUNT)]
+}
+
+macro_rules! sink_tuple {
+    ($name:ident { $($field:ident),+ $(,)? }) => {
+        #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+        pub struct $name {
+            $(pub $field: u64),+
+        }
+
+        impl SinkTuple for $name {
+            const FIELD_NAMES: &'static [&'static str] = &[$(stringify!($field)),+];
+
+            fn from_values(values: &[u64]) -> Self {
+                let mut values = values.iter().copied();
+                Self {
+                    $($field: values.next().expect("not enough values to create tuple")),+
+                }
+            }
+
+            fn to_values(&self) -> Vec<u64> {
+                vec![$(self.$field),+]
+            }
+        }
+    };
+}
+
+//declares the fields of a logical source together with the sink tuple carrying them, so a field is added in one
+//place. every line names the sink field, the source field and its type
+macro_rules! source_schema {
+    ($fields:ident, $name:ident { $($field:ident: $source_name:literal $field_type:ident),+ $(,)? }) => {
+        pub const $fields: &[SourceField] = &[$(SourceField { name: $source_name, field_type: FieldType::$field_type }),+];
+
+        sink_tuple!($name { $($field),+ });
+    };
+}
+
+//like source_schema for a join of two sources. the sink tuple holds the window bounds followed by the fields of the
+//first and then of the second source, every line names the sink field of both sides
+macro_rules! join_source_schema {
+    ($fields:ident, $name:ident { $($left:ident, $right:ident: $source_name:literal $field_type:ident),+ $(,)? }) => {
+        pub const $fields: &[SourceField] = &[$(SourceField { name: $source_name, field_type: FieldType::$field_type }),+];
+
+        sink_tuple!($name { win_start, win_end, $($left),+, $($right),+ });
+    };
+}
+
+//stateless workload: one output tuple per source tuple
+source_schema!(STATELESS_SOURCE_FIELDS, StatelessOutputTuple {
+    id: "id" UINT64,
+    sequence_number: "value" UINT64,
+    event_time: "ingestion_timestamp" UINT64,
+    processing_time: "processing_timestamp" UINT64,
+    emission_time: "output_timestamp" UINT64,
+});
+
+//join workload: the window bounds followed by the fields of both joined source tuples
+pub const JOIN_WINDOW_FIELD_COUNT: usize = 2;
+
+join_source_schema!(JOIN_SOURCE_FIELDS, JoinOutputTuple {
+    id_1, id_2: "id" UINT64,
+    join_id_1, join_id_2: "join_id" UINT64,
+    sequence_number_1, sequence_number_2: "value" UINT64,
+    event_time_1, event_time_2: "event_timestamp" UINT64,
+    processing_time_1, processing_time_2: "processing_timestamp" UINT64,
+    emission_time_1, emission_time_2: "output_timestamp" UINT64,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sink_layout_matches_source_fields() {
+        assert_eq!(StatelessOutputTuple::FIELD_NAMES.len(), STATELESS_SOURCE_FIELDS.len());
+        assert_eq!(
+            JoinOutputTuple::FIELD_NAMES.len(),
+            JOIN_WINDOW_FIELD_COUNT + 2 * JOIN_SOURCE_FIELDS.len()
+        );
+    }
+
+    #[test]
+    fn test_decode_round_trip() {
+        let values = (0..JoinOutputTuple::FIELD_NAMES.len() as u64).collect_vec();
+        let bytes = values.iter().flat_map(|v| v.to_le_bytes()).collect_vec();
+        let tuple = JoinOutputTuple::from_bytes(&bytes).unwrap();
+        assert!(JoinOutputTuple::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+        assert_eq!(tuple.win_start, 0);
+        assert_eq!(tuple.emission_time_2, 13);
+        assert_eq!(tuple.to_values(), values);
+        assert_eq!(JoinOutputTuple::tuple_size(), 112);
+    }
+
+    #[test]
+    fn test_derived_schemas() {
+        avro_rs::Schema::parse_str(&StatelessOutputTuple::avro_schema()).unwrap();
+        parquet::schema::parser::parse_message_type(&JoinOutputTuple::parquet_message_type()).unwrap();
+        assert_eq!(
+            StatelessOutputTuple::csv_header(),
+            "id,sequence_number,event_time,processing_time,emission_time"
+        );
+    }
+}